@@ -0,0 +1,221 @@
+//! Fixed-point fee allocation for marketplace sales.
+//!
+//! Every cut of a sale is allocated from the one price in a single step, so
+//! the cuts and the residual (the seller's proceeds) always sum exactly to
+//! the price. The remainder policy is:
+//!
+//! 1. The combined fee is rounded down once from the sum of the rates, so
+//!    rounding costs the payer of fees at most one lamport overall and never
+//!    takes from the residual.
+//! 2. That combined fee is apportioned between the cuts by largest
+//!    remainder: each cut gets its exact share rounded down, and the
+//!    lamports left over go one each to the cuts with the largest dropped
+//!    fractions, ties going to the earlier cut.
+//!
+//! The crate depends only on `core`.
+#![cfg_attr(not(test), no_std)]
+
+/// Basis points in a whole.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// A fixed-point rate in parts per billion of an amount. A basis point is
+/// 100,000 parts, so quarter basis points are exact.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Rate(u64);
+
+impl Rate {
+    /// Parts in a whole.
+    pub const SCALE: u64 = 1_000_000_000;
+    pub const ZERO: Rate = Rate(0);
+    pub const ONE: Rate = Rate(Rate::SCALE);
+
+    pub const fn from_parts(parts: u64) -> Rate {
+        Rate(parts)
+    }
+
+    pub const fn from_bps(bps: u16) -> Rate {
+        Rate(bps as u64 * (Rate::SCALE / BPS_DENOMINATOR))
+    }
+
+    pub const fn parts(self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Rate) -> Option<Rate> {
+        self.0.checked_add(other.0).map(Rate)
+    }
+
+    pub fn checked_sub(self, other: Rate) -> Option<Rate> {
+        self.0.checked_sub(other.0).map(Rate)
+    }
+
+    /// `self * numerator / denominator`, rounded down.
+    pub fn checked_mul_div(self, numerator: u64, denominator: u64) -> Option<Rate> {
+        if denominator == 0 {
+            return None;
+        }
+        let parts = self.0 as u128 * numerator as u128 / denominator as u128;
+        u64::try_from(parts).ok().map(Rate)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeError {
+    /// The rates add up to more than the whole amount.
+    RatesExceedWhole,
+}
+
+/// An amount divided into `N` cuts and the residual left after them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Allocation<const N: usize> {
+    pub cuts: [u64; N],
+    pub residual: u64,
+}
+
+/// Allocates `amount` between cuts at `rates`, following the crate's
+/// remainder policy; whatever the cuts do not take is the residual.
+pub fn allocate<const N: usize>(amount: u64, rates: [Rate; N]) -> Result<Allocation<N>, FeeError> {
+    let mut total_rate = Rate::ZERO;
+    for rate in rates {
+        total_rate = total_rate
+            .checked_add(rate)
+            .filter(|total| *total <= Rate::ONE)
+            .ok_or(FeeError::RatesExceedWhole)?;
+    }
+    let scale = Rate::SCALE as u128;
+    // Both products are at most `amount * SCALE`, which fits in a u128, and
+    // the quotients are at most `amount`.
+    let total_fee = (amount as u128 * total_rate.0 as u128 / scale) as u64;
+
+    let mut cuts = [0u64; N];
+    let mut fractions = [0u64; N];
+    let mut allocated = 0u64;
+    for (index, rate) in rates.iter().enumerate() {
+        let exact = amount as u128 * rate.0 as u128;
+        cuts[index] = (exact / scale) as u64;
+        fractions[index] = (exact % scale) as u64;
+        allocated += cuts[index];
+    }
+
+    // The dropped fractions add up to less than `N` lamports, and the
+    // combined fee rounds down from their sum, so at most one lamport goes
+    // to each cut.
+    let mut rewarded = [false; N];
+    for _ in allocated..total_fee {
+        let mut best: Option<usize> = None;
+        for index in 0..N {
+            if rewarded[index] || fractions[index] == 0 {
+                continue;
+            }
+            if best.is_none_or(|best| fractions[index] > fractions[best]) {
+                best = Some(index);
+            }
+        }
+        let Some(index) = best else {
+            break;
+        };
+        rewarded[index] = true;
+        cuts[index] += 1;
+    }
+
+    Ok(Allocation {
+        cuts,
+        residual: amount - total_fee,
+    })
+}
+
+/// Fee at `rate` of `amount`, rounded down.
+pub fn fee(amount: u64, rate: Rate) -> Result<u64, FeeError> {
+    allocate(amount, [rate]).map(|allocation| allocation.cuts[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn rates(bps: [u16; 4]) -> [Rate; 4] {
+        bps.map(Rate::from_bps)
+    }
+
+    #[test]
+    fn small_prices_lose_nothing_to_rounding() {
+        // 2.5% royalty, 1% developer, 0.375% reward pool, 0.125% ops.
+        let rates = [
+            Rate::from_bps(250),
+            Rate::from_bps(100),
+            Rate::from_parts(3_750_000),
+            Rate::from_parts(1_250_000),
+        ];
+        let allocation = allocate(1_000, rates).unwrap();
+        // Exact shares are 25, 10, 3.75 and 1.25 lamports: the combined fee
+        // is exactly 40, and the reward pool has the larger dropped fraction.
+        assert_eq!(allocation.cuts, [25, 10, 4, 1]);
+        assert_eq!(allocation.residual, 960);
+    }
+
+    #[test]
+    fn ties_go_to_the_earlier_cut() {
+        let half_lamport = Rate::from_bps(5_000);
+        let allocation = allocate(1, [half_lamport, half_lamport]).unwrap();
+        assert_eq!(allocation.cuts, [1, 0]);
+        assert_eq!(allocation.residual, 0);
+    }
+
+    #[test]
+    fn rejects_rates_above_the_whole() {
+        assert_eq!(
+            allocate(100, rates([6_000, 4_000, 1, 0])),
+            Err(FeeError::RatesExceedWhole)
+        );
+        assert!(allocate(100, rates([6_000, 4_000, 0, 0])).is_ok());
+    }
+
+    proptest! {
+        #[test]
+        fn cuts_and_residual_sum_to_the_amount(
+            amount in any::<u64>(),
+            parts in proptest::array::uniform4(0..=Rate::SCALE / 4),
+        ) {
+            let allocation = allocate(amount, parts.map(Rate::from_parts)).unwrap();
+            let total = allocation
+                .cuts
+                .iter()
+                .map(|cut| *cut as u128)
+                .sum::<u128>()
+                + allocation.residual as u128;
+            prop_assert_eq!(total, amount as u128);
+        }
+
+        #[test]
+        fn combined_fee_rounds_down_once(
+            amount in any::<u64>(),
+            parts in proptest::array::uniform4(0..=Rate::SCALE / 4),
+        ) {
+            let allocation = allocate(amount, parts.map(Rate::from_parts)).unwrap();
+            let total_parts: u128 = parts.iter().map(|parts| *parts as u128).sum();
+            let combined_fee = amount as u128 * total_parts / Rate::SCALE as u128;
+            prop_assert_eq!(amount as u128 - allocation.residual as u128, combined_fee);
+        }
+
+        #[test]
+        fn each_cut_is_within_a_lamport_of_its_exact_share(
+            amount in any::<u64>(),
+            bps in proptest::array::uniform4(0..=2_500u16),
+        ) {
+            let allocation = allocate(amount, rates(bps)).unwrap();
+            for (cut, bps) in allocation.cuts.iter().zip(bps) {
+                let exact = amount as u128 * bps as u128;
+                let floor = exact / BPS_DENOMINATOR as u128;
+                let ceil = exact.div_ceil(BPS_DENOMINATOR as u128);
+                prop_assert!(floor <= *cut as u128 && *cut as u128 <= ceil);
+            }
+        }
+
+        #[test]
+        fn single_fee_matches_truncating_bps_math(amount in any::<u64>(), bps in 0..=10_000u16) {
+            let expected = amount as u128 * bps as u128 / BPS_DENOMINATOR as u128;
+            prop_assert_eq!(fee(amount, Rate::from_bps(bps)).unwrap() as u128, expected);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Sorted-pair merkle proofs over SHA-256.
+//!
+//! Each parent is the hash of its two children in ascending byte order, so a
+//! proof is just the list of siblings from the leaf up and carries no
+//! left/right flags. Leaves are hashed by the caller, which keeps the leaf
+//! encoding (a wallet, a mint) with the program that defines it.
+#![cfg_attr(not(test), no_std)]
+
+use solana_sha256_hasher::hashv;
+
+/// Parent of two nodes: the hash of the smaller followed by the larger.
+pub fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hashv(&[&a, &b]).to_bytes()
+    } else {
+        hashv(&[&b, &a]).to_bytes()
+    }
+}
+
+/// Whether `proof` leads from `leaf` to `root`. An empty proof only admits
+/// the root itself.
+pub fn verify(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(node, *sibling));
+    computed == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(byte: u8) -> [u8; 32] {
+        hashv(&[&[byte; 32]]).to_bytes()
+    }
+
+    #[test]
+    fn pair_hash_ignores_argument_order() {
+        let (a, b) = (leaf(1), leaf(2));
+        assert_eq!(hash_pair(a, b), hash_pair(b, a));
+        assert_ne!(hash_pair(a, b), hash_pair(a, a));
+    }
+
+    #[test]
+    fn proofs_admit_only_their_own_leaf() {
+        let leaves = [leaf(1), leaf(2), leaf(3), leaf(4)];
+        let left = hash_pair(leaves[0], leaves[1]);
+        let right = hash_pair(leaves[2], leaves[3]);
+        let root = hash_pair(left, right);
+
+        for (index, node) in leaves.iter().enumerate() {
+            let sibling_subtree = if index < 2 { right } else { left };
+            let proof = [leaves[index ^ 1], sibling_subtree];
+            assert!(verify(&proof, root, *node));
+            assert!(!verify(&proof, root, leaves[index ^ 2]));
+            assert!(!verify(&proof[..1], root, *node));
+        }
+        assert!(!verify(&[right], root, leaves[0]));
+        assert!(verify(&[], root, root));
+        assert!(!verify(&[], root, leaves[0]));
+    }
+}
@@ -0,0 +1,23 @@
+//! Assertions shared by the programs' unit tests. Used only as a
+//! dev-dependency.
+
+use std::fmt::Debug;
+
+use anchor_lang::error::Error;
+use anchor_lang::Result;
+
+/// Numeric code of an Anchor error, or of the program error it wraps.
+pub fn error_code_number(error: &Error) -> u32 {
+    match error {
+        Error::AnchorError(error) => error.error_code_number,
+        Error::ProgramError(error) => u64::from(error.program_error.clone()) as u32,
+    }
+}
+
+/// Asserts that `result` failed with `expected`, a program's `#[error_code]`
+/// variant.
+#[track_caller]
+pub fn assert_error<T: Debug>(result: Result<T>, expected: impl Into<u32>) {
+    let error = result.expect_err("expected an error");
+    assert_eq!(error_code_number(&error), expected.into());
+}
@@ -10,6 +10,8 @@ pub const POOL_SEED: &[u8] = b"pool";
 pub const POOL_VAULT_SEED: &[u8] = b"pool-vault";
 pub const POOL_SIGNER_SEED: &[u8] = b"pool-signer";
 pub const POSITION_SEED: &[u8] = b"position";
+pub const REVENUE_POOL_SEED: &[u8] = b"revenue-pool";
+pub const REVENUE_POSITION_SEED: &[u8] = b"revenue-position";
 
 const REWARD_SCALE: u128 = 1_000_000_000;
 
@@ -105,6 +107,16 @@ pub mod clout_staking {
 
         accrue_pool(pool)?;
         accrue_position(pool, position)?;
+        let revenue_pool = &mut ctx.accounts.revenue_pool;
+        init_revenue_pool(revenue_pool, pool.key(), ctx.bumps.revenue_pool);
+        sync_revenue(revenue_pool, pool.total_staked)?;
+        accrue_revenue_position(
+            revenue_pool,
+            position.amount,
+            &mut ctx.accounts.revenue_position,
+            position.key(),
+            ctx.bumps.revenue_position,
+        )?;
 
         let staker_key = ctx.accounts.staker.key();
         if position.is_uninitialized() {
@@ -162,6 +174,16 @@ pub mod clout_staking {
 
         accrue_pool(pool)?;
         accrue_position(pool, position)?;
+        let revenue_pool = &mut ctx.accounts.revenue_pool;
+        init_revenue_pool(revenue_pool, pool.key(), ctx.bumps.revenue_pool);
+        sync_revenue(revenue_pool, pool.total_staked)?;
+        accrue_revenue_position(
+            revenue_pool,
+            position.amount,
+            &mut ctx.accounts.revenue_position,
+            position.key(),
+            ctx.bumps.revenue_position,
+        )?;
 
         pool.total_staked = pool
             .total_staked
@@ -251,6 +273,70 @@ pub mod clout_staking {
 
         Ok(())
     }
+
+    /// Creates the SOL revenue pool for a staking pool ahead of the first
+    /// stake or unstake, which otherwise create it. Marketplace fees
+    /// credited to it are shared among stakers pro rata to their stake.
+    pub fn create_revenue_pool(ctx: Context<CreateRevenuePool>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.pool.authority,
+            ctx.accounts.authority.key(),
+            StakingError::Unauthorized
+        );
+
+        init_revenue_pool(
+            &mut ctx.accounts.revenue_pool,
+            ctx.accounts.pool.key(),
+            ctx.bumps.revenue_pool,
+        );
+        Ok(())
+    }
+
+    /// Accounts for lamports credited to the revenue pool since the last
+    /// sync. Permissionless; claims, stakes and unstakes also sync first.
+    pub fn sync_revenue_pool(ctx: Context<SyncRevenuePool>) -> Result<()> {
+        sync_revenue(
+            &mut ctx.accounts.revenue_pool,
+            ctx.accounts.pool.total_staked,
+        )
+    }
+
+    /// Pays a staker the SOL revenue their position has accrued.
+    pub fn claim_revenue(ctx: Context<ClaimRevenue>) -> Result<()> {
+        let position = &ctx.accounts.position;
+        require_keys_eq!(
+            position.owner,
+            ctx.accounts.staker.key(),
+            StakingError::Unauthorized
+        );
+
+        let revenue_pool = &mut ctx.accounts.revenue_pool;
+        sync_revenue(revenue_pool, ctx.accounts.pool.total_staked)?;
+        let revenue_position = &mut ctx.accounts.revenue_position;
+        accrue_revenue_position(
+            revenue_pool,
+            position.amount,
+            revenue_position,
+            position.key(),
+            ctx.bumps.revenue_position,
+        )?;
+
+        let amount = take_pending_revenue(revenue_pool, revenue_position)?;
+
+        let revenue_pool_info = revenue_pool.to_account_info();
+        let remaining = revenue_pool_info
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(StakingError::InsufficientRevenue)?;
+        **revenue_pool_info.try_borrow_mut_lamports()? = remaining;
+        let staker_info = ctx.accounts.staker.to_account_info();
+        let credited = staker_info
+            .lamports()
+            .checked_add(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        **staker_info.try_borrow_mut_lamports()? = credited;
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
@@ -329,6 +415,22 @@ pub struct Stake<'info> {
         constraint = staker_token.mint == pool.clout_mint @ StakingError::MismatchedStakeMint
     )]
     pub staker_token: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = staker,
+        space = RevenuePool::LEN,
+        seeds = [REVENUE_POOL_SEED, pool.key().as_ref()],
+        bump
+    )]
+    pub revenue_pool: Account<'info, RevenuePool>,
+    #[account(
+        init_if_needed,
+        payer = staker,
+        space = RevenuePosition::LEN,
+        seeds = [REVENUE_POSITION_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub revenue_position: Account<'info, RevenuePosition>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
@@ -365,7 +467,24 @@ pub struct Unstake<'info> {
         bump = pool.signer_bump
     )]
     pub pool_signer: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = staker,
+        space = RevenuePool::LEN,
+        seeds = [REVENUE_POOL_SEED, pool.key().as_ref()],
+        bump
+    )]
+    pub revenue_pool: Account<'info, RevenuePool>,
+    #[account(
+        init_if_needed,
+        payer = staker,
+        space = RevenuePosition::LEN,
+        seeds = [REVENUE_POSITION_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub revenue_position: Account<'info, RevenuePosition>,
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -407,6 +526,60 @@ pub struct Harvest<'info> {
     pub rewards_vault_program: Program<'info, RewardsVaultProgram>,
 }
 
+#[derive(Accounts)]
+pub struct CreateRevenuePool<'info> {
+    pub pool: Account<'info, StakingPool>,
+    #[account(
+        init,
+        payer = authority,
+        space = RevenuePool::LEN,
+        seeds = [REVENUE_POOL_SEED, pool.key().as_ref()],
+        bump
+    )]
+    pub revenue_pool: Account<'info, RevenuePool>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncRevenuePool<'info> {
+    pub pool: Account<'info, StakingPool>,
+    #[account(
+        mut,
+        seeds = [REVENUE_POOL_SEED, pool.key().as_ref()],
+        bump = revenue_pool.bump
+    )]
+    pub revenue_pool: Account<'info, RevenuePool>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRevenue<'info> {
+    pub pool: Account<'info, StakingPool>,
+    #[account(
+        seeds = [POSITION_SEED, pool.key().as_ref(), staker.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, StakePosition>,
+    #[account(
+        mut,
+        seeds = [REVENUE_POOL_SEED, pool.key().as_ref()],
+        bump = revenue_pool.bump
+    )]
+    pub revenue_pool: Account<'info, RevenuePool>,
+    #[account(
+        init_if_needed,
+        payer = staker,
+        space = RevenuePosition::LEN,
+        seeds = [REVENUE_POSITION_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub revenue_position: Account<'info, RevenuePosition>,
+    #[account(mut)]
+    pub staker: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[account]
 pub struct StakingPool {
     pub bump: u8,
@@ -463,6 +636,49 @@ impl StakePosition {
     }
 }
 
+/// SOL revenue shared among a staking pool's stakers. Lamports credited to
+/// this account are picked up by the next sync.
+#[account]
+pub struct RevenuePool {
+    pub bump: u8,
+    pub pool: Pubkey,
+    pub revenue_per_token_stored: u128,
+    /// Lamports above rent already accounted for and not yet claimed.
+    pub tracked_lamports: u64,
+    /// Revenue received while nothing was staked, shared at the next sync
+    /// with stake.
+    pub undistributed: u64,
+    pub total_received: u64,
+}
+
+impl RevenuePool {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // pool
+        + 16 // revenue per token stored
+        + 8 // tracked lamports
+        + 8 // undistributed
+        + 8; // total received
+}
+
+/// A stake position's share of the revenue pool. Created lazily; a position
+/// that predates the revenue pool starts from zero like the accumulator.
+#[account]
+pub struct RevenuePosition {
+    pub bump: u8,
+    pub position: Pubkey,
+    pub revenue_per_token_paid: u128,
+    pub pending_revenue: u64,
+}
+
+impl RevenuePosition {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // position
+        + 16 // revenue per token paid
+        + 8; // pending revenue
+}
+
 fn derive_pool_signer(mint: &Pubkey, bump: u8) -> Result<Pubkey> {
     Pubkey::create_program_address(&[POOL_SIGNER_SEED, mint.as_ref(), &[bump]], &crate::ID)
         .map_err(|_| StakingError::InvalidPoolSigner.into())
@@ -540,6 +756,116 @@ fn accrue_position(
     Ok(())
 }
 
+/// Fills in a revenue pool that was just created; a no-op for one that
+/// already exists, so pools that predate revenue sharing are created by the
+/// first stake or unstake.
+fn init_revenue_pool(revenue_pool: &mut RevenuePool, pool: Pubkey, bump: u8) {
+    if revenue_pool.pool != Pubkey::default() {
+        return;
+    }
+    revenue_pool.bump = bump;
+    revenue_pool.pool = pool;
+    revenue_pool.revenue_per_token_stored = 0;
+    revenue_pool.tracked_lamports = 0;
+    revenue_pool.undistributed = 0;
+    revenue_pool.total_received = 0;
+}
+
+/// Distributes lamports credited to the revenue pool since the last sync
+/// over the current stake, the same way `accrue_pool` distributes emissions.
+fn sync_revenue(revenue_pool: &mut Account<RevenuePool>, total_staked: u64) -> Result<()> {
+    let rent_exempt = Rent::get()?.minimum_balance(RevenuePool::LEN);
+    let available = revenue_pool
+        .to_account_info()
+        .lamports()
+        .saturating_sub(rent_exempt);
+    distribute_revenue(revenue_pool, available, total_staked)
+}
+
+/// `sync_revenue` given the pool's lamports above rent.
+fn distribute_revenue(
+    revenue_pool: &mut RevenuePool,
+    available: u64,
+    total_staked: u64,
+) -> Result<()> {
+    let received = available
+        .checked_sub(revenue_pool.tracked_lamports)
+        .ok_or(StakingError::MathOverflow)?;
+    if received > 0 {
+        revenue_pool.tracked_lamports = available;
+        revenue_pool.undistributed = revenue_pool
+            .undistributed
+            .checked_add(received)
+            .ok_or(StakingError::MathOverflow)?;
+        revenue_pool.total_received = revenue_pool
+            .total_received
+            .checked_add(received)
+            .ok_or(StakingError::MathOverflow)?;
+    }
+
+    if revenue_pool.undistributed == 0 || total_staked == 0 {
+        return Ok(());
+    }
+    let per_token_increment = (revenue_pool.undistributed as u128)
+        .checked_mul(REWARD_SCALE)
+        .ok_or(StakingError::MathOverflow)?
+        .checked_div(total_staked as u128)
+        .ok_or(StakingError::MathOverflow)?;
+    revenue_pool.revenue_per_token_stored = revenue_pool
+        .revenue_per_token_stored
+        .checked_add(per_token_increment)
+        .ok_or(StakingError::MathOverflow)?;
+    revenue_pool.undistributed = 0;
+    Ok(())
+}
+
+/// Moves a position's newly earned revenue into `pending_revenue`. Must run
+/// before the position's staked amount changes.
+fn accrue_revenue_position(
+    revenue_pool: &RevenuePool,
+    staked_amount: u64,
+    revenue_position: &mut RevenuePosition,
+    position: Pubkey,
+    bump: u8,
+) -> Result<()> {
+    if revenue_position.position == Pubkey::default() {
+        revenue_position.bump = bump;
+        revenue_position.position = position;
+    }
+    let delta = revenue_pool
+        .revenue_per_token_stored
+        .checked_sub(revenue_position.revenue_per_token_paid)
+        .ok_or(StakingError::MathOverflow)?;
+    let accrued = (staked_amount as u128)
+        .checked_mul(delta)
+        .ok_or(StakingError::MathOverflow)?
+        .checked_div(REWARD_SCALE)
+        .ok_or(StakingError::MathOverflow)?;
+    let pending = (revenue_position.pending_revenue as u128)
+        .checked_add(accrued)
+        .ok_or(StakingError::MathOverflow)?;
+    require!(pending <= u64::MAX as u128, StakingError::MathOverflow);
+    revenue_position.pending_revenue = pending as u64;
+    revenue_position.revenue_per_token_paid = revenue_pool.revenue_per_token_stored;
+    Ok(())
+}
+
+/// Zeroes a position's accrued revenue and releases it from the pool's
+/// tracked lamports, returning the amount to pay out.
+fn take_pending_revenue(
+    revenue_pool: &mut RevenuePool,
+    revenue_position: &mut RevenuePosition,
+) -> Result<u64> {
+    let amount = revenue_position.pending_revenue;
+    require!(amount > 0, StakingError::NoRewardsAvailable);
+    revenue_position.pending_revenue = 0;
+    revenue_pool.tracked_lamports = revenue_pool
+        .tracked_lamports
+        .checked_sub(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    Ok(amount)
+}
+
 #[error_code]
 pub enum StakingError {
     #[msg("Amount must be greater than zero.")]
@@ -570,4 +896,128 @@ pub enum StakingError {
     MismatchedPoolVaultMint,
     #[msg("Provided staking token mint does not match pool configuration.")]
     MismatchedStakeMint,
+    #[msg("Revenue pool balance is insufficient for the claim.")]
+    InsufficientRevenue,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_utils::assert_error;
+
+    fn revenue_pool() -> RevenuePool {
+        RevenuePool {
+            bump: 255,
+            pool: Pubkey::new_unique(),
+            revenue_per_token_stored: 0,
+            tracked_lamports: 0,
+            undistributed: 0,
+            total_received: 0,
+        }
+    }
+
+    fn revenue_position() -> RevenuePosition {
+        RevenuePosition {
+            bump: 0,
+            position: Pubkey::default(),
+            revenue_per_token_paid: 0,
+            pending_revenue: 0,
+        }
+    }
+
+    #[test]
+    fn revenue_is_shared_pro_rata_to_stake() {
+        let mut pool = revenue_pool();
+        let (small, large) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut small_position = revenue_position();
+        let mut large_position = revenue_position();
+        accrue_revenue_position(&pool, 1_000, &mut small_position, small, 254).unwrap();
+        accrue_revenue_position(&pool, 2_000, &mut large_position, large, 253).unwrap();
+        assert_eq!(small_position.position, small);
+        assert_eq!(small_position.bump, 254);
+
+        distribute_revenue(&mut pool, 3_000, 3_000).unwrap();
+        assert_eq!(pool.revenue_per_token_stored, REWARD_SCALE);
+        accrue_revenue_position(&pool, 1_000, &mut small_position, small, 254).unwrap();
+        accrue_revenue_position(&pool, 2_000, &mut large_position, large, 253).unwrap();
+        assert_eq!(small_position.pending_revenue, 1_000);
+        assert_eq!(large_position.pending_revenue, 2_000);
+
+        // Accruing again without new revenue adds nothing.
+        accrue_revenue_position(&pool, 1_000, &mut small_position, small, 254).unwrap();
+        assert_eq!(small_position.pending_revenue, 1_000);
+        assert_eq!(small_position.revenue_per_token_paid, REWARD_SCALE);
+    }
+
+    #[test]
+    fn sync_holds_revenue_received_while_nothing_is_staked() {
+        let mut pool = revenue_pool();
+        distribute_revenue(&mut pool, 500, 0).unwrap();
+        assert_eq!(pool.tracked_lamports, 500);
+        assert_eq!(pool.undistributed, 500);
+        assert_eq!(pool.total_received, 500);
+        assert_eq!(pool.revenue_per_token_stored, 0);
+
+        // The held revenue and the new credit go to the first stake.
+        distribute_revenue(&mut pool, 800, 100).unwrap();
+        assert_eq!(pool.tracked_lamports, 800);
+        assert_eq!(pool.undistributed, 0);
+        assert_eq!(pool.total_received, 800);
+        assert_eq!(pool.revenue_per_token_stored, 8 * REWARD_SCALE);
+
+        // Syncing an unchanged balance is a no-op.
+        distribute_revenue(&mut pool, 800, 100).unwrap();
+        assert_eq!(pool.total_received, 800);
+        assert_eq!(pool.revenue_per_token_stored, 8 * REWARD_SCALE);
+
+        // Lamports leaving the pool outside a claim are an accounting error.
+        assert_error(
+            distribute_revenue(&mut pool, 799, 100),
+            StakingError::MathOverflow,
+        );
+    }
+
+    #[test]
+    fn claims_release_revenue_from_the_tracked_balance() {
+        let mut pool = revenue_pool();
+        let position = Pubkey::new_unique();
+        let mut revenue_position = revenue_position();
+        accrue_revenue_position(&pool, 400, &mut revenue_position, position, 254).unwrap();
+        distribute_revenue(&mut pool, 1_000, 400).unwrap();
+        accrue_revenue_position(&pool, 400, &mut revenue_position, position, 254).unwrap();
+
+        assert_eq!(
+            take_pending_revenue(&mut pool, &mut revenue_position).unwrap(),
+            1_000
+        );
+        assert_eq!(revenue_position.pending_revenue, 0);
+        assert_eq!(pool.tracked_lamports, 0);
+        // The claimed lamports left the pool, so the next sync sees nothing new.
+        distribute_revenue(&mut pool, 0, 400).unwrap();
+        assert_eq!(pool.total_received, 1_000);
+
+        assert_error(
+            take_pending_revenue(&mut pool, &mut revenue_position),
+            StakingError::NoRewardsAvailable,
+        );
+    }
+
+    #[test]
+    fn existing_revenue_pools_are_not_reinitialized() {
+        let staking_pool = Pubkey::new_unique();
+        let mut pool = RevenuePool {
+            pool: Pubkey::default(),
+            ..revenue_pool()
+        };
+        init_revenue_pool(&mut pool, staking_pool, 250);
+        assert_eq!(pool.pool, staking_pool);
+        assert_eq!(pool.bump, 250);
+
+        distribute_revenue(&mut pool, 700, 7).unwrap();
+        init_revenue_pool(&mut pool, Pubkey::new_unique(), 1);
+        assert_eq!(pool.pool, staking_pool);
+        assert_eq!(pool.bump, 250);
+        assert_eq!(pool.tracked_lamports, 700);
+        assert_eq!(pool.revenue_per_token_stored, 100 * REWARD_SCALE);
+    }
 }
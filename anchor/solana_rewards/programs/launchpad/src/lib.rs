@@ -0,0 +1,1101 @@
+#![allow(unexpected_cfgs)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::mpl_token_metadata::types::{Collection, Creator, DataV2};
+use anchor_spl::metadata::{
+    self, CreateMasterEditionV3, CreateMetadataAccountsV3, Metadata, MetadataAccount,
+    VerifyCollection, VerifySizedCollectionItem,
+};
+use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount};
+
+use clout_staking::{RevenuePool, StakingPool, REVENUE_POOL_SEED};
+use fee_engine::{Allocation, Rate};
+use loyalty_registry::program::LoyaltyRegistry as LoyaltyRegistryProgram;
+use loyalty_registry::{self, LoyaltyProfile, RegistryConfig, PROFILE_SEED, REGISTRY_CONFIG_SEED};
+use rewards_vault::program::RewardsVault as RewardsVaultProgram;
+use rewards_vault::{self, VaultConfig, VAULT_SIGNER_SEED};
+
+pub const LAUNCHPAD_CONFIG_SEED: &[u8] = b"launchpad-config";
+pub const LAUNCH_SEED: &[u8] = b"launch";
+pub const PHASE_SEED: &[u8] = b"launch-phase";
+pub const MINT_RECORD_SEED: &[u8] = b"mint-record";
+const METADATA_SEED: &[u8] = b"metadata";
+const EDITION_SEED: &[u8] = b"edition";
+const MAX_NAME_LEN: usize = 24; // leaves room for the " #<edition>" suffix
+const MAX_SYMBOL_LEN: usize = 10;
+const MAX_BASE_URI_LEN: usize = 160;
+const BPS_DENOMINATOR: u64 = 10_000;
+const PLATFORM_FEE_BPS: u16 = 150; // 1.50%
+const DEVELOPER_FEE_BPS: u16 = 100; // 1.00%
+const REMAINING_FEE_BPS: u16 = PLATFORM_FEE_BPS - DEVELOPER_FEE_BPS; // 0.50%
+const REWARD_FUND_RATIO_NUMERATOR: u64 = 3; // 75% of the remaining fee
+const OPS_TREASURY_RATIO_NUMERATOR: u64 = 1; // 25% of the remaining fee
+const RATIO_DENOMINATOR: u64 = REWARD_FUND_RATIO_NUMERATOR + OPS_TREASURY_RATIO_NUMERATOR;
+const DEVELOPER_WALLET: Pubkey = pubkey!("FsoPx1WmXA6FDxYTSULRDko3tKbNG7KxdRTq2icQJGjM");
+
+declare_id!("AejLqguBK3Qvi45iwDJnUnQrzBVQcTkLUdrxpJbMnUDk");
+
+#[program]
+pub mod launchpad {
+    use super::*;
+
+    /// Creates the launchpad configuration holding the `clout_staking` revenue
+    /// pool and ops treasury wallet that receive their share of every primary
+    /// mint.
+    pub fn initialize_launchpad(
+        ctx: Context<InitializeLaunchpad>,
+        ops_treasury: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.launchpad_config;
+        config.bump = ctx.bumps.launchpad_config;
+        config.authority = ctx.accounts.authority.key();
+        config.revenue_pool = ctx.accounts.revenue_pool.key();
+        config.ops_treasury = ops_treasury;
+        config.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Points the platform revenue split at the revenue pool of
+    /// `staking_pool` and a new ops wallet.
+    pub fn set_revenue_destinations(
+        ctx: Context<SetRevenueDestinations>,
+        ops_treasury: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.launchpad_config;
+        config.revenue_pool = ctx.accounts.revenue_pool.key();
+        config.ops_treasury = ops_treasury;
+        config.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Creator registers a new collection launch. Minted NFTs are named
+    /// `<name> #<edition>`, point at `<base_uri><edition>.json` and are
+    /// verified into `collection_mint`, whose update authority must be handed
+    /// to the launch PDA before the first mint.
+    pub fn create_launch(
+        ctx: Context<CreateLaunch>,
+        launch_id: u64,
+        params: LaunchParams,
+    ) -> Result<()> {
+        require!(
+            !params.name.is_empty() && params.name.len() <= MAX_NAME_LEN,
+            LaunchpadError::InvalidLaunchName
+        );
+        require!(
+            params.symbol.len() <= MAX_SYMBOL_LEN,
+            LaunchpadError::InvalidLaunchSymbol
+        );
+        require!(
+            !params.base_uri.is_empty() && params.base_uri.len() <= MAX_BASE_URI_LEN,
+            LaunchpadError::InvalidBaseUri
+        );
+        require!(
+            params.seller_fee_bps as u64 <= BPS_DENOMINATOR,
+            LaunchpadError::InvalidRoyalty
+        );
+        require!(params.max_supply > 0, LaunchpadError::InvalidMaxSupply);
+
+        let launch = &mut ctx.accounts.launch;
+        launch.bump = ctx.bumps.launch;
+        launch.creator = ctx.accounts.creator.key();
+        launch.launch_id = launch_id;
+        launch.collection_mint = ctx.accounts.collection_mint.key();
+        launch.name = params.name;
+        launch.symbol = params.symbol;
+        launch.base_uri = params.base_uri;
+        launch.seller_fee_bps = params.seller_fee_bps;
+        launch.max_supply = params.max_supply;
+        launch.minted = 0;
+        launch.phase_count = 0;
+        launch.creation_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Appends the next mint phase to a launch. Phases priced in an SPL token
+    /// pass that token's mint; phases priced in SOL omit it.
+    pub fn add_phase(ctx: Context<AddPhase>, params: PhaseParams) -> Result<()> {
+        if let Some(end_ts) = params.end_ts {
+            require!(end_ts > params.start_ts, LaunchpadError::InvalidPhaseWindow);
+        }
+        require!(
+            params.per_wallet_limit > 0,
+            LaunchpadError::InvalidWalletLimit
+        );
+
+        let launch = &mut ctx.accounts.launch;
+        let phase = &mut ctx.accounts.phase;
+        phase.bump = ctx.bumps.phase;
+        phase.launch = launch.key();
+        phase.index = launch.phase_count;
+        phase.kind = params.kind;
+        phase.price = params.price;
+        phase.payment_mint = ctx.accounts.payment_mint.as_ref().map(|mint| mint.key());
+        phase.start_ts = params.start_ts;
+        phase.end_ts = params.end_ts;
+        phase.per_wallet_limit = params.per_wallet_limit;
+        phase.minted = 0;
+
+        launch.phase_count = launch
+            .phase_count
+            .checked_add(1)
+            .ok_or(LaunchpadError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Replaces the merkle root of an allowlist phase.
+    pub fn set_allowlist_root(ctx: Context<UpdatePhase>, merkle_root: [u8; 32]) -> Result<()> {
+        let phase = &mut ctx.accounts.phase;
+        require!(
+            matches!(phase.kind, PhaseKind::Allowlist { .. }),
+            LaunchpadError::NotAllowlistPhase
+        );
+        phase.kind = PhaseKind::Allowlist { merkle_root };
+        Ok(())
+    }
+
+    /// Mints the next NFT of a launch to the caller during an active phase.
+    /// The phase price is split between the creator, the developer wallet,
+    /// the `clout_staking` revenue pool and the ops wallet, after which the
+    /// minter earns CLOUT rewards and loyalty points. Only SOL-priced mints
+    /// count towards loyalty volume.
+    pub fn mint_from_phase(
+        ctx: Context<MintFromPhase>,
+        allowlist_proof: Vec<[u8; 32]>,
+        reward_amount: u64,
+        loyalty_bonus_points: u64,
+    ) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.developer_wallet.key(),
+            DEVELOPER_WALLET,
+            LaunchpadError::InvalidDeveloperWallet
+        );
+        let minter_key = ctx.accounts.minter.key();
+        let now = Clock::get()?.unix_timestamp;
+        let phase = &ctx.accounts.phase;
+        phase.ensure_admits(&minter_key, now, &allowlist_proof)?;
+
+        let mint_record = &mut ctx.accounts.mint_record;
+        if mint_record.wallet == Pubkey::default() {
+            mint_record.bump = ctx.bumps.mint_record;
+            mint_record.phase = phase.key();
+            mint_record.wallet = minter_key;
+        }
+        mint_record.record_mint(phase.per_wallet_limit)?;
+
+        let edition = ctx.accounts.launch.take_next_edition()?;
+        let phase = &mut ctx.accounts.phase;
+        phase.minted = phase
+            .minted
+            .checked_add(1)
+            .ok_or(LaunchpadError::MathOverflow)?;
+
+        let price = phase.price;
+        let payment_mint = phase.payment_mint;
+        let split = compute_revenue_split(price)?;
+        match payment_mint {
+            None => pay_with_sol(ctx.accounts, &split)?,
+            Some(payment_mint) => pay_with_token(ctx.accounts, payment_mint, &split)?,
+        }
+
+        mint_edition(ctx.accounts, edition)?;
+
+        if reward_amount > 0 {
+            require_keys_eq!(
+                ctx.accounts.reward_vault.authority,
+                ctx.accounts.reward_authority.key(),
+                LaunchpadError::UnauthorizedRewardAuthority
+            );
+            let reward_ctx = CpiContext::new(
+                ctx.accounts.rewards_vault_program.to_account_info(),
+                rewards_vault::cpi::accounts::MintRewards {
+                    vault_config: ctx.accounts.reward_vault.to_account_info(),
+                    vault_signer: ctx.accounts.vault_signer.to_account_info(),
+                    reward_mint: ctx.accounts.reward_mint.to_account_info(),
+                    recipient: ctx.accounts.minter_reward_account.to_account_info(),
+                    authority: ctx.accounts.reward_authority.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            );
+            rewards_vault::cpi::mint_rewards(reward_ctx, reward_amount)?;
+        }
+
+        let loyalty_volume = if payment_mint.is_none() { price } else { 0 };
+        let loyalty_ctx = CpiContext::new(
+            ctx.accounts.loyalty_program.to_account_info(),
+            loyalty_registry::cpi::accounts::RecordActivity {
+                actor: ctx.accounts.minter.to_account_info(),
+                profile: ctx.accounts.loyalty_profile.to_account_info(),
+                registry_config: ctx.accounts.loyalty_registry_config.to_account_info(),
+                authority: ctx.accounts.loyalty_authority.to_account_info(),
+            },
+        );
+        loyalty_registry::cpi::record_activity(loyalty_ctx, loyalty_volume, loyalty_bonus_points)
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeLaunchpad<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = LaunchpadConfig::LEN,
+        seeds = [LAUNCHPAD_CONFIG_SEED],
+        bump
+    )]
+    pub launchpad_config: Account<'info, LaunchpadConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(
+        seeds = [REVENUE_POOL_SEED, staking_pool.key().as_ref()],
+        bump = revenue_pool.bump,
+        seeds::program = clout_staking::ID
+    )]
+    pub revenue_pool: Account<'info, RevenuePool>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateLaunchpadConfig<'info> {
+    #[account(
+        mut,
+        seeds = [LAUNCHPAD_CONFIG_SEED],
+        bump = launchpad_config.bump,
+        has_one = authority @ LaunchpadError::UnauthorizedLaunchpadAuthority
+    )]
+    pub launchpad_config: Account<'info, LaunchpadConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRevenueDestinations<'info> {
+    #[account(
+        mut,
+        seeds = [LAUNCHPAD_CONFIG_SEED],
+        bump = launchpad_config.bump,
+        has_one = authority @ LaunchpadError::UnauthorizedLaunchpadAuthority
+    )]
+    pub launchpad_config: Account<'info, LaunchpadConfig>,
+    pub authority: Signer<'info>,
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(
+        seeds = [REVENUE_POOL_SEED, staking_pool.key().as_ref()],
+        bump = revenue_pool.bump,
+        seeds::program = clout_staking::ID
+    )]
+    pub revenue_pool: Account<'info, RevenuePool>,
+}
+
+#[derive(Accounts)]
+#[instruction(launch_id: u64)]
+pub struct CreateLaunch<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = Launch::LEN,
+        seeds = [LAUNCH_SEED, creator.key().as_ref(), &launch_id.to_le_bytes()],
+        bump
+    )]
+    pub launch: Account<'info, Launch>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub collection_mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddPhase<'info> {
+    #[account(mut, has_one = creator)]
+    pub launch: Account<'info, Launch>,
+    #[account(
+        init,
+        payer = creator,
+        space = LaunchPhase::LEN,
+        seeds = [PHASE_SEED, launch.key().as_ref(), &[launch.phase_count]],
+        bump
+    )]
+    pub phase: Account<'info, LaunchPhase>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    /// SPL token the phase is priced in; omitted for SOL-priced phases.
+    pub payment_mint: Option<Account<'info, Mint>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePhase<'info> {
+    #[account(has_one = creator)]
+    pub launch: Account<'info, Launch>,
+    #[account(mut, has_one = launch)]
+    pub phase: Account<'info, LaunchPhase>,
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MintFromPhase<'info> {
+    #[account(mut)]
+    pub minter: Signer<'info>,
+    #[account(mut, has_one = creator)]
+    pub launch: Box<Account<'info, Launch>>,
+    #[account(
+        mut,
+        seeds = [PHASE_SEED, launch.key().as_ref(), &[phase.index]],
+        bump = phase.bump,
+        has_one = launch
+    )]
+    pub phase: Box<Account<'info, LaunchPhase>>,
+    #[account(
+        init_if_needed,
+        payer = minter,
+        space = MintRecord::LEN,
+        seeds = [MINT_RECORD_SEED, phase.key().as_ref(), minter.key().as_ref()],
+        bump
+    )]
+    pub mint_record: Box<Account<'info, MintRecord>>,
+    #[account(seeds = [LAUNCHPAD_CONFIG_SEED], bump = launchpad_config.bump)]
+    pub launchpad_config: Box<Account<'info, LaunchpadConfig>>,
+    #[account(mut)]
+    pub creator: SystemAccount<'info>,
+    #[account(mut)]
+    pub developer_wallet: SystemAccount<'info>,
+    /// CHECK: the `clout_staking` revenue pool recorded in the launchpad config.
+    #[account(
+        mut,
+        address = launchpad_config.revenue_pool @ LaunchpadError::RevenueDestinationMismatch
+    )]
+    pub rewards_pool_destination: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = launchpad_config.ops_treasury @ LaunchpadError::RevenueDestinationMismatch
+    )]
+    pub ops_treasury_destination: SystemAccount<'info>,
+    /// Minter's token account funding an SPL-priced phase.
+    #[account(mut)]
+    pub minter_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub creator_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub developer_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub ops_treasury_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        init,
+        payer = minter,
+        mint::decimals = 0,
+        mint::authority = launch,
+        mint::freeze_authority = launch
+    )]
+    pub nft_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init,
+        payer = minter,
+        associated_token::mint = nft_mint,
+        associated_token::authority = minter
+    )]
+    pub minter_nft_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: created by the token metadata program; address validated via seeds.
+    #[account(
+        mut,
+        seeds = [METADATA_SEED, metadata_program.key().as_ref(), nft_mint.key().as_ref()],
+        seeds::program = metadata_program.key(),
+        bump
+    )]
+    pub nft_metadata: UncheckedAccount<'info>,
+    /// CHECK: created by the token metadata program; address validated via seeds.
+    #[account(
+        mut,
+        seeds = [METADATA_SEED, metadata_program.key().as_ref(), nft_mint.key().as_ref(), EDITION_SEED],
+        seeds::program = metadata_program.key(),
+        bump
+    )]
+    pub nft_master_edition: UncheckedAccount<'info>,
+    #[account(address = launch.collection_mint @ LaunchpadError::CollectionMismatch)]
+    pub collection_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = collection_metadata.mint == launch.collection_mint
+            @ LaunchpadError::CollectionMismatch,
+        constraint = collection_metadata.update_authority == launch.key()
+            @ LaunchpadError::CollectionAuthorityMismatch
+    )]
+    pub collection_metadata: Box<Account<'info, MetadataAccount>>,
+    /// CHECK: owned by the token metadata program; address validated via seeds.
+    #[account(
+        seeds = [METADATA_SEED, metadata_program.key().as_ref(), collection_mint.key().as_ref(), EDITION_SEED],
+        seeds::program = metadata_program.key(),
+        bump
+    )]
+    pub collection_master_edition: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        has_one = reward_mint @ LaunchpadError::MismatchedRewardMint
+    )]
+    pub reward_vault: Box<Account<'info, VaultConfig>>,
+    /// CHECK: PDA signer validated via seeds.
+    #[account(
+        seeds = [VAULT_SIGNER_SEED, reward_mint.key().as_ref()],
+        bump = reward_vault.signer_bump,
+        seeds::program = rewards_vault_program.key()
+    )]
+    pub vault_signer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub reward_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = minter_reward_account.mint == reward_mint.key(),
+        constraint = minter_reward_account.owner == minter.key()
+    )]
+    pub minter_reward_account: Box<Account<'info, TokenAccount>>,
+    pub reward_authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, minter.key().as_ref()],
+        bump = loyalty_profile.bump,
+        seeds::program = loyalty_program.key()
+    )]
+    pub loyalty_profile: Box<Account<'info, LoyaltyProfile>>,
+    #[account(
+        mut,
+        seeds = [REGISTRY_CONFIG_SEED],
+        bump = loyalty_registry_config.bump,
+        seeds::program = loyalty_program.key(),
+        constraint = loyalty_registry_config.authority == loyalty_authority.key() @ LaunchpadError::UnauthorizedLoyaltyAuthority
+    )]
+    pub loyalty_registry_config: Box<Account<'info, RegistryConfig>>,
+    pub loyalty_authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub metadata_program: Program<'info, Metadata>,
+    pub rewards_vault_program: Program<'info, RewardsVaultProgram>,
+    pub loyalty_program: Program<'info, LoyaltyRegistryProgram>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[account]
+pub struct LaunchpadConfig {
+    pub bump: u8,
+    pub authority: Pubkey,
+    /// `clout_staking` revenue pool that receives the reward pool cut of
+    /// SOL-priced mints.
+    pub revenue_pool: Pubkey,
+    pub ops_treasury: Pubkey,
+    pub last_updated_ts: i64,
+}
+
+impl LaunchpadConfig {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // authority
+        + 32 // revenue pool
+        + 32 // ops treasury
+        + 8; // last updated timestamp
+}
+
+#[account]
+pub struct Launch {
+    pub bump: u8,
+    pub creator: Pubkey,
+    pub launch_id: u64,
+    pub collection_mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub base_uri: String,
+    pub seller_fee_bps: u16,
+    pub max_supply: u32,
+    pub minted: u32,
+    pub phase_count: u8,
+    pub creation_ts: i64,
+}
+
+impl Launch {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // creator
+        + 8 // launch id
+        + 32 // collection mint
+        + 4 + MAX_NAME_LEN // name
+        + 4 + MAX_SYMBOL_LEN // symbol
+        + 4 + MAX_BASE_URI_LEN // base uri
+        + 2 // seller fee bps
+        + 4 // max supply
+        + 4 // minted
+        + 1 // phase count
+        + 8; // creation ts
+
+    /// Claims the next edition number, failing once `max_supply` is minted.
+    fn take_next_edition(&mut self) -> Result<u32> {
+        require!(self.minted < self.max_supply, LaunchpadError::SoldOut);
+        self.minted = self
+            .minted
+            .checked_add(1)
+            .ok_or(LaunchpadError::MathOverflow)?;
+        Ok(self.minted)
+    }
+}
+
+#[account]
+pub struct LaunchPhase {
+    pub bump: u8,
+    pub launch: Pubkey,
+    pub index: u8,
+    pub kind: PhaseKind,
+    pub price: u64,
+    pub payment_mint: Option<Pubkey>,
+    pub start_ts: i64,
+    pub end_ts: Option<i64>,
+    pub per_wallet_limit: u32,
+    pub minted: u32,
+}
+
+impl LaunchPhase {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // launch
+        + 1 // index
+        + 1 + 32 // kind enum with merkle root
+        + 8 // price
+        + 1 + 32 // payment mint option
+        + 8 // start ts
+        + 1 + 8 // end ts option
+        + 4 // per wallet limit
+        + 4; // minted
+
+    /// Fails unless the phase is open at `now` and, for allowlist phases,
+    /// `allowlist_proof` places `minter` under the phase root.
+    fn ensure_admits(&self, minter: &Pubkey, now: i64, allowlist_proof: &[[u8; 32]]) -> Result<()> {
+        require!(
+            now >= self.start_ts && self.end_ts.is_none_or(|end_ts| now < end_ts),
+            LaunchpadError::PhaseNotActive
+        );
+        if let PhaseKind::Allowlist { merkle_root } = self.kind {
+            require!(
+                merkle_proof::verify(allowlist_proof, merkle_root, allowlist_leaf(minter)),
+                LaunchpadError::NotAllowlisted
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Tracks how many NFTs a wallet has minted in one phase.
+#[account]
+pub struct MintRecord {
+    pub bump: u8,
+    pub phase: Pubkey,
+    pub wallet: Pubkey,
+    pub minted: u32,
+}
+
+impl MintRecord {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // phase
+        + 32 // wallet
+        + 4; // minted
+
+    /// Counts one more mint by the wallet, failing at the phase limit.
+    fn record_mint(&mut self, per_wallet_limit: u32) -> Result<()> {
+        require!(
+            self.minted < per_wallet_limit,
+            LaunchpadError::WalletLimitReached
+        );
+        self.minted = self
+            .minted
+            .checked_add(1)
+            .ok_or(LaunchpadError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LaunchParams {
+    pub name: String,
+    pub symbol: String,
+    pub base_uri: String,
+    pub seller_fee_bps: u16,
+    pub max_supply: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PhaseParams {
+    pub kind: PhaseKind,
+    pub price: u64,
+    pub start_ts: i64,
+    pub end_ts: Option<i64>,
+    pub per_wallet_limit: u32,
+}
+
+/// Allowlist phases admit wallets whose `allowlist_leaf` is included under
+/// `merkle_root`; public phases admit anyone.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PhaseKind {
+    Allowlist { merkle_root: [u8; 32] },
+    Public,
+}
+
+struct RevenueSplit {
+    creator_proceeds: u64,
+    developer_cut: u64,
+    reward_pool_cut: u64,
+    ops_cut: u64,
+}
+
+/// Splits a primary-sale price with the same developer / reward-pool / ops
+/// fee schedule as secondary sales on `market_escrow`, allocated in one step
+/// with `fee_engine` so the split always sums to the price.
+fn compute_revenue_split(price: u64) -> Result<RevenueSplit> {
+    let remaining_fee_rate = Rate::from_bps(REMAINING_FEE_BPS);
+    let reward_pool_rate = remaining_fee_rate
+        .checked_mul_div(REWARD_FUND_RATIO_NUMERATOR, RATIO_DENOMINATOR)
+        .ok_or(LaunchpadError::MathOverflow)?;
+    let ops_rate = remaining_fee_rate
+        .checked_sub(reward_pool_rate)
+        .ok_or(LaunchpadError::MathOverflow)?;
+    let Allocation {
+        cuts: [developer_cut, reward_pool_cut, ops_cut],
+        residual: creator_proceeds,
+    } = fee_engine::allocate(
+        price,
+        [
+            Rate::from_bps(DEVELOPER_FEE_BPS),
+            reward_pool_rate,
+            ops_rate,
+        ],
+    )
+    .map_err(|_| error!(LaunchpadError::MathOverflow))?;
+    Ok(RevenueSplit {
+        creator_proceeds,
+        developer_cut,
+        reward_pool_cut,
+        ops_cut,
+    })
+}
+
+fn pay_with_sol(accounts: &MintFromPhase, split: &RevenueSplit) -> Result<()> {
+    let payouts = [
+        (accounts.creator.to_account_info(), split.creator_proceeds),
+        (
+            accounts.developer_wallet.to_account_info(),
+            split.developer_cut,
+        ),
+        (
+            accounts.rewards_pool_destination.to_account_info(),
+            split.reward_pool_cut,
+        ),
+        (
+            accounts.ops_treasury_destination.to_account_info(),
+            split.ops_cut,
+        ),
+    ];
+    for (destination, amount) in payouts {
+        if amount == 0 {
+            continue;
+        }
+        system_program::transfer(
+            CpiContext::new(
+                accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: accounts.minter.to_account_info(),
+                    to: destination,
+                },
+            ),
+            amount,
+        )?;
+    }
+    Ok(())
+}
+
+fn pay_with_token(
+    accounts: &MintFromPhase,
+    payment_mint: Pubkey,
+    split: &RevenueSplit,
+) -> Result<()> {
+    let (Some(source), Some(creator_account), Some(developer_account), Some(ops_treasury_account)) = (
+        accounts.minter_payment_account.as_ref(),
+        accounts.creator_payment_account.as_ref(),
+        accounts.developer_payment_account.as_ref(),
+        accounts.ops_treasury_payment_account.as_ref(),
+    ) else {
+        return err!(LaunchpadError::MissingPaymentAccounts);
+    };
+    require_keys_eq!(
+        source.owner,
+        accounts.minter.key(),
+        LaunchpadError::PaymentAccountOwnerMismatch
+    );
+
+    // The revenue pool only pays stakers SOL, so the ops treasury takes the
+    // reward pool cut of token-priced mints.
+    let ops_cut = split
+        .ops_cut
+        .checked_add(split.reward_pool_cut)
+        .ok_or(LaunchpadError::MathOverflow)?;
+    let payouts = [
+        (
+            creator_account,
+            accounts.creator.key(),
+            split.creator_proceeds,
+        ),
+        (developer_account, DEVELOPER_WALLET, split.developer_cut),
+        (
+            ops_treasury_account,
+            accounts.launchpad_config.ops_treasury,
+            ops_cut,
+        ),
+    ];
+    for (destination, expected_owner, amount) in payouts {
+        require_keys_eq!(
+            destination.mint,
+            payment_mint,
+            LaunchpadError::PaymentMintMismatch
+        );
+        require_keys_eq!(
+            destination.owner,
+            expected_owner,
+            LaunchpadError::PaymentAccountOwnerMismatch
+        );
+        if amount == 0 {
+            continue;
+        }
+        token::transfer(
+            CpiContext::new(
+                accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: source.to_account_info(),
+                    to: destination.to_account_info(),
+                    authority: accounts.minter.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+    }
+    Ok(())
+}
+
+/// Mints edition `edition` of the launch to the minter, creates its metadata
+/// and master edition with the launch PDA as update authority, and verifies
+/// it into the launch collection.
+fn mint_edition(accounts: &MintFromPhase, edition: u32) -> Result<()> {
+    let launch = &accounts.launch;
+    let launch_id = launch.launch_id.to_le_bytes();
+    let signer_seeds: &[&[u8]] = &[
+        LAUNCH_SEED,
+        launch.creator.as_ref(),
+        &launch_id,
+        &[launch.bump],
+    ];
+
+    token::mint_to(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            MintTo {
+                mint: accounts.nft_mint.to_account_info(),
+                to: accounts.minter_nft_account.to_account_info(),
+                authority: launch.to_account_info(),
+            },
+            &[signer_seeds],
+        ),
+        1,
+    )?;
+
+    let data = edition_data(launch, edition);
+    metadata::create_metadata_accounts_v3(
+        CpiContext::new_with_signer(
+            accounts.metadata_program.to_account_info(),
+            CreateMetadataAccountsV3 {
+                metadata: accounts.nft_metadata.to_account_info(),
+                mint: accounts.nft_mint.to_account_info(),
+                mint_authority: launch.to_account_info(),
+                payer: accounts.minter.to_account_info(),
+                update_authority: launch.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                rent: accounts.rent.to_account_info(),
+            },
+            &[signer_seeds],
+        ),
+        data,
+        true,
+        true,
+        None,
+    )?;
+    metadata::create_master_edition_v3(
+        CpiContext::new_with_signer(
+            accounts.metadata_program.to_account_info(),
+            CreateMasterEditionV3 {
+                edition: accounts.nft_master_edition.to_account_info(),
+                mint: accounts.nft_mint.to_account_info(),
+                update_authority: launch.to_account_info(),
+                mint_authority: launch.to_account_info(),
+                payer: accounts.minter.to_account_info(),
+                metadata: accounts.nft_metadata.to_account_info(),
+                token_program: accounts.token_program.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                rent: accounts.rent.to_account_info(),
+            },
+            &[signer_seeds],
+        ),
+        Some(0),
+    )?;
+
+    let program = accounts.metadata_program.to_account_info();
+    let payer = accounts.minter.to_account_info();
+    let nft_metadata = accounts.nft_metadata.to_account_info();
+    let collection_authority = launch.to_account_info();
+    let collection_mint = accounts.collection_mint.to_account_info();
+    let collection_metadata = accounts.collection_metadata.to_account_info();
+    let collection_master_edition = accounts.collection_master_edition.to_account_info();
+    if accounts.collection_metadata.collection_details.is_some() {
+        metadata::verify_sized_collection_item(
+            CpiContext::new_with_signer(
+                program,
+                VerifySizedCollectionItem {
+                    payer,
+                    metadata: nft_metadata,
+                    collection_authority,
+                    collection_mint,
+                    collection_metadata,
+                    collection_master_edition,
+                },
+                &[signer_seeds],
+            ),
+            None,
+        )
+    } else {
+        metadata::verify_collection(
+            CpiContext::new_with_signer(
+                program,
+                VerifyCollection {
+                    payer,
+                    metadata: nft_metadata,
+                    collection_authority,
+                    collection_mint,
+                    collection_metadata,
+                    collection_master_edition,
+                },
+                &[signer_seeds],
+            ),
+            None,
+        )
+    }
+}
+
+/// Metadata of edition `edition`. The collection is created unverified and
+/// verified by the launch PDA right after, in `mint_edition`.
+pub fn edition_data(launch: &Launch, edition: u32) -> DataV2 {
+    DataV2 {
+        name: format!("{} #{}", launch.name, edition),
+        symbol: launch.symbol.clone(),
+        uri: format!("{}{}.json", launch.base_uri, edition),
+        seller_fee_basis_points: launch.seller_fee_bps,
+        creators: Some(vec![Creator {
+            address: launch.creator,
+            verified: false,
+            share: 100,
+        }]),
+        collection: Some(Collection {
+            verified: false,
+            key: launch.collection_mint,
+        }),
+        uses: None,
+    }
+}
+
+/// Merkle leaf committing a wallet to a phase allowlist.
+pub fn allowlist_leaf(wallet: &Pubkey) -> [u8; 32] {
+    hashv(&[wallet.as_ref()]).to_bytes()
+}
+
+#[error_code]
+pub enum LaunchpadError {
+    #[msg("Launch name must be between 1 and 24 bytes.")]
+    InvalidLaunchName,
+    #[msg("Launch symbol exceeds 10 bytes.")]
+    InvalidLaunchSymbol,
+    #[msg("Base URI must be between 1 and 160 bytes.")]
+    InvalidBaseUri,
+    #[msg("Seller fee exceeds 100%.")]
+    InvalidRoyalty,
+    #[msg("Max supply must be greater than zero.")]
+    InvalidMaxSupply,
+    #[msg("Phase end must be after its start.")]
+    InvalidPhaseWindow,
+    #[msg("Per-wallet limit must be greater than zero.")]
+    InvalidWalletLimit,
+    #[msg("Phase is not an allowlist phase.")]
+    NotAllowlistPhase,
+    #[msg("Phase is not currently active.")]
+    PhaseNotActive,
+    #[msg("Wallet is not on the phase allowlist.")]
+    NotAllowlisted,
+    #[msg("Wallet has reached the phase mint limit.")]
+    WalletLimitReached,
+    #[msg("Launch is sold out.")]
+    SoldOut,
+    #[msg("Token payment accounts are required for this phase.")]
+    MissingPaymentAccounts,
+    #[msg("Payment token account mint does not match the phase.")]
+    PaymentMintMismatch,
+    #[msg("Payment token account owner does not match the expected wallet.")]
+    PaymentAccountOwnerMismatch,
+    #[msg("Developer wallet must match the configured developer address.")]
+    InvalidDeveloperWallet,
+    #[msg("Revenue destination does not match the launchpad configuration.")]
+    RevenueDestinationMismatch,
+    #[msg("Signer is not the launchpad authority.")]
+    UnauthorizedLaunchpadAuthority,
+    #[msg("Reward authority does not match vault configuration.")]
+    UnauthorizedRewardAuthority,
+    #[msg("Reward mint mismatch.")]
+    MismatchedRewardMint,
+    #[msg("Loyalty authority does not match registry configuration.")]
+    UnauthorizedLoyaltyAuthority,
+    #[msg("Arithmetic overflow encountered.")]
+    MathOverflow,
+    #[msg("Collection accounts do not match the launch collection.")]
+    CollectionMismatch,
+    #[msg("The launch PDA is not the collection's update authority.")]
+    CollectionAuthorityMismatch,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_utils::assert_error;
+
+    const START_TS: i64 = 1_700_000_000;
+
+    fn pubkey(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn phase(kind: PhaseKind, end_ts: Option<i64>, per_wallet_limit: u32) -> LaunchPhase {
+        LaunchPhase {
+            bump: 255,
+            launch: pubkey(1),
+            index: 0,
+            kind,
+            price: 1_000_000_000,
+            payment_mint: None,
+            start_ts: START_TS,
+            end_ts,
+            per_wallet_limit,
+            minted: 0,
+        }
+    }
+
+    #[test]
+    fn phase_is_open_from_start_until_before_end() {
+        let minter = pubkey(2);
+        let bounded = phase(PhaseKind::Public, Some(START_TS + 3_600), 1);
+        assert_error(
+            bounded.ensure_admits(&minter, START_TS - 1, &[]),
+            LaunchpadError::PhaseNotActive,
+        );
+        bounded.ensure_admits(&minter, START_TS, &[]).unwrap();
+        bounded
+            .ensure_admits(&minter, START_TS + 3_599, &[])
+            .unwrap();
+        assert_error(
+            bounded.ensure_admits(&minter, START_TS + 3_600, &[]),
+            LaunchpadError::PhaseNotActive,
+        );
+
+        let open_ended = phase(PhaseKind::Public, None, 1);
+        open_ended.ensure_admits(&minter, i64::MAX, &[]).unwrap();
+    }
+
+    #[test]
+    fn allowlist_phase_admits_only_wallets_under_the_root() {
+        let wallets = [pubkey(3), pubkey(4), pubkey(5), pubkey(6)];
+        let leaves = wallets.map(|wallet| allowlist_leaf(&wallet));
+        let left = merkle_proof::hash_pair(leaves[0], leaves[1]);
+        let right = merkle_proof::hash_pair(leaves[2], leaves[3]);
+        let merkle_root = merkle_proof::hash_pair(left, right);
+        let allowlist = phase(PhaseKind::Allowlist { merkle_root }, None, 1);
+
+        allowlist
+            .ensure_admits(&wallets[2], START_TS, &[leaves[3], left])
+            .unwrap();
+        allowlist
+            .ensure_admits(&wallets[1], START_TS, &[leaves[0], right])
+            .unwrap();
+        for (wallet, proof) in [
+            (pubkey(7), vec![leaves[3], left]),
+            (wallets[2], vec![leaves[2], left]),
+            (wallets[2], vec![leaves[3]]),
+            (wallets[0], vec![]),
+        ] {
+            assert_error(
+                allowlist.ensure_admits(&wallet, START_TS, &proof),
+                LaunchpadError::NotAllowlisted,
+            );
+        }
+        assert_error(
+            allowlist.ensure_admits(&wallets[2], START_TS - 1, &[leaves[3], left]),
+            LaunchpadError::PhaseNotActive,
+        );
+    }
+
+    #[test]
+    fn wallet_limit_caps_mints_per_phase() {
+        let mut record = MintRecord {
+            bump: 255,
+            phase: pubkey(8),
+            wallet: pubkey(9),
+            minted: 0,
+        };
+        record.record_mint(2).unwrap();
+        record.record_mint(2).unwrap();
+        assert_error(record.record_mint(2), LaunchpadError::WalletLimitReached);
+        assert_eq!(record.minted, 2);
+    }
+
+    #[test]
+    fn editions_are_numbered_from_one_until_sold_out() {
+        let mut launch = Launch {
+            bump: 255,
+            creator: pubkey(10),
+            launch_id: 1,
+            collection_mint: pubkey(11),
+            name: "Launch".to_string(),
+            symbol: "LCH".to_string(),
+            base_uri: "https://example.com/".to_string(),
+            seller_fee_bps: 500,
+            max_supply: 2,
+            minted: 0,
+            phase_count: 1,
+            creation_ts: START_TS,
+        };
+        assert_eq!(launch.take_next_edition().unwrap(), 1);
+        assert_eq!(launch.take_next_edition().unwrap(), 2);
+        assert_error(launch.take_next_edition(), LaunchpadError::SoldOut);
+        assert_eq!(launch.minted, 2);
+    }
+
+    #[test]
+    fn revenue_split_pays_out_the_whole_price() {
+        for price in [0, 1, 999, 1_000_000_000, u64::MAX] {
+            let split = compute_revenue_split(price).unwrap();
+            assert_eq!(
+                split.creator_proceeds
+                    + split.developer_cut
+                    + split.reward_pool_cut
+                    + split.ops_cut,
+                price
+            );
+        }
+
+        // 1.5% of 1,000 is 15 lamports: 10 to the developer and 3.75 / 1.25
+        // between the reward pool and ops, the spare lamport going to the
+        // larger remainder.
+        let split = compute_revenue_split(1_000).unwrap();
+        assert_eq!(split.developer_cut, 10);
+        assert_eq!(split.reward_pool_cut, 4);
+        assert_eq!(split.ops_cut, 1);
+        assert_eq!(split.creator_proceeds, 985);
+    }
+}
@@ -91,14 +91,7 @@ pub mod loyalty_registry {
             LoyaltyError::Unauthorized
         );
 
-        let computed_points = volume_lamports
-            .checked_mul(config.points_per_sol)
-            .ok_or(LoyaltyError::MathOverflow)?
-            .checked_div(LAMPORTS_PER_SOL)
-            .ok_or(LoyaltyError::MathOverflow)?;
-        let total_points = computed_points
-            .checked_add(bonus_points)
-            .ok_or(LoyaltyError::MathOverflow)?;
+        let total_points = config.points_for_activity(volume_lamports, bonus_points)?;
 
         profile.total_volume = profile
             .total_volume
@@ -285,6 +278,19 @@ impl RegistryConfig {
         + 8 // points per sol
         + 4 // total profiles
         + 8; // last updated timestamp
+
+    /// Points awarded by `record_activity` for the given volume and bonus.
+    pub fn points_for_activity(&self, volume_lamports: u64, bonus_points: u64) -> Result<u64> {
+        let computed_points = volume_lamports
+            .checked_mul(self.points_per_sol)
+            .ok_or(LoyaltyError::MathOverflow)?
+            .checked_div(LAMPORTS_PER_SOL)
+            .ok_or(LoyaltyError::MathOverflow)?;
+        let total_points = computed_points
+            .checked_add(bonus_points)
+            .ok_or(LoyaltyError::MathOverflow)?;
+        Ok(total_points)
+    }
 }
 
 #[account]
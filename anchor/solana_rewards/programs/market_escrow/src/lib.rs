@@ -1,17 +1,58 @@
 #![allow(unexpected_cfgs)]
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
 use anchor_lang::system_program;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::{Metadata, MetadataAccount};
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
 
+use clout_staking::{RevenuePool, StakePosition, StakingPool, REVENUE_POOL_SEED};
+use fee_engine::{Allocation, Rate};
 use loyalty_registry::program::LoyaltyRegistry as LoyaltyRegistryProgram;
-use loyalty_registry::{self, LoyaltyProfile, RegistryConfig, PROFILE_SEED, REGISTRY_CONFIG_SEED};
+use loyalty_registry::{
+    self, LoyaltyProfile, LoyaltyTier, RegistryConfig, PROFILE_SEED, REGISTRY_CONFIG_SEED,
+};
 use rewards_vault::program::RewardsVault as RewardsVaultProgram;
 use rewards_vault::{self, VaultConfig, VAULT_SIGNER_SEED};
 
 pub const LISTING_SEED: &[u8] = b"listing";
 pub const ESCROW_VAULT_SEED: &[u8] = b"escrow";
 pub const RECEIPT_SEED: &[u8] = b"receipt";
+pub const COLLECTION_STATS_SEED: &[u8] = b"collection-stats";
+pub const COLLECTION_ENTRY_SEED: &[u8] = b"collection-entry";
+pub const BLOCKLIST_PAGE_SEED: &[u8] = b"blocklist";
+pub const MINT_SALE_RECORD_SEED: &[u8] = b"mint-sale";
+pub const PAIR_VOLUME_SEED: &[u8] = b"pair-volume";
+pub const CLOUT_FEE_ORACLE_SEED: &[u8] = b"clout-fee-oracle";
+pub const INSTALLMENT_PLAN_SEED: &[u8] = b"installment-plan";
+pub const RAFFLE_SEED: &[u8] = b"raffle";
+pub const RAFFLE_TICKET_PAGE_SEED: &[u8] = b"raffle-tickets";
+pub const TRAIT_OFFER_SEED: &[u8] = b"trait-offer";
+pub const ORDER_AUTHORITY_SEED: &[u8] = b"order-authority";
+pub const ORDER_NONCE_SEED: &[u8] = b"order-nonce";
+pub const TRADE_HISTORY_SEED: &[u8] = b"trade-history";
+/// Domain separator prefixed to every signed order message.
+pub const SIGNED_ORDER_DOMAIN: &[u8] = b"market_escrow:signed-order:v1";
+pub const MARKET_CONFIG_SEED: &[u8] = b"market-config";
+pub const SEALED_AUCTION_SEED: &[u8] = b"sealed-auction";
+pub const SEALED_BID_SEED: &[u8] = b"sealed-bid";
+const METADATA_SEED: &[u8] = b"metadata";
+/// Remaining accounts supplied per listing to `settle_many`, in order:
+/// listing, escrow vault, seller (signer), buyer, royalty destination,
+/// receipt, buyer reward token account, buyer loyalty profile, NFT metadata
+/// PDA (which may not exist), collection stats (or this program's id when the
+/// NFT has no verified collection; created on the collection's first sale),
+/// mint sale record, pair volume record, buyer trade history, seller trade
+/// history. Listings with payout destinations append those wallets, in
+/// order, after the fixed accounts (see `SettleManyEntry`).
+pub const SETTLE_MANY_ACCOUNTS_PER_LISTING: usize = 14;
 const BPS_DENOMINATOR: u64 = 10_000;
 const PLATFORM_FEE_BPS: u16 = 150; // 1.50%
 const DEVELOPER_FEE_BPS: u16 = 100; // 1.00%
@@ -22,6 +63,23 @@ const RATIO_DENOMINATOR: u64 = REWARD_FUND_RATIO_NUMERATOR + OPS_TREASURY_RATIO_
 const OPS_TREASURY_BPS: u16 =
     ((REMAINING_FEE_BPS as u64 * OPS_TREASURY_RATIO_NUMERATOR) / RATIO_DENOMINATOR) as u16; // ~0.125%
 const REWARD_POOL_BPS: u16 = REMAINING_FEE_BPS - OPS_TREASURY_BPS; // ~0.375%
+const MAX_EXPIRY_BOUNTY_BPS: u16 = 1_000; // 10% of reclaimed listing rent
+const MAX_PLATFORM_FEE_OVERRIDE_BPS: u16 = 500; // 5.00%
+const MIN_REDEMPTION_WINDOW_SECS: u32 = 24 * 60 * 60; // one day to confirm delivery
+const MAX_CLOUT_FEE_DISCOUNT_BPS: u16 = 5_000; // 50% off the platform fee
+const MAX_BUYER_CANCEL_WINDOW_SECS: u32 = 24 * 60 * 60; // buyers may back out for at most a day
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+const MAX_INSTALLMENTS: u8 = 24;
+/// Wallets a listing's seller proceeds can be split between.
+pub const MAX_PAYOUT_DESTINATIONS: usize = 4;
+const RAFFLE_REVEAL_WINDOW_SECS: i64 = 24 * 60 * 60; // seller reveals within a day of the close
+/// Ticket purchases recorded per raffle ledger page.
+pub const RAFFLE_TICKET_PAGE_CAPACITY: usize = 64;
+/// Blocked wallets are sharded into pages by the first byte of their key.
+pub const BLOCKLIST_PAGE_CAPACITY: usize = 128;
+/// Recent trades kept per wallet; older ones are overwritten.
+pub const TRADE_HISTORY_CAPACITY: usize = 32;
+const ROLLING_AVERAGE_WEIGHT_BPS: u64 = 2_000; // 20% weight on the newest sale
 const DEVELOPER_WALLET: Pubkey = pubkey!("FsoPx1WmXA6FDxYTSULRDko3tKbNG7KxdRTq2icQJGjM");
 
 declare_id!("8um9wXkGXVuxs9jVCpt3DrzkmMAiLDKrKkaHSLyPqPcX");
@@ -30,8 +88,13 @@ declare_id!("8um9wXkGXVuxs9jVCpt3DrzkmMAiLDKrKkaHSLyPqPcX");
 pub mod market_escrow {
     use super::*;
 
-    /// Creates a new listing PDA along with an escrow vault that will hold SOL
-    /// until the sale settles.
+    /// Creates a new listing PDA and funds the rent-exempt floor of its escrow
+    /// vault, which holds SOL until the sale settles. An optional early-access window restricts
+    /// buyers for the first seconds after creation. NFTs from a verified
+    /// collection must satisfy the collection registry: banned collections
+    /// are rejected and the registry's royalty policy and fee override apply.
+    /// Seller proceeds go to the seller unless `payout_destinations` splits
+    /// them between other wallets by share.
     #[allow(clippy::too_many_arguments)]
     pub fn create_listing(
         ctx: Context<CreateListing>,
@@ -41,11 +104,48 @@ pub mod market_escrow {
         royalty_bps: u16,
         treasury_bps: u16,
         marketplace_fee_bps: u16,
+        early_access: Option<EarlyAccess>,
+        payout_destinations: Vec<PayoutDestination>,
     ) -> Result<()> {
         require!(price_lamports > 0, EscrowError::InvalidListingPrice);
-        validate_fee_configuration(royalty_bps, treasury_bps, marketplace_fee_bps)?;
+        ensure_not_blocked(
+            &ctx.accounts.seller.key(),
+            &ctx.accounts.seller_blocklist_page,
+            EscrowError::SellerBlocked,
+        )?;
+        let collection_entry = load_collection_entry(
+            &ctx.accounts.nft_metadata,
+            ctx.accounts.collection_entry.as_ref(),
+        )?;
+        let platform_fee_override_bps = match &collection_entry {
+            Some(entry) => {
+                require!(
+                    entry.status != CollectionStatus::Banned,
+                    EscrowError::CollectionBanned
+                );
+                entry
+                    .royalty_policy
+                    .check(royalty_bps, &ctx.accounts.royalty_destination.key())?;
+                entry.platform_fee_override_bps
+            }
+            None => None,
+        };
+        validate_fee_configuration(
+            royalty_bps,
+            treasury_bps,
+            marketplace_fee_bps,
+            platform_fee_override_bps,
+        )?;
+        if let Some(early_access) = early_access {
+            require!(
+                early_access.window_secs > 0,
+                EscrowError::InvalidEarlyAccessWindow
+            );
+        }
+        validate_payout_destinations(&payout_destinations)?;
 
         let listing = &mut ctx.accounts.listing;
+        listing.version = Listing::VERSION;
         listing.bump = ctx.bumps.listing;
         listing.escrow_bump = ctx.bumps.escrow_vault;
         listing.seller = ctx.accounts.seller.key();
@@ -56,18 +156,34 @@ pub mod market_escrow {
         listing.creation_ts = Clock::get()?.unix_timestamp;
         listing.expiration_ts = expiration_ts;
         listing.sale_ts = None;
+        listing.buyer_cancel_deadline_ts = None;
         listing.settlement_ts = None;
         listing.status = ListingStatus::Active;
         listing.royalty_bps = royalty_bps;
         listing.royalty_destination = ctx.accounts.royalty_destination.key();
         listing.treasury_bps = OPS_TREASURY_BPS;
         listing.marketplace_fee_bps = REWARD_POOL_BPS;
+        listing.sale_mode = SaleMode::FixedPrice;
+        listing.early_access = early_access;
+        listing.platform_fee_override_bps = platform_fee_override_bps;
+        listing.payout_destinations = payout_destinations;
+        listing.rent_payer = ctx.accounts.payer.key();
 
-        let escrow = &mut ctx.accounts.escrow_vault;
-        escrow.bump = ctx.bumps.escrow_vault;
-        escrow.listing = listing.key();
-        escrow.total_deposited = 0;
-
+        let floor_top_up = Rent::get()?
+            .minimum_balance(0)
+            .saturating_sub(ctx.accounts.escrow_vault.lamports());
+        if floor_top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: ctx.accounts.escrow_vault.to_account_info(),
+                    },
+                ),
+                floor_top_up,
+            )?;
+        }
         Ok(())
     }
 
@@ -79,7 +195,11 @@ pub mod market_escrow {
             EscrowError::ListingNotActive
         );
         require!(
-            ctx.accounts.escrow_vault.total_deposited == 0,
+            listing.sale_mode == SaleMode::FixedPrice,
+            EscrowError::ListingInAuction
+        );
+        require!(
+            escrow_balance(&ctx.accounts.escrow_vault)? == 0,
             EscrowError::OutstandingEscrowBalance
         );
 
@@ -89,13 +209,29 @@ pub mod market_escrow {
     }
 
     /// Buyer transfers SOL into escrow. Once deposited, the listing moves into
-    /// a pending settlement state awaiting admin settlement.
+    /// a pending settlement state awaiting admin settlement. When the market
+    /// has a buyer cancellation window, the sale cannot settle until it
+    /// passes and the buyer may call `cancel_purchase` until then.
     pub fn execute_sale(ctx: Context<ExecuteSale>) -> Result<()> {
+        ensure_not_blocked(
+            &ctx.accounts.seller.key(),
+            &ctx.accounts.seller_blocklist_page,
+            EscrowError::SellerBlocked,
+        )?;
+        ensure_not_blocked(
+            &ctx.accounts.buyer.key(),
+            &ctx.accounts.buyer_blocklist_page,
+            EscrowError::BuyerBlocked,
+        )?;
         let listing = &mut ctx.accounts.listing;
         require!(
             listing.status == ListingStatus::Active,
             EscrowError::ListingNotActive
         );
+        require!(
+            listing.sale_mode == SaleMode::FixedPrice,
+            EscrowError::ListingInAuction
+        );
 
         if let Some(expiration) = listing.expiration_ts {
             require!(
@@ -105,7 +241,7 @@ pub mod market_escrow {
         }
 
         require!(
-            ctx.accounts.escrow_vault.total_deposited == 0,
+            escrow_balance(&ctx.accounts.escrow_vault)? == 0,
             EscrowError::EscrowAlreadyFunded
         );
         require_keys_eq!(
@@ -113,42 +249,72 @@ pub mod market_escrow {
             ctx.accounts.seller.key(),
             EscrowError::SellerMismatch
         );
+        check_early_access(
+            listing,
+            &ctx.accounts.buyer.key(),
+            Clock::get()?.unix_timestamp,
+            ctx.accounts.buyer_loyalty_profile.as_deref(),
+            ctx.accounts.buyer_stake_position.as_deref(),
+            Some(&ctx.accounts.market_config),
+        )?;
 
-        system_program::transfer(
-            CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                system_program::Transfer {
-                    from: ctx.accounts.buyer.to_account_info(),
-                    to: ctx.accounts.escrow_vault.to_account_info(),
-                },
-            ),
+        pay_into_escrow(
+            &ctx.accounts.buyer,
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.system_program,
             listing.price_lamports,
         )?;
 
-        let escrow = &mut ctx.accounts.escrow_vault;
-        escrow.total_deposited = escrow
-            .total_deposited
-            .checked_add(listing.price_lamports)
-            .ok_or(EscrowError::MathOverflow)?;
-
+        let now = Clock::get()?.unix_timestamp;
         listing.status = ListingStatus::PendingSettlement;
         listing.buyer = Some(ctx.accounts.buyer.key());
-        listing.sale_ts = Some(Clock::get()?.unix_timestamp);
+        listing.sale_ts = Some(now);
+        let cancel_window_secs = ctx.accounts.market_config.buyer_cancel_window_secs;
+        listing.buyer_cancel_deadline_ts = if cancel_window_secs > 0 {
+            Some(
+                now.checked_add(cancel_window_secs as i64)
+                    .ok_or(EscrowError::MathOverflow)?,
+            )
+        } else {
+            None
+        };
+        start_redemption_window(listing, now)?;
+        Ok(())
+    }
+
+    /// Buyer backs out of a direct purchase within the cancellation window.
+    /// The escrow is refunded and the listing returns to `Active` for sale
+    /// again. Once the window passes only `settle_sale` can complete it.
+    pub fn cancel_purchase(ctx: Context<CancelPurchase>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        revert_pending_purchase(&mut ctx.accounts.listing, &ctx.accounts.buyer.key(), now)?;
+
+        let escrow = ListingEscrow::new(
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.listing,
+            &ctx.accounts.system_program,
+        );
+        escrow.pay(&ctx.accounts.buyer, escrow.balance()?)?;
         Ok(())
     }
 
     /// Settles a sale by distributing escrowed funds, minting reward tokens,
-    /// and recording loyalty activity.
-    pub fn settle_sale(
-        ctx: Context<SettleSale>,
+    /// and recording loyalty activity. Rewards and loyalty activity are
+    /// withheld from sales that trip the wash-trading rules. Listings with
+    /// payout destinations pass those wallets, in order, as remaining
+    /// accounts.
+    pub fn settle_sale<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleSale<'info>>,
         reward_amount: u64,
         loyalty_bonus_points: u64,
     ) -> Result<()> {
-        let listing = &mut ctx.accounts.listing;
-        require!(
-            listing.status == ListingStatus::PendingSettlement,
-            EscrowError::ListingNotPending
+        let escrow = ListingEscrow::new(
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.listing,
+            &ctx.accounts.system_program,
         );
+        let listing = &mut ctx.accounts.listing;
+        ensure_ready_to_settle(listing, Clock::get()?.unix_timestamp)?;
         let buyer_key = listing.buyer.ok_or(EscrowError::MissingBuyer)?;
         require_keys_eq!(
             buyer_key,
@@ -160,10 +326,6 @@ pub mod market_escrow {
             ctx.accounts.royalty_destination.key(),
             EscrowError::RoyaltyDestinationMismatch
         );
-        require!(
-            ctx.accounts.escrow_vault.total_deposited >= listing.price_lamports,
-            EscrowError::InsufficientEscrowBalance
-        );
         require_keys_eq!(
             ctx.accounts.developer_wallet.key(),
             DEVELOPER_WALLET,
@@ -171,75 +333,87 @@ pub mod market_escrow {
         );
 
         let price = listing.price_lamports;
-        let royalty_cut = compute_fee(price, listing.royalty_bps)?;
-        let developer_cut = compute_fee(price, DEVELOPER_FEE_BPS)?;
-        let remaining_platform_fee = compute_fee(price, REMAINING_FEE_BPS)?;
-        let reward_pool_cut = remaining_platform_fee
-            .checked_mul(REWARD_FUND_RATIO_NUMERATOR)
-            .ok_or(EscrowError::MathOverflow)?
-            .checked_div(RATIO_DENOMINATOR)
-            .ok_or(EscrowError::MathOverflow)?;
-        let ops_cut = remaining_platform_fee
-            .checked_sub(reward_pool_cut)
-            .ok_or(EscrowError::MathOverflow)?;
-        let platform_cut = developer_cut
-            .checked_add(reward_pool_cut)
-            .ok_or(EscrowError::MathOverflow)?
-            .checked_add(ops_cut)
-            .ok_or(EscrowError::MathOverflow)?;
-        let total_fees = royalty_cut
-            .checked_add(platform_cut)
-            .ok_or(EscrowError::MathOverflow)?;
-        require!(total_fees <= price, EscrowError::InvalidFeeConfiguration);
-        let seller_payout = price
-            .checked_sub(total_fees)
-            .ok_or(EscrowError::MathOverflow)?;
+        let split = listing_sale_split(listing)?;
+        let escrowed_amount = split.escrowed_amount()?;
+        require!(
+            escrow.balance()? >= escrowed_amount,
+            EscrowError::InsufficientEscrowBalance
+        );
+        let SaleSplit {
+            seller_payout,
+            royalty_cut,
+            developer_cut,
+            reward_pool_cut,
+            ops_cut,
+        } = split;
 
-        disburse(
-            &ctx.accounts.escrow_vault.to_account_info(),
-            &ctx.accounts.seller.to_account_info(),
+        let seller_payouts = pay_seller_proceeds(
+            &escrow,
+            &ctx.accounts.seller,
+            &listing.payout_destinations,
+            ctx.remaining_accounts,
             seller_payout,
         )?;
-        if royalty_cut > 0 {
-            disburse(
-                &ctx.accounts.escrow_vault.to_account_info(),
-                &ctx.accounts.royalty_destination.to_account_info(),
-                royalty_cut,
-            )?;
-        }
-        if developer_cut > 0 {
-            disburse(
-                &ctx.accounts.escrow_vault.to_account_info(),
-                &ctx.accounts.developer_wallet.to_account_info(),
-                developer_cut,
-            )?;
-        }
-        if reward_pool_cut > 0 {
-            disburse(
-                &ctx.accounts.escrow_vault.to_account_info(),
-                &ctx.accounts.rewards_pool_destination.to_account_info(),
-                reward_pool_cut,
-            )?;
-        }
-        if ops_cut > 0 {
-            disburse(
-                &ctx.accounts.escrow_vault.to_account_info(),
-                &ctx.accounts.ops_treasury_destination.to_account_info(),
-                ops_cut,
-            )?;
-        }
-
-        ctx.accounts.escrow_vault.total_deposited = ctx
-            .accounts
-            .escrow_vault
-            .total_deposited
-            .checked_sub(price)
-            .ok_or(EscrowError::MathOverflow)?;
+        escrow.pay(&ctx.accounts.royalty_destination, royalty_cut)?;
+        escrow.pay(&ctx.accounts.developer_wallet, developer_cut)?;
+        escrow.pay(&ctx.accounts.rewards_pool_destination, reward_pool_cut)?;
+        escrow.pay(&ctx.accounts.ops_treasury_destination, ops_cut)?;
 
         let now = Clock::get()?.unix_timestamp;
         listing.status = ListingStatus::Settled;
         listing.settlement_ts = Some(now);
 
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let metadata = load_optional_metadata(&ctx.accounts.nft_metadata)?;
+        if let Some(collection) = metadata.as_ref().and_then(verified_collection) {
+            let stats_info = ctx
+                .accounts
+                .collection_stats
+                .as_ref()
+                .ok_or(EscrowError::MissingCollectionStats)?;
+            let bump = collection_stats_bump(stats_info, &collection)?;
+            let mut stats = Vec::new();
+            record_collection_sale(
+                &mut stats,
+                stats_info,
+                &collection,
+                bump,
+                price,
+                now,
+                &payer,
+                &system_program,
+            )?;
+            for (info, stats) in stats.iter() {
+                stats.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+            }
+        }
+
+        let mint_sale_record = &mut ctx.accounts.mint_sale_record;
+        mint_sale_record.bump = ctx.bumps.mint_sale_record;
+        mint_sale_record.mint = listing.mint;
+        let pair_volume = &mut ctx.accounts.pair_volume;
+        pair_volume.bump = ctx.bumps.pair_volume;
+        let (wallet_a, wallet_b) = ordered_pair(&listing.seller, &buyer_key);
+        pair_volume.wallet_a = *wallet_a;
+        pair_volume.wallet_b = *wallet_b;
+        let suppression = assess_wash_trading(
+            &ctx.accounts.market_config.wash_trading_rules,
+            mint_sale_record,
+            pair_volume,
+            &listing.seller,
+            &buyer_key,
+            price,
+            now,
+        )?;
+        let (reward_amount, loyalty_bonus_points) = match suppression {
+            Some(reason) => {
+                msg!("Rewards suppressed: {:?}", reason);
+                (0, 0)
+            }
+            None => (reward_amount, loyalty_bonus_points),
+        };
+
         // Mint buyer rewards if configured.
         if reward_amount > 0 {
             require_keys_eq!(
@@ -267,19 +441,22 @@ pub mod market_escrow {
         }
 
         // Update loyalty registry.
-        let loyalty_ctx = CpiContext::new(
-            ctx.accounts.loyalty_program.to_account_info(),
-            loyalty_registry::cpi::accounts::RecordActivity {
-                actor: ctx.accounts.buyer.to_account_info(),
-                profile: ctx.accounts.loyalty_profile.to_account_info(),
-                registry_config: ctx.accounts.loyalty_registry_config.to_account_info(),
-                authority: ctx.accounts.loyalty_authority.to_account_info(),
-            },
-        );
-        loyalty_registry::cpi::record_activity(loyalty_ctx, price, loyalty_bonus_points)?;
+        if suppression.is_none() {
+            let loyalty_ctx = CpiContext::new(
+                ctx.accounts.loyalty_program.to_account_info(),
+                loyalty_registry::cpi::accounts::RecordActivity {
+                    actor: ctx.accounts.buyer.to_account_info(),
+                    profile: ctx.accounts.loyalty_profile.to_account_info(),
+                    registry_config: ctx.accounts.loyalty_registry_config.to_account_info(),
+                    authority: ctx.accounts.loyalty_authority.to_account_info(),
+                },
+            );
+            loyalty_registry::cpi::record_activity(loyalty_ctx, price, loyalty_bonus_points)?;
+        }
 
         // Persist receipt for analytics / auditing.
         let receipt = &mut ctx.accounts.receipt;
+        receipt.version = SaleReceipt::VERSION;
         receipt.bump = ctx.bumps.receipt;
         receipt.listing = listing.key();
         receipt.buyer = buyer_key;
@@ -293,319 +470,7390 @@ pub mod market_escrow {
         receipt.rewards_minted = reward_amount;
         receipt.loyalty_points_awarded = loyalty_bonus_points;
         receipt.timestamp = now;
+        receipt.rewards_suppressed = suppression;
+        let clout_fee_payment = listing.clout_fee_payment.unwrap_or_default();
+        receipt.clout_fee_paid = clout_fee_payment.clout_paid;
+        receipt.clout_fee_burned = clout_fee_payment.clout_burned;
+        receipt.seller_payouts = seller_payouts;
+        receipt.sequence = next_sale_sequence(&mut ctx.accounts.market_config)?;
+
+        let mut histories = Vec::new();
+        for (info, wallet, bump, side) in [
+            (
+                &ctx.accounts.buyer_trade_history,
+                buyer_key,
+                ctx.bumps.buyer_trade_history,
+                TradeSide::Buy,
+            ),
+            (
+                &ctx.accounts.seller_trade_history,
+                listing.seller,
+                ctx.bumps.seller_trade_history,
+                TradeSide::Sell,
+            ),
+        ] {
+            let entry = TradeHistoryEntry {
+                receipt: receipt.key(),
+                sequence: receipt.sequence,
+                side,
+            };
+            record_trade(
+                &mut histories,
+                info,
+                &wallet,
+                bump,
+                entry,
+                &payer,
+                &system_program,
+            )?;
+        }
+        for (info, history) in histories.iter() {
+            history.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        }
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(listing_id: u64)]
-pub struct CreateListing<'info> {
-    #[account(
-        init,
-        payer = seller,
-        space = Listing::LEN,
-        seeds = [LISTING_SEED, seller.key().as_ref(), nft_mint.key().as_ref(), &listing_id.to_le_bytes()],
-        bump
-    )]
-    pub listing: Account<'info, Listing>,
-    #[account(
-        init,
-        payer = seller,
-        space = EscrowVault::LEN,
-        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
-        bump
-    )]
-    pub escrow_vault: Account<'info, EscrowVault>,
-    #[account(mut)]
-    pub seller: Signer<'info>,
-    pub nft_mint: Account<'info, Mint>,
-    /// CHECK: stored and verified during settlement
-    pub royalty_destination: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Settles a batch of pending listings passed via remaining accounts. A
+    /// listing that fails validation is skipped and reported in the returned
+    /// outcomes instead of aborting the batch; failures inside reward or
+    /// loyalty CPIs still abort the transaction.
+    pub fn settle_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleMany<'info>>,
+        entries: Vec<SettleManyEntry>,
+    ) -> Result<Vec<SettlementOutcome>> {
+        require!(!entries.is_empty(), EscrowError::InvalidSettlementBatch);
+        let account_groups = split_settlement_accounts(ctx.remaining_accounts, &entries)?;
+        require_keys_eq!(
+            ctx.accounts.developer_wallet.key(),
+            DEVELOPER_WALLET,
+            EscrowError::InvalidDeveloperWallet
+        );
+        if entries.iter().any(|entry| entry.reward_amount > 0) {
+            require_keys_eq!(
+                ctx.accounts.reward_vault.authority,
+                ctx.accounts.reward_authority.key(),
+                EscrowError::UnauthorizedRewardAuthority
+            );
+        }
 
-#[derive(Accounts)]
-pub struct CancelListing<'info> {
-    #[account(mut, has_one = seller)]
-    pub listing: Account<'info, Listing>,
-    #[account(
-        mut,
-        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
-        bump = listing.escrow_bump
-    )]
-    pub escrow_vault: Account<'info, EscrowVault>,
-    pub seller: Signer<'info>,
-}
+        let reward_mint = ctx.accounts.reward_mint.key();
+        let batch: Vec<_> = entries.iter().zip(account_groups).collect();
+        let (mut pending, outcomes) = partition_settlement_batch(
+            batch.iter().map(|(_, accounts)| accounts[0].key()),
+            |index| {
+                let (entry, accounts) = batch[index];
+                load_pending_settlement(accounts, entry, &reward_mint)
+            },
+        );
 
-#[derive(Accounts)]
-pub struct ExecuteSale<'info> {
-    #[account(mut, has_one = seller)]
-    pub listing: Account<'info, Listing>,
-    #[account(
-        mut,
-        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
-        bump = listing.escrow_bump
-    )]
-    pub escrow_vault: Account<'info, EscrowVault>,
-    #[account(mut)]
-    pub buyer: Signer<'info>,
-    /// CHECK: Seller receives payouts during settlement.
-    pub seller: UncheckedAccount<'info>,
-    pub system_program: Program<'info, System>,
-}
+        // Move escrowed lamports first so no reward or loyalty CPI observes a
+        // partial payout.
+        let system_program = ctx.accounts.system_program.to_account_info();
+        for settlement in pending.iter_mut() {
+            let split = settlement.split;
+            let escrow = ListingEscrow::new(
+                &settlement.accounts[1],
+                &settlement.listing,
+                &system_program,
+            );
+            settlement.seller_payouts = pay_seller_proceeds(
+                &escrow,
+                &settlement.accounts[2],
+                &settlement.listing.payout_destinations,
+                &settlement.accounts[SETTLE_MANY_ACCOUNTS_PER_LISTING..],
+                split.seller_payout,
+            )?;
+            escrow.pay(&settlement.accounts[4], split.royalty_cut)?;
+        }
+        // Platform cuts are pooled in the first escrow so each shared
+        // recipient gets a single transfer for the whole batch.
+        if let Some((first, rest)) = pending.split_first() {
+            let collector = ListingEscrow::new(&first.accounts[1], &first.listing, &system_program);
+            for settlement in rest {
+                let escrow = ListingEscrow::new(
+                    &settlement.accounts[1],
+                    &settlement.listing,
+                    &system_program,
+                );
+                escrow.pay(&first.accounts[1], settlement.split.platform_fee()?)?;
+            }
+            let platform_cuts =
+                sum_platform_cuts(pending.iter().map(|settlement| settlement.split))?;
+            collector.pay(&ctx.accounts.developer_wallet, platform_cuts.developer_cut)?;
+            collector.pay(
+                &ctx.accounts.rewards_pool_destination,
+                platform_cuts.reward_pool_cut,
+            )?;
+            collector.pay(
+                &ctx.accounts.ops_treasury_destination,
+                platform_cuts.ops_cut,
+            )?;
+        }
 
-#[derive(Accounts)]
-pub struct SettleSale<'info> {
-    #[account(mut, has_one = seller)]
-    pub listing: Account<'info, Listing>,
-    #[account(
-        mut,
-        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
-        bump = listing.escrow_bump
-    )]
-    pub escrow_vault: Account<'info, EscrowVault>,
-    #[account(mut)]
-    pub seller: Signer<'info>,
-    /// CHECK: buyer validated against listing.buyer
-    pub buyer: UncheckedAccount<'info>,
-    #[account(mut)]
-    pub developer_wallet: SystemAccount<'info>,
-    #[account(mut)]
-    pub rewards_pool_destination: SystemAccount<'info>,
-    #[account(mut)]
-    pub ops_treasury_destination: SystemAccount<'info>,
-    #[account(mut)]
-    pub royalty_destination: SystemAccount<'info>,
-    #[account(
-        init,
-        payer = seller,
-        space = SaleReceipt::LEN,
-        seeds = [RECEIPT_SEED, listing.key().as_ref(), buyer.key().as_ref()],
-        bump
-    )]
-    pub receipt: Account<'info, SaleReceipt>,
-    #[account(
-        mut,
-        has_one = reward_mint @ EscrowError::MismatchedRewardMint
-    )]
-    pub reward_vault: Account<'info, VaultConfig>,
-    /// CHECK: PDA signer validated via seeds.
-    #[account(
-        seeds = [VAULT_SIGNER_SEED, reward_mint.key().as_ref()],
-        bump = reward_vault.signer_bump
-    )]
-    pub vault_signer: UncheckedAccount<'info>,
-    #[account(mut)]
-    pub reward_mint: Account<'info, Mint>,
-    #[account(
-        mut,
-        constraint = buyer_reward_account.mint == reward_mint.key(),
-        constraint = buyer_reward_account.owner == buyer.key()
-    )]
-    pub buyer_reward_account: Account<'info, TokenAccount>,
-    pub reward_authority: Signer<'info>,
-    #[account(
-        mut,
-        seeds = [PROFILE_SEED, buyer.key().as_ref()],
-        bump = loyalty_profile.bump
-    )]
-    pub loyalty_profile: Account<'info, LoyaltyProfile>,
-    #[account(
-        mut,
-        seeds = [REGISTRY_CONFIG_SEED],
-        bump = loyalty_registry_config.bump,
-        constraint = loyalty_registry_config.authority == loyalty_authority.key() @ EscrowError::UnauthorizedLoyaltyAuthority
-    )]
-    pub loyalty_registry_config: Account<'info, RegistryConfig>,
-    pub loyalty_authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-    pub rewards_vault_program: Program<'info, RewardsVaultProgram>,
-    pub loyalty_program: Program<'info, LoyaltyRegistryProgram>,
-    pub system_program: Program<'info, System>,
-}
+        let now = Clock::get()?.unix_timestamp;
+        let payer = ctx.accounts.payer.to_account_info();
+        let mut collection_stats: Vec<(AccountInfo<'info>, CollectionStats)> = Vec::new();
+        let mut mint_sale_records: Vec<(AccountInfo<'info>, MintSaleRecord)> = Vec::new();
+        let mut pair_volumes: Vec<(AccountInfo<'info>, PairVolume)> = Vec::new();
+        let mut trade_histories: Vec<(AccountInfo<'info>, TradeHistory)> = Vec::new();
+        for mut settlement in pending {
+            let price = settlement.listing.price_lamports;
+            settlement.listing.status = ListingStatus::Settled;
+            settlement.listing.settlement_ts = Some(now);
+            settlement.listing.exit(&crate::ID)?;
 
-#[account]
-pub struct Listing {
-    pub bump: u8,
-    pub escrow_bump: u8,
-    pub seller: Pubkey,
-    pub buyer: Option<Pubkey>,
-    pub mint: Pubkey,
-    pub listing_id: u64,
-    pub price_lamports: u64,
-    pub creation_ts: i64,
-    pub expiration_ts: Option<i64>,
-    pub sale_ts: Option<i64>,
-    pub settlement_ts: Option<i64>,
-    pub status: ListingStatus,
-    pub royalty_bps: u16,
-    pub royalty_destination: Pubkey,
-    pub treasury_bps: u16,
-    pub marketplace_fee_bps: u16,
-}
+            if let Some((collection, bump)) = settlement.collection {
+                record_collection_sale(
+                    &mut collection_stats,
+                    &settlement.accounts[9],
+                    &collection,
+                    bump,
+                    price,
+                    now,
+                    &payer,
+                    &system_program,
+                )?;
+            }
 
-impl Listing {
-    pub const LEN: usize = 8 // discriminator
-        + 1 // bump
-        + 1 // escrow bump
-        + 32 // seller
-        + 1 + 32 // buyer option
-        + 32 // mint
-        + 8 // listing id
-        + 8 // price
-        + 8 // creation ts
-        + 1 + 8 // expiration option
-        + 1 + 8 // sale ts option
-        + 1 + 8 // settlement ts option
-        + 1 // status enum
-        + 2 // royalty bps
-        + 32 // royalty destination
-        + 2 // treasury bps
-        + 2; // marketplace fee bps
-}
+            let listing_key = settlement.listing.key();
+            let seller_key = settlement.accounts[2].key();
+            let buyer_key = settlement.accounts[3].key();
+            let clout_fee_payment = settlement.listing.clout_fee_payment.unwrap_or_default();
+            let mint = settlement.listing.mint;
+            let (wallet_a, wallet_b) = ordered_pair(&seller_key, &buyer_key);
+            let record_index = load_or_create_record(
+                &mut mint_sale_records,
+                &settlement.accounts[10],
+                &payer,
+                &system_program,
+                &[
+                    MINT_SALE_RECORD_SEED,
+                    mint.as_ref(),
+                    &[settlement.mint_sale_record_bump],
+                ],
+                MintSaleRecord::LEN,
+                || MintSaleRecord {
+                    bump: settlement.mint_sale_record_bump,
+                    mint,
+                    ..Default::default()
+                },
+            )?;
+            let pair_index = load_or_create_record(
+                &mut pair_volumes,
+                &settlement.accounts[11],
+                &payer,
+                &system_program,
+                &[
+                    PAIR_VOLUME_SEED,
+                    wallet_a.as_ref(),
+                    wallet_b.as_ref(),
+                    &[settlement.pair_volume_bump],
+                ],
+                PairVolume::LEN,
+                || PairVolume {
+                    bump: settlement.pair_volume_bump,
+                    wallet_a: *wallet_a,
+                    wallet_b: *wallet_b,
+                    ..Default::default()
+                },
+            )?;
+            let suppression = assess_wash_trading(
+                &ctx.accounts.market_config.wash_trading_rules,
+                &mut mint_sale_records[record_index].1,
+                &mut pair_volumes[pair_index].1,
+                &seller_key,
+                &buyer_key,
+                price,
+                now,
+            )?;
+            if let Some(reason) = suppression {
+                msg!("Rewards suppressed for {}: {:?}", listing_key, reason);
+                settlement.reward_amount = 0;
+                settlement.loyalty_bonus_points = 0;
+            }
 
+            let receipt_seeds: &[&[u8]] = &[
+                RECEIPT_SEED,
+                listing_key.as_ref(),
+                buyer_key.as_ref(),
+                &[settlement.receipt_bump],
+            ];
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::CreateAccount {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: settlement.accounts[5].clone(),
+                    },
+                    &[receipt_seeds],
+                ),
+                Rent::get()?.minimum_balance(SaleReceipt::LEN),
+                SaleReceipt::LEN as u64,
+                &crate::ID,
+            )?;
+            let receipt = SaleReceipt {
+                version: SaleReceipt::VERSION,
+                bump: settlement.receipt_bump,
+                listing: listing_key,
+                buyer: buyer_key,
+                seller: seller_key,
+                amount_paid: price,
+                seller_proceeds: settlement.split.seller_payout,
+                royalty_paid: settlement.split.royalty_cut,
+                treasury_paid: settlement.split.ops_cut,
+                marketplace_fee_paid: settlement.split.reward_pool_cut,
+                developer_paid: settlement.split.developer_cut,
+                rewards_minted: settlement.reward_amount,
+                loyalty_points_awarded: settlement.loyalty_bonus_points,
+                timestamp: now,
+                rewards_suppressed: suppression,
+                clout_fee_paid: clout_fee_payment.clout_paid,
+                clout_fee_burned: clout_fee_payment.clout_burned,
+                seller_payouts: std::mem::take(&mut settlement.seller_payouts),
+                sequence: next_sale_sequence(&mut ctx.accounts.market_config)?,
+                reserved: [0; SaleReceipt::RESERVED_LEN],
+            };
+            let mut receipt_data = settlement.accounts[5].try_borrow_mut_data()?;
+            receipt.try_serialize(&mut &mut receipt_data[..])?;
+            drop(receipt_data);
+
+            for (info, wallet, bump, side) in [
+                (
+                    &settlement.accounts[12],
+                    buyer_key,
+                    settlement.buyer_trade_history_bump,
+                    TradeSide::Buy,
+                ),
+                (
+                    &settlement.accounts[13],
+                    seller_key,
+                    settlement.seller_trade_history_bump,
+                    TradeSide::Sell,
+                ),
+            ] {
+                let entry = TradeHistoryEntry {
+                    receipt: settlement.accounts[5].key(),
+                    sequence: receipt.sequence,
+                    side,
+                };
+                record_trade(
+                    &mut trade_histories,
+                    info,
+                    &wallet,
+                    bump,
+                    entry,
+                    &payer,
+                    &system_program,
+                )?;
+            }
+
+            if settlement.reward_amount > 0 {
+                let reward_ctx = CpiContext::new(
+                    ctx.accounts.rewards_vault_program.to_account_info(),
+                    rewards_vault::cpi::accounts::MintRewards {
+                        vault_config: ctx.accounts.reward_vault.to_account_info(),
+                        vault_signer: ctx.accounts.vault_signer.to_account_info(),
+                        reward_mint: ctx.accounts.reward_mint.to_account_info(),
+                        recipient: settlement.accounts[6].clone(),
+                        authority: ctx.accounts.reward_authority.to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                    },
+                );
+                rewards_vault::cpi::mint_rewards(reward_ctx, settlement.reward_amount)?;
+            }
+
+            if suppression.is_none() {
+                let loyalty_ctx = CpiContext::new(
+                    ctx.accounts.loyalty_program.to_account_info(),
+                    loyalty_registry::cpi::accounts::RecordActivity {
+                        actor: settlement.accounts[3].clone(),
+                        profile: settlement.accounts[7].clone(),
+                        registry_config: ctx.accounts.loyalty_registry_config.to_account_info(),
+                        authority: ctx.accounts.loyalty_authority.to_account_info(),
+                    },
+                );
+                loyalty_registry::cpi::record_activity(
+                    loyalty_ctx,
+                    price,
+                    settlement.loyalty_bonus_points,
+                )?;
+            }
+        }
+        for (info, stats) in collection_stats.iter() {
+            stats.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        }
+        for (info, record) in mint_sale_records.iter() {
+            record.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        }
+        for (info, pair) in pair_volumes.iter() {
+            pair.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        }
+        for (info, history) in trade_histories.iter() {
+            history.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        }
+
+        Ok(outcomes)
+    }
+
+    /// Read-only quote of what `settle_sale` would pay out for a listing with
+    /// the given reward and bonus arguments. The quote is returned via return
+    /// data so clients can obtain it by simulating the instruction.
+    pub fn quote_sale(
+        ctx: Context<QuoteSale>,
+        reward_amount: u64,
+        loyalty_bonus_points: u64,
+    ) -> Result<SaleQuote> {
+        let listing = &ctx.accounts.listing;
+        let price = listing.price_lamports;
+        let split = listing_sale_split(listing)?;
+        let loyalty_points = ctx
+            .accounts
+            .loyalty_registry_config
+            .points_for_activity(price, loyalty_bonus_points)?;
+
+        Ok(SaleQuote {
+            price,
+            seller_proceeds: split.seller_payout,
+            royalty: split.royalty_cut,
+            developer_fee: split.developer_cut,
+            reward_pool_fee: split.reward_pool_cut,
+            ops_fee: split.ops_cut,
+            rewards: reward_amount,
+            loyalty_points,
+        })
+    }
+
+    /// Permissionless crank that retires an expired, unsold listing. Whoever
+    /// paid the listing's rent gets the escrow vault and listing rent back,
+    /// minus a bounty paid to the cranker from the listing rent.
+    pub fn expire_listing(ctx: Context<ExpireListing>) -> Result<()> {
+        let escrow = ListingEscrow::new(
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.listing,
+            &ctx.accounts.system_program,
+        );
+        let listing = &mut ctx.accounts.listing;
+        require!(
+            listing.status == ListingStatus::Active,
+            EscrowError::ListingNotActive
+        );
+        require!(
+            listing.sale_mode == SaleMode::FixedPrice,
+            EscrowError::ListingInAuction
+        );
+        let expiration = listing
+            .expiration_ts
+            .ok_or(EscrowError::ListingNotExpired)?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now > expiration, EscrowError::ListingNotExpired);
+        require!(
+            escrow_balance(&ctx.accounts.escrow_vault)? == 0,
+            EscrowError::OutstandingEscrowBalance
+        );
+
+        listing.status = ListingStatus::Expired;
+        listing.settlement_ts = Some(now);
+
+        escrow.close(&ctx.accounts.rent_payer)?;
+        let bounty = compute_fee(
+            listing.to_account_info().lamports(),
+            ctx.accounts.market_config.expiry_bounty_bps,
+        )?;
+        disburse(
+            &listing.to_account_info(),
+            &ctx.accounts.cranker.to_account_info(),
+            bounty,
+        )?;
+        Ok(())
+    }
+
+    /// Initializes the market-wide configuration with a controlling authority.
+    /// Only the program's upgrade authority may call it, so the config PDA
+    /// cannot be claimed by whoever reaches it first after deployment.
+    pub fn initialize_market_config(
+        ctx: Context<InitializeMarketConfig>,
+        authority: Pubkey,
+        expiry_bounty_bps: u16,
+    ) -> Result<()> {
+        ensure_upgrade_authority(&ctx.accounts.program_data, &ctx.accounts.payer.key())?;
+        require!(
+            expiry_bounty_bps <= MAX_EXPIRY_BOUNTY_BPS,
+            EscrowError::InvalidExpiryBounty
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.bump = ctx.bumps.market_config;
+        config.authority = authority;
+        config.expiry_bounty_bps = expiry_bounty_bps;
+        config.early_access_pool = Pubkey::default();
+        config.compliance_authority = authority;
+        config.wash_trading_rules = WashTradingRules::default();
+        config.redemption_arbiter = authority;
+        config.revenue_pool = Pubkey::default();
+        config.buyer_cancel_window_secs = 0;
+        config.last_sale_sequence = 0;
+        config.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Sets the key that resolves physical-redemption disputes.
+    pub fn set_redemption_arbiter(
+        ctx: Context<UpdateMarketConfig>,
+        redemption_arbiter: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.market_config;
+        config.redemption_arbiter = redemption_arbiter;
+        config.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Updates the rules that withhold rewards from suspected wash trades.
+    pub fn set_wash_trading_rules(
+        ctx: Context<UpdateMarketConfig>,
+        rules: WashTradingRules,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.market_config;
+        config.wash_trading_rules = rules;
+        config.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Hands blocklist management to a dedicated compliance authority.
+    pub fn set_compliance_authority(
+        ctx: Context<UpdateMarketConfig>,
+        compliance_authority: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.market_config;
+        config.compliance_authority = compliance_authority;
+        config.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Sets the `clout_staking` pool whose stakers qualify for early access.
+    pub fn set_early_access_pool(ctx: Context<UpdateMarketConfig>, pool: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.market_config;
+        config.early_access_pool = pool;
+        config.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Points the reward pool cut of every sale at the `clout_staking`
+    /// revenue pool of `staking_pool`. Settlement is rejected until one is set.
+    pub fn set_revenue_pool(ctx: Context<SetRevenuePool>) -> Result<()> {
+        let config = &mut ctx.accounts.market_config;
+        config.revenue_pool = ctx.accounts.revenue_pool.key();
+        config.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Sets how long after `execute_sale` the buyer may cancel the purchase.
+    /// Zero disables buyer cancellation. Applies to sales executed afterwards.
+    pub fn set_buyer_cancel_window(
+        ctx: Context<UpdateMarketConfig>,
+        window_secs: u32,
+    ) -> Result<()> {
+        require!(
+            window_secs <= MAX_BUYER_CANCEL_WINDOW_SECS,
+            EscrowError::InvalidBuyerCancelWindow
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.buyer_cancel_window_secs = window_secs;
+        config.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Updates the share of listing rent paid to `expire_listing` crankers.
+    pub fn set_expiry_bounty(
+        ctx: Context<UpdateMarketConfig>,
+        expiry_bounty_bps: u16,
+    ) -> Result<()> {
+        require!(
+            expiry_bounty_bps <= MAX_EXPIRY_BOUNTY_BPS,
+            EscrowError::InvalidExpiryBounty
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.expiry_bounty_bps = expiry_bounty_bps;
+        config.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Upgrades a listing from an older layout (unversioned or v1) to the
+    /// current one, and converts a program-owned escrow vault into the data-less
+    /// system account used now. The payer tops up any additional listing
+    /// rent; the old vault's rent above the new floor returns to the
+    /// listing's rent payer.
+    pub fn migrate_listing(ctx: Context<MigrateListing>) -> Result<()> {
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let listing_migrated =
+            migrate_account::<Listing>(&ctx.accounts.listing, &payer, &system_program)?;
+        let listing = Listing::try_deserialize(&mut &ctx.accounts.listing.try_borrow_data()?[..])?;
+        require_keys_eq!(
+            listing.rent_payer,
+            ctx.accounts.rent_payer.key(),
+            EscrowError::RentPayerMismatch
+        );
+        let escrow_migrated =
+            convert_legacy_escrow_vault(&ctx.accounts.escrow_vault, &ctx.accounts.rent_payer)?;
+        require!(
+            listing_migrated || escrow_migrated,
+            EscrowError::AccountAlreadyMigrated
+        );
+        Ok(())
+    }
+
+    /// Upgrades a sale receipt from an older layout (unversioned or v1).
+    pub fn migrate_receipt(ctx: Context<MigrateReceipt>) -> Result<()> {
+        let migrated = migrate_account::<SaleReceipt>(
+            &ctx.accounts.receipt,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        require!(migrated, EscrowError::AccountAlreadyMigrated);
+        Ok(())
+    }
+
+    /// Converts an active listing into a sealed-bid auction. The listing price
+    /// becomes the reserve; bids are committed until `commit_end_ts` and
+    /// revealed until `reveal_end_ts`.
+    pub fn create_sealed_auction(
+        ctx: Context<CreateSealedAuction>,
+        commit_end_ts: i64,
+        reveal_end_ts: i64,
+        pricing: AuctionPricing,
+    ) -> Result<()> {
+        let listing = &mut ctx.accounts.listing;
+        require!(
+            listing.status == ListingStatus::Active,
+            EscrowError::ListingNotActive
+        );
+        require!(
+            listing.sale_mode == SaleMode::FixedPrice,
+            EscrowError::ListingInAuction
+        );
+        require!(
+            escrow_balance(&ctx.accounts.escrow_vault)? == 0,
+            EscrowError::EscrowAlreadyFunded
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            commit_end_ts > now && reveal_end_ts > commit_end_ts,
+            EscrowError::InvalidAuctionSchedule
+        );
+        if let Some(expiration) = listing.expiration_ts {
+            require!(
+                reveal_end_ts <= expiration,
+                EscrowError::InvalidAuctionSchedule
+            );
+        }
+
+        listing.sale_mode = SaleMode::SealedBidAuction;
+
+        let auction = &mut ctx.accounts.auction;
+        auction.bump = ctx.bumps.auction;
+        auction.listing = listing.key();
+        auction.seller = listing.seller;
+        auction.pricing = pricing;
+        auction.reserve_price = listing.price_lamports;
+        auction.commit_end_ts = commit_end_ts;
+        auction.reveal_end_ts = reveal_end_ts;
+        auction.bid_count = 0;
+        auction.revealed_count = 0;
+        auction.highest_bid = 0;
+        auction.highest_bidder = None;
+        auction.second_price = 0;
+        auction.status = AuctionStatus::Open;
+        Ok(())
+    }
+
+    /// Commits a sealed bid. `commitment` must equal `bid_commitment(bidder,
+    /// amount, salt)` and the escrowed deposit must cover the hidden amount.
+    pub fn commit_bid(
+        ctx: Context<CommitBid>,
+        commitment: [u8; 32],
+        deposit_lamports: u64,
+    ) -> Result<()> {
+        let auction = &mut ctx.accounts.auction;
+        require!(
+            auction.status == AuctionStatus::Open,
+            EscrowError::AuctionNotOpen
+        );
+        require!(
+            Clock::get()?.unix_timestamp <= auction.commit_end_ts,
+            EscrowError::CommitPhaseClosed
+        );
+        require!(
+            deposit_lamports >= auction.reserve_price,
+            EscrowError::InsufficientBidDeposit
+        );
+        require_keys_neq!(
+            ctx.accounts.bidder.key(),
+            auction.seller,
+            EscrowError::SellerCannotBid
+        );
+        ensure_not_blocked(
+            &auction.seller,
+            &ctx.accounts.seller_blocklist_page,
+            EscrowError::SellerBlocked,
+        )?;
+        ensure_not_blocked(
+            &ctx.accounts.bidder.key(),
+            &ctx.accounts.bidder_blocklist_page,
+            EscrowError::BuyerBlocked,
+        )?;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.bidder.to_account_info(),
+                    to: ctx.accounts.bid.to_account_info(),
+                },
+            ),
+            deposit_lamports,
+        )?;
+
+        let bid = &mut ctx.accounts.bid;
+        bid.bump = ctx.bumps.bid;
+        bid.auction = auction.key();
+        bid.bidder = ctx.accounts.bidder.key();
+        bid.commitment = commitment;
+        bid.deposit_lamports = deposit_lamports;
+        bid.revealed_amount = None;
+        bid.rent_payer = ctx.accounts.payer.key();
+
+        auction.bid_count = auction
+            .bid_count
+            .checked_add(1)
+            .ok_or(EscrowError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Reveals a committed bid during the reveal phase. Bids below the reserve
+    /// are accepted as revealed (and refundable) but cannot win.
+    pub fn reveal_bid(ctx: Context<RevealBid>, amount: u64, salt: [u8; 32]) -> Result<()> {
+        let auction = &mut ctx.accounts.auction;
+        let bid = &mut ctx.accounts.bid;
+        require!(
+            auction.status == AuctionStatus::Open,
+            EscrowError::AuctionNotOpen
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now > auction.commit_end_ts && now <= auction.reveal_end_ts,
+            EscrowError::NotInRevealPhase
+        );
+        require!(
+            bid.revealed_amount.is_none(),
+            EscrowError::BidAlreadyRevealed
+        );
+        require!(
+            bid_commitment(&bid.bidder, amount, &salt) == bid.commitment,
+            EscrowError::CommitmentMismatch
+        );
+        require!(
+            amount <= bid.deposit_lamports,
+            EscrowError::InsufficientBidDeposit
+        );
+
+        bid.revealed_amount = Some(amount);
+        auction.revealed_count = auction
+            .revealed_count
+            .checked_add(1)
+            .ok_or(EscrowError::MathOverflow)?;
+        if amount >= auction.reserve_price {
+            auction.record_revealed_bid(bid.bidder, amount);
+        }
+        Ok(())
+    }
+
+    /// Ends the auction after the reveal phase. The winner's clearing price
+    /// (first or second price) moves into the escrow vault and the listing
+    /// becomes pending settlement, to be paid out by `settle_sale`. Without a
+    /// winning bid the listing reverts to a fixed-price listing.
+    pub fn finalize_sealed_auction(ctx: Context<FinalizeSealedAuction>) -> Result<()> {
+        let auction = &mut ctx.accounts.auction;
+        let listing = &mut ctx.accounts.listing;
+        require!(
+            auction.status == AuctionStatus::Open,
+            EscrowError::AuctionNotOpen
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(now > auction.reveal_end_ts, EscrowError::RevealPhaseOpen);
+        auction.status = AuctionStatus::Finalized;
+
+        let Some(winner) = auction.highest_bidder else {
+            listing.sale_mode = SaleMode::FixedPrice;
+            return Ok(());
+        };
+        let winning_bid = ctx
+            .accounts
+            .winning_bid
+            .as_mut()
+            .ok_or(EscrowError::WinningBidMismatch)?;
+        require_keys_eq!(winning_bid.bidder, winner, EscrowError::WinningBidMismatch);
+        require_keys_eq!(
+            winning_bid.auction,
+            auction.key(),
+            EscrowError::WinningBidMismatch
+        );
+
+        let clearing_price = auction.clearing_price();
+        disburse(
+            &winning_bid.to_account_info(),
+            &ctx.accounts.escrow_vault,
+            clearing_price,
+        )?;
+        winning_bid.deposit_lamports = winning_bid
+            .deposit_lamports
+            .checked_sub(clearing_price)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        listing.price_lamports = clearing_price;
+        listing.status = ListingStatus::PendingSettlement;
+        listing.buyer = Some(winner);
+        listing.sale_ts = Some(now);
+        start_redemption_window(listing, now)?;
+        Ok(())
+    }
+
+    /// Closes a bid once the reveal phase is over. Revealed bids get their
+    /// remaining deposit back; unrevealed bids forfeit the deposit to the
+    /// seller. The bid rent returns to its payer. The winning bid can only be
+    /// closed after finalization.
+    pub fn close_bid(ctx: Context<CloseBid>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        let bid = &ctx.accounts.bid;
+        require!(
+            Clock::get()?.unix_timestamp > auction.reveal_end_ts,
+            EscrowError::RevealPhaseOpen
+        );
+        if auction.highest_bidder == Some(bid.bidder) {
+            require!(
+                auction.status == AuctionStatus::Finalized,
+                EscrowError::AuctionNotFinalized
+            );
+        }
+
+        let deposit_recipient = if bid.revealed_amount.is_some() {
+            ctx.accounts.bidder.to_account_info()
+        } else {
+            ctx.accounts.seller.to_account_info()
+        };
+        disburse(
+            &bid.to_account_info(),
+            &deposit_recipient,
+            bid.deposit_lamports,
+        )?;
+        Ok(())
+    }
+
+    /// Creates the market statistics account for a verified collection. Any
+    /// payer may initialize it ahead of time; otherwise settlement creates it
+    /// on the collection's first sale and keeps it up to date afterwards.
+    pub fn initialize_collection_stats(ctx: Context<InitializeCollectionStats>) -> Result<()> {
+        let stats = &mut ctx.accounts.collection_stats;
+        stats.bump = ctx.bumps.collection_stats;
+        stats.collection = ctx.accounts.collection_mint.key();
+        stats.total_volume = 0;
+        stats.sale_count = 0;
+        stats.last_sale_price = 0;
+        stats.last_sale_ts = 0;
+        stats.rolling_average_price = 0;
+        Ok(())
+    }
+
+    /// Market authority adds a verified Metaplex collection to the curated
+    /// registry with its status, royalty policy and optional platform fee
+    /// override.
+    pub fn register_collection(
+        ctx: Context<RegisterCollection>,
+        status: CollectionStatus,
+        royalty_policy: RoyaltyPolicy,
+        platform_fee_override_bps: Option<u16>,
+    ) -> Result<()> {
+        validate_collection_terms(&royalty_policy, platform_fee_override_bps)?;
+        let entry = &mut ctx.accounts.collection_entry;
+        entry.bump = ctx.bumps.collection_entry;
+        entry.collection = ctx.accounts.collection_mint.key();
+        entry.status = status;
+        entry.royalty_policy = royalty_policy;
+        entry.platform_fee_override_bps = platform_fee_override_bps;
+        entry.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Market authority updates a registered collection. Existing listings
+    /// keep the terms they were created under.
+    pub fn update_collection(
+        ctx: Context<UpdateCollection>,
+        status: CollectionStatus,
+        royalty_policy: RoyaltyPolicy,
+        platform_fee_override_bps: Option<u16>,
+    ) -> Result<()> {
+        validate_collection_terms(&royalty_policy, platform_fee_override_bps)?;
+        let entry = &mut ctx.accounts.collection_entry;
+        entry.status = status;
+        entry.royalty_policy = royalty_policy;
+        entry.platform_fee_override_bps = platform_fee_override_bps;
+        entry.last_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Compliance authority blocks a wallet from listing, buying and bidding.
+    /// The wallet's blocklist page is created on first use.
+    pub fn block_wallet(ctx: Context<BlockWallet>, wallet: Pubkey) -> Result<()> {
+        let page = &mut ctx.accounts.blocklist_page;
+        if page.entries.is_empty() {
+            page.bump = ctx.bumps.blocklist_page;
+            page.page_index = blocklist_page_index(&wallet);
+        }
+        page.insert(wallet)?;
+
+        emit!(WalletBlocked {
+            wallet,
+            authority: ctx.accounts.compliance_authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Compliance authority lifts a block placed by `block_wallet`.
+    pub fn unblock_wallet(ctx: Context<UnblockWallet>, wallet: Pubkey) -> Result<()> {
+        ctx.accounts.blocklist_page.remove(&wallet)?;
+
+        emit!(WalletUnblocked {
+            wallet,
+            authority: ctx.accounts.compliance_authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Seller marks an unsold listing as backed by physical goods. After a
+    /// sale the payment stays in escrow until the buyer confirms delivery or
+    /// `confirmation_window_secs` pass without a dispute.
+    pub fn enable_physical_redemption(
+        ctx: Context<EnablePhysicalRedemption>,
+        confirmation_window_secs: u32,
+    ) -> Result<()> {
+        let listing = &mut ctx.accounts.listing;
+        require!(
+            listing.status == ListingStatus::Active,
+            EscrowError::ListingNotActive
+        );
+        require!(
+            confirmation_window_secs >= MIN_REDEMPTION_WINDOW_SECS,
+            EscrowError::InvalidRedemptionWindow
+        );
+        listing.redemption = Some(Redemption {
+            status: RedemptionStatus::AwaitingSale,
+            confirmation_window_secs,
+            confirmation_deadline_ts: 0,
+        });
+        Ok(())
+    }
+
+    /// Buyer confirms the physical goods arrived, releasing the escrow to
+    /// `settle_sale`.
+    pub fn confirm_delivery(ctx: Context<ConfirmDelivery>) -> Result<()> {
+        let listing = &mut ctx.accounts.listing;
+        require!(
+            listing.status == ListingStatus::PendingSettlement,
+            EscrowError::ListingNotPending
+        );
+        require!(
+            listing.buyer == Some(ctx.accounts.buyer.key()),
+            EscrowError::BuyerMismatch
+        );
+        let redemption = listing
+            .redemption
+            .as_mut()
+            .ok_or(EscrowError::NotPhysicalRedemption)?;
+        require!(
+            redemption.status == RedemptionStatus::AwaitingDelivery,
+            EscrowError::RedemptionNotAwaitingDelivery
+        );
+        redemption.status = RedemptionStatus::Delivered;
+        Ok(())
+    }
+
+    /// Buyer or seller disputes a physical-redemption sale before the
+    /// confirmation deadline, freezing the escrow until the arbiter rules.
+    pub fn open_dispute(ctx: Context<OpenDispute>) -> Result<()> {
+        let listing = &mut ctx.accounts.listing;
+        require!(
+            listing.status == ListingStatus::PendingSettlement,
+            EscrowError::ListingNotPending
+        );
+        let party = ctx.accounts.party.key();
+        require!(
+            party == listing.seller || listing.buyer == Some(party),
+            EscrowError::NotSaleParty
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let redemption = listing
+            .redemption
+            .as_mut()
+            .ok_or(EscrowError::NotPhysicalRedemption)?;
+        require!(
+            redemption.status == RedemptionStatus::AwaitingDelivery,
+            EscrowError::RedemptionNotAwaitingDelivery
+        );
+        require!(
+            now <= redemption.confirmation_deadline_ts,
+            EscrowError::ConfirmationWindowClosed
+        );
+        redemption.status = RedemptionStatus::Disputed;
+        Ok(())
+    }
+
+    /// Arbiter resolves a dispute by refunding `buyer_share_bps` of the
+    /// escrowed price to the buyer and paying the rest to the seller. No
+    /// marketplace fees, rewards or loyalty activity apply.
+    pub fn resolve_dispute<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ResolveDispute<'info>>,
+        buyer_share_bps: u16,
+    ) -> Result<()> {
+        require!(
+            buyer_share_bps as u64 <= BPS_DENOMINATOR,
+            EscrowError::InvalidDisputeSplit
+        );
+        let escrow = ListingEscrow::new(
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.listing,
+            &ctx.accounts.system_program,
+        );
+        let listing = &mut ctx.accounts.listing;
+        require!(
+            listing.status == ListingStatus::PendingSettlement,
+            EscrowError::ListingNotPending
+        );
+        require!(
+            listing.buyer == Some(ctx.accounts.buyer.key()),
+            EscrowError::BuyerMismatch
+        );
+        let mut redemption = listing
+            .redemption
+            .ok_or(EscrowError::NotPhysicalRedemption)?;
+        require!(
+            redemption.status == RedemptionStatus::Disputed,
+            EscrowError::RedemptionNotDisputed
+        );
+
+        let escrowed_amount = listing_sale_split(listing)?.escrowed_amount()?;
+        let buyer_refund = compute_fee(escrowed_amount, buyer_share_bps)?;
+        let seller_payout = escrowed_amount
+            .checked_sub(buyer_refund)
+            .ok_or(EscrowError::MathOverflow)?;
+        require!(
+            escrow.balance()? >= escrowed_amount,
+            EscrowError::InsufficientEscrowBalance
+        );
+        escrow.pay(&ctx.accounts.buyer, buyer_refund)?;
+        pay_seller_proceeds(
+            &escrow,
+            &ctx.accounts.seller,
+            &listing.payout_destinations,
+            ctx.remaining_accounts,
+            seller_payout,
+        )?;
+
+        redemption.status = RedemptionStatus::Resolved;
+        listing.redemption = Some(redemption);
+        listing.status = ListingStatus::Arbitrated;
+        listing.settlement_ts = Some(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Market authority configures paying platform fees in CLOUT: the oracle
+    /// authority that publishes the CLOUT/SOL rate, the reward pool token
+    /// account and the discount and burn terms.
+    pub fn initialize_clout_fee_oracle(
+        ctx: Context<InitializeCloutFeeOracle>,
+        oracle_authority: Pubkey,
+        terms: CloutFeeTerms,
+    ) -> Result<()> {
+        validate_clout_fee_terms(&terms)?;
+        let oracle = &mut ctx.accounts.clout_fee_oracle;
+        oracle.bump = ctx.bumps.clout_fee_oracle;
+        oracle.oracle_authority = oracle_authority;
+        oracle.clout_mint = ctx.accounts.clout_mint.key();
+        oracle.reward_pool_account = ctx.accounts.reward_pool_account.key();
+        oracle.terms = terms;
+        oracle.clout_per_sol = 0;
+        oracle.rate_updated_ts = 0;
+        Ok(())
+    }
+
+    /// Market authority updates the CLOUT fee discount and burn terms.
+    pub fn set_clout_fee_terms(ctx: Context<SetCloutFeeTerms>, terms: CloutFeeTerms) -> Result<()> {
+        validate_clout_fee_terms(&terms)?;
+        ctx.accounts.clout_fee_oracle.terms = terms;
+        Ok(())
+    }
+
+    /// Oracle authority publishes the exchange rate, in CLOUT base units per
+    /// SOL.
+    pub fn update_clout_rate(ctx: Context<UpdateCloutRate>, clout_per_sol: u64) -> Result<()> {
+        require!(clout_per_sol > 0, EscrowError::InvalidCloutRate);
+        let oracle = &mut ctx.accounts.clout_fee_oracle;
+        oracle.clout_per_sol = clout_per_sol;
+        oracle.rate_updated_ts = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Buyer of a pending sale pays the platform fee in CLOUT at the oracle
+    /// rate less the configured discount. Part of the CLOUT is burned and the
+    /// rest goes to the reward pool; the SOL platform fee is refunded from
+    /// escrow and waived at settlement.
+    pub fn pay_fees_in_clout(ctx: Context<PayFeesInClout>, max_clout_amount: u64) -> Result<()> {
+        let escrow = ListingEscrow::new(
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.listing,
+            &ctx.accounts.system_program,
+        );
+        let listing = &mut ctx.accounts.listing;
+        require!(
+            listing.status == ListingStatus::PendingSettlement,
+            EscrowError::ListingNotPending
+        );
+        require!(
+            listing.buyer == Some(ctx.accounts.buyer.key()),
+            EscrowError::BuyerMismatch
+        );
+        require!(
+            listing.clout_fee_payment.is_none(),
+            EscrowError::FeesAlreadyPaidInClout
+        );
+
+        let oracle = &ctx.accounts.clout_fee_oracle;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            oracle.clout_per_sol > 0
+                && now.saturating_sub(oracle.rate_updated_ts)
+                    <= oracle.terms.max_rate_age_secs as i64,
+            EscrowError::StaleCloutRate
+        );
+        let split = listing_sale_split(listing)?;
+        let platform_fee = split.platform_fee()?;
+        let clout_paid = oracle.clout_fee_for(platform_fee)?;
+        require!(clout_paid > 0, EscrowError::InvalidCloutRate);
+        require!(
+            clout_paid <= max_clout_amount,
+            EscrowError::CloutFeeSlippageExceeded
+        );
+        let clout_burned = (clout_paid as u128)
+            .checked_mul(oracle.terms.burn_bps as u128)
+            .ok_or(EscrowError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(EscrowError::MathOverflow)? as u64;
+        let clout_to_pool = clout_paid
+            .checked_sub(clout_burned)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        if clout_burned > 0 {
+            token::burn(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Burn {
+                        mint: ctx.accounts.clout_mint.to_account_info(),
+                        from: ctx.accounts.buyer_clout_account.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                clout_burned,
+            )?;
+        }
+        if clout_to_pool > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.buyer_clout_account.to_account_info(),
+                        to: ctx.accounts.reward_pool_account.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                clout_to_pool,
+            )?;
+        }
+
+        escrow.pay(&ctx.accounts.buyer, platform_fee)?;
+
+        listing.clout_fee_payment = Some(CloutFeePayment {
+            clout_paid,
+            clout_burned,
+        });
+        Ok(())
+    }
+
+    /// Seller offers an active fixed-price listing on installments: a deposit
+    /// followed by `installment_count` equal payments, one per interval.
+    pub fn offer_installments(
+        ctx: Context<OfferInstallments>,
+        terms: InstallmentTerms,
+    ) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        require!(
+            listing.status == ListingStatus::Active,
+            EscrowError::ListingNotActive
+        );
+        require!(
+            listing.sale_mode == SaleMode::FixedPrice,
+            EscrowError::ListingInAuction
+        );
+        require!(
+            terms.deposit_bps as u64 <= BPS_DENOMINATOR
+                && terms.forfeit_bps as u64 <= BPS_DENOMINATOR
+                && terms.installment_count > 0
+                && terms.installment_count <= MAX_INSTALLMENTS
+                && terms.interval_secs > 0,
+            EscrowError::InvalidInstallmentTerms
+        );
+
+        let plan = &mut ctx.accounts.installment_plan;
+        plan.bump = ctx.bumps.installment_plan;
+        plan.listing = listing.key();
+        plan.seller = listing.seller;
+        plan.terms = terms;
+        plan.rent_payer = ctx.accounts.payer.key();
+        plan.reset();
+        Ok(())
+    }
+
+    /// Seller withdraws an installment offer no buyer has taken up.
+    pub fn withdraw_installments(ctx: Context<WithdrawInstallments>) -> Result<()> {
+        require!(
+            ctx.accounts.installment_plan.status == InstallmentStatus::Offered,
+            EscrowError::InstallmentPlanNotOffered
+        );
+        Ok(())
+    }
+
+    /// Buyer starts the installment plan by paying the deposit into escrow.
+    /// The listing is reserved for the buyer until the plan completes or
+    /// defaults.
+    pub fn start_installment_plan(ctx: Context<StartInstallmentPlan>) -> Result<()> {
+        ensure_not_blocked(
+            &ctx.accounts.listing.seller,
+            &ctx.accounts.seller_blocklist_page,
+            EscrowError::SellerBlocked,
+        )?;
+        ensure_not_blocked(
+            &ctx.accounts.buyer.key(),
+            &ctx.accounts.buyer_blocklist_page,
+            EscrowError::BuyerBlocked,
+        )?;
+        let listing = &mut ctx.accounts.listing;
+        require!(
+            listing.status == ListingStatus::Active,
+            EscrowError::ListingNotActive
+        );
+        require!(
+            listing.sale_mode == SaleMode::FixedPrice,
+            EscrowError::ListingInAuction
+        );
+        require!(
+            escrow_balance(&ctx.accounts.escrow_vault)? == 0,
+            EscrowError::EscrowAlreadyFunded
+        );
+        let now = Clock::get()?.unix_timestamp;
+        if let Some(expiration) = listing.expiration_ts {
+            require!(now <= expiration, EscrowError::ListingExpired);
+        }
+        check_early_access(
+            listing,
+            &ctx.accounts.buyer.key(),
+            now,
+            ctx.accounts.buyer_loyalty_profile.as_deref(),
+            ctx.accounts.buyer_stake_position.as_deref(),
+            ctx.accounts.market_config.as_deref(),
+        )?;
+
+        let plan = &mut ctx.accounts.installment_plan;
+        require!(
+            plan.status == InstallmentStatus::Offered,
+            EscrowError::InstallmentPlanNotOffered
+        );
+        let deposit = compute_fee(listing.price_lamports, plan.terms.deposit_bps)?;
+        pay_into_escrow(
+            &ctx.accounts.buyer,
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.system_program,
+            deposit,
+        )?;
+
+        plan.buyer = Some(ctx.accounts.buyer.key());
+        plan.status = InstallmentStatus::Active;
+        plan.start_ts = now;
+        plan.amount_paid = deposit;
+        listing.sale_mode = SaleMode::Installment;
+        listing.buyer = Some(ctx.accounts.buyer.key());
+        Ok(())
+    }
+
+    /// Buyer pays the next scheduled installment. Paying early is allowed;
+    /// once the full price is escrowed the listing moves to pending
+    /// settlement like a regular sale.
+    pub fn pay_installment(ctx: Context<PayInstallment>) -> Result<()> {
+        let listing = &mut ctx.accounts.listing;
+        let plan = &mut ctx.accounts.installment_plan;
+        require!(
+            plan.status == InstallmentStatus::Active,
+            EscrowError::InstallmentPlanNotActive
+        );
+        require!(
+            plan.buyer == Some(ctx.accounts.buyer.key()),
+            EscrowError::BuyerMismatch
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(now <= plan.next_due_ts()?, EscrowError::InstallmentOverdue);
+
+        let amount = plan.next_installment_amount(listing.price_lamports)?;
+        pay_into_escrow(
+            &ctx.accounts.buyer,
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+        plan.installments_paid += 1;
+        plan.amount_paid = plan
+            .amount_paid
+            .checked_add(amount)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        if plan.installments_paid == plan.terms.installment_count {
+            plan.status = InstallmentStatus::Completed;
+            listing.status = ListingStatus::PendingSettlement;
+            listing.sale_ts = Some(now);
+            start_redemption_window(listing, now)?;
+        }
+        Ok(())
+    }
+
+    /// Permissionless crank for a plan with a missed installment. The seller
+    /// keeps `forfeit_bps` of what was paid, the buyer is refunded the rest,
+    /// and the listing is released for sale again with the offer reopened.
+    pub fn default_installment_plan(ctx: Context<DefaultInstallmentPlan>) -> Result<()> {
+        let escrow = ListingEscrow::new(
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.listing,
+            &ctx.accounts.system_program,
+        );
+        let listing = &mut ctx.accounts.listing;
+        let plan = &mut ctx.accounts.installment_plan;
+        require!(
+            plan.status == InstallmentStatus::Active,
+            EscrowError::InstallmentPlanNotActive
+        );
+        require!(
+            plan.buyer == Some(ctx.accounts.buyer.key()),
+            EscrowError::BuyerMismatch
+        );
+        require!(
+            Clock::get()?.unix_timestamp > plan.next_due_ts()?,
+            EscrowError::InstallmentNotOverdue
+        );
+
+        let paid = plan.amount_paid;
+        let forfeit = compute_fee(paid, plan.terms.forfeit_bps)?;
+        let refund = paid.checked_sub(forfeit).ok_or(EscrowError::MathOverflow)?;
+        require!(
+            escrow.balance()? >= paid,
+            EscrowError::InsufficientEscrowBalance
+        );
+        escrow.pay(&ctx.accounts.seller, forfeit)?;
+        escrow.pay(&ctx.accounts.buyer, refund)?;
+
+        plan.reset();
+        listing.sale_mode = SaleMode::FixedPrice;
+        listing.buyer = None;
+        Ok(())
+    }
+
+    /// Converts an active listing into a raffle. Tickets sell for
+    /// `ticket_price` until `sales_end_ts`; `seed_commitment` must equal
+    /// `raffle_seed_commitment(listing, seed)` for the seed the seller reveals
+    /// to draw the winner.
+    pub fn create_raffle(
+        ctx: Context<CreateRaffle>,
+        ticket_price: u64,
+        min_tickets: u32,
+        sales_end_ts: i64,
+        seed_commitment: [u8; 32],
+    ) -> Result<()> {
+        let listing = &mut ctx.accounts.listing;
+        require!(
+            listing.status == ListingStatus::Active,
+            EscrowError::ListingNotActive
+        );
+        require!(
+            listing.sale_mode == SaleMode::FixedPrice,
+            EscrowError::ListingInAuction
+        );
+        require!(
+            escrow_balance(&ctx.accounts.escrow_vault)? == 0,
+            EscrowError::EscrowAlreadyFunded
+        );
+        require!(
+            ticket_price > 0 && min_tickets > 0,
+            EscrowError::InvalidRaffleTerms
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let reveal_deadline_ts = sales_end_ts
+            .checked_add(RAFFLE_REVEAL_WINDOW_SECS)
+            .ok_or(EscrowError::MathOverflow)?;
+        require!(sales_end_ts > now, EscrowError::InvalidRaffleTerms);
+        if let Some(expiration) = listing.expiration_ts {
+            require!(
+                reveal_deadline_ts <= expiration,
+                EscrowError::InvalidRaffleTerms
+            );
+        }
+
+        listing.sale_mode = SaleMode::Raffle;
+
+        let raffle = &mut ctx.accounts.raffle;
+        raffle.bump = ctx.bumps.raffle;
+        raffle.listing = listing.key();
+        raffle.seller = listing.seller;
+        raffle.ticket_price = ticket_price;
+        raffle.min_tickets = min_tickets;
+        raffle.sales_end_ts = sales_end_ts;
+        raffle.reveal_deadline_ts = reveal_deadline_ts;
+        raffle.seed_commitment = seed_commitment;
+        raffle.tickets_sold = 0;
+        raffle.tickets_refunded = 0;
+        raffle.page_count = 0;
+        raffle.status = RaffleStatus::Open;
+        raffle.winning_ticket = None;
+        Ok(())
+    }
+
+    /// Buys `ticket_count` tickets, escrowing their price. The purchase is
+    /// appended to ledger page `page_index`, which must be the newest page or,
+    /// once that is full, the next one.
+    pub fn buy_raffle_tickets(
+        ctx: Context<BuyRaffleTickets>,
+        page_index: u32,
+        ticket_count: u32,
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        require!(
+            raffle.status == RaffleStatus::Open,
+            EscrowError::RaffleNotOpen
+        );
+        require!(
+            Clock::get()?.unix_timestamp <= raffle.sales_end_ts,
+            EscrowError::RaffleSalesClosed
+        );
+        require!(ticket_count > 0, EscrowError::InvalidRaffleTerms);
+        require_keys_neq!(
+            ctx.accounts.buyer.key(),
+            raffle.seller,
+            EscrowError::SellerCannotBuyTickets
+        );
+        ensure_not_blocked(
+            &raffle.seller,
+            &ctx.accounts.seller_blocklist_page,
+            EscrowError::SellerBlocked,
+        )?;
+        ensure_not_blocked(
+            &ctx.accounts.buyer.key(),
+            &ctx.accounts.buyer_blocklist_page,
+            EscrowError::BuyerBlocked,
+        )?;
+
+        let page = &mut ctx.accounts.ticket_page;
+        if page.raffle == Pubkey::default() {
+            require!(
+                page_index == raffle.page_count,
+                EscrowError::InvalidRaffleTicketPage
+            );
+            page.bump = ctx.bumps.ticket_page;
+            page.raffle = raffle.key();
+            page.index = page_index;
+            raffle.page_count += 1;
+        } else {
+            require!(
+                raffle.page_count.checked_sub(1) == Some(page_index),
+                EscrowError::InvalidRaffleTicketPage
+            );
+        }
+        require!(
+            page.entries.len() < RAFFLE_TICKET_PAGE_CAPACITY,
+            EscrowError::RaffleTicketPageFull
+        );
+
+        let cost = raffle
+            .ticket_price
+            .checked_mul(ticket_count as u64)
+            .ok_or(EscrowError::MathOverflow)?;
+        pay_into_escrow(
+            &ctx.accounts.buyer,
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.system_program,
+            cost,
+        )?;
+
+        page.entries.push(RaffleTicketEntry {
+            buyer: ctx.accounts.buyer.key(),
+            first_ticket: raffle.tickets_sold,
+            ticket_count,
+            refunded: false,
+        });
+        raffle.tickets_sold = raffle
+            .tickets_sold
+            .checked_add(ticket_count)
+            .ok_or(EscrowError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Seller reveals the committed seed after ticket sales close. The
+    /// winning ticket is drawn from the seed combined with the most recent
+    /// slot hash, so neither input alone decides the outcome.
+    pub fn reveal_raffle_seed(ctx: Context<RevealRaffleSeed>, seed: [u8; 32]) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        require!(
+            raffle.status == RaffleStatus::Open,
+            EscrowError::RaffleNotOpen
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(now > raffle.sales_end_ts, EscrowError::RaffleSalesOpen);
+        require!(
+            now <= raffle.reveal_deadline_ts,
+            EscrowError::RaffleRevealClosed
+        );
+        require!(
+            raffle.tickets_sold >= raffle.min_tickets,
+            EscrowError::RaffleMinimumNotMet
+        );
+        require!(
+            raffle_seed_commitment(&raffle.listing, &seed) == raffle.seed_commitment,
+            EscrowError::CommitmentMismatch
+        );
+
+        let slot_hash = most_recent_slot_hash(&ctx.accounts.slot_hashes)?;
+        let draw = hashv(&[&seed, &slot_hash, raffle.key().as_ref()]).to_bytes();
+        let mut draw_bytes = [0u8; 8];
+        draw_bytes.copy_from_slice(&draw[..8]);
+        let winning_ticket = (u64::from_le_bytes(draw_bytes) % raffle.tickets_sold as u64) as u32;
+
+        raffle.status = RaffleStatus::Drawn;
+        raffle.winning_ticket = Some(winning_ticket);
+        Ok(())
+    }
+
+    /// Permissionless crank pointing at the ledger entry that holds the
+    /// winning ticket. The winner becomes the buyer and the ticket proceeds
+    /// become the sale price, settled through `settle_sale`.
+    pub fn assign_raffle_winner(
+        ctx: Context<AssignRaffleWinner>,
+        _page_index: u32,
+        entry_index: u16,
+    ) -> Result<()> {
+        let raffle = &mut ctx.accounts.raffle;
+        require!(
+            raffle.status == RaffleStatus::Drawn,
+            EscrowError::RaffleNotDrawn
+        );
+        let winning_ticket = raffle.winning_ticket.ok_or(EscrowError::RaffleNotDrawn)?;
+        let entry = ctx
+            .accounts
+            .ticket_page
+            .entries
+            .get(entry_index as usize)
+            .ok_or(EscrowError::RaffleEntryMismatch)?;
+        require!(
+            entry.holds(winning_ticket),
+            EscrowError::RaffleEntryMismatch
+        );
+
+        let proceeds = raffle
+            .ticket_price
+            .checked_mul(raffle.tickets_sold as u64)
+            .ok_or(EscrowError::MathOverflow)?;
+        let now = Clock::get()?.unix_timestamp;
+        let listing = &mut ctx.accounts.listing;
+        listing.price_lamports = proceeds;
+        listing.status = ListingStatus::PendingSettlement;
+        listing.buyer = Some(entry.buyer);
+        listing.sale_ts = Some(now);
+        start_redemption_window(listing, now)?;
+        raffle.status = RaffleStatus::Completed;
+        Ok(())
+    }
+
+    /// Cancels a raffle that closed below its minimum ticket count or whose
+    /// seed was not revealed in time, opening ticket refunds. The listing
+    /// returns to fixed-price once every ticket has been refunded.
+    pub fn cancel_raffle(ctx: Context<CancelRaffle>) -> Result<()> {
+        cancel_raffle_listing(
+            &mut ctx.accounts.raffle,
+            &mut ctx.accounts.listing,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    /// Refunds one ledger entry of a cancelled raffle in full to its buyer.
+    /// The last refund returns the listing to fixed-price.
+    pub fn claim_raffle_refund(
+        ctx: Context<ClaimRaffleRefund>,
+        _page_index: u32,
+        entry_index: u16,
+    ) -> Result<()> {
+        let entry = ctx
+            .accounts
+            .ticket_page
+            .entries
+            .get_mut(entry_index as usize)
+            .ok_or(EscrowError::RaffleEntryMismatch)?;
+        require_keys_eq!(
+            entry.buyer,
+            ctx.accounts.buyer.key(),
+            EscrowError::BuyerMismatch
+        );
+        let refund =
+            refund_raffle_entry(&mut ctx.accounts.raffle, &mut ctx.accounts.listing, entry)?;
+
+        ListingEscrow::new(
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.listing,
+            &ctx.accounts.system_program,
+        )
+        .pay(&ctx.accounts.buyer, refund)?;
+        Ok(())
+    }
+
+    /// Buyer offers `price_per_item` for up to `quantity` NFTs of a verified
+    /// collection whose mints are in the merkle tree rooted at `merkle_root`
+    /// (leaves are `trait_offer_leaf(mint)`). The full amount is escrowed in
+    /// the offer account.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_trait_offer(
+        ctx: Context<CreateTraitOffer>,
+        offer_id: u64,
+        collection: Pubkey,
+        merkle_root: [u8; 32],
+        price_per_item: u64,
+        quantity: u32,
+        expiration_ts: i64,
+    ) -> Result<()> {
+        require!(
+            price_per_item > 0 && quantity > 0,
+            EscrowError::InvalidTraitOffer
+        );
+        require!(
+            expiration_ts > Clock::get()?.unix_timestamp,
+            EscrowError::InvalidTraitOffer
+        );
+        ensure_not_blocked(
+            &ctx.accounts.buyer.key(),
+            &ctx.accounts.buyer_blocklist_page,
+            EscrowError::BuyerBlocked,
+        )?;
+
+        let escrowed = price_per_item
+            .checked_mul(quantity as u64)
+            .ok_or(EscrowError::MathOverflow)?;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.trait_offer.to_account_info(),
+                },
+            ),
+            escrowed,
+        )?;
+
+        let offer = &mut ctx.accounts.trait_offer;
+        offer.bump = ctx.bumps.trait_offer;
+        offer.buyer = ctx.accounts.buyer.key();
+        offer.offer_id = offer_id;
+        offer.collection = collection;
+        offer.merkle_root = merkle_root;
+        offer.price_per_item = price_per_item;
+        offer.quantity = quantity;
+        offer.filled = 0;
+        offer.expiration_ts = expiration_ts;
+        offer.rent_payer = ctx.accounts.payer.key();
+        Ok(())
+    }
+
+    /// A holder sells one eligible NFT into a trait offer. The NFT moves to
+    /// the buyer and the item price is paid out of the offer with the same
+    /// royalty and platform fee split as a listing sale; the creator royalty
+    /// comes from the NFT metadata and must satisfy the collection registry.
+    pub fn fill_trait_offer(ctx: Context<FillTraitOffer>, proof: Vec<[u8; 32]>) -> Result<()> {
+        let offer = &ctx.accounts.trait_offer;
+        require!(
+            Clock::get()?.unix_timestamp <= offer.expiration_ts,
+            EscrowError::TraitOfferExpired
+        );
+        require!(offer.filled < offer.quantity, EscrowError::TraitOfferFilled);
+        require_keys_neq!(
+            ctx.accounts.seller.key(),
+            offer.buyer,
+            EscrowError::SellerCannotFillOwnOffer
+        );
+        ensure_not_blocked(
+            &ctx.accounts.seller.key(),
+            &ctx.accounts.seller_blocklist_page,
+            EscrowError::SellerBlocked,
+        )?;
+        ensure_not_blocked(
+            &offer.buyer,
+            &ctx.accounts.buyer_blocklist_page,
+            EscrowError::BuyerBlocked,
+        )?;
+        require!(
+            verified_collection(&ctx.accounts.nft_metadata) == Some(offer.collection),
+            EscrowError::TraitOfferCollectionMismatch
+        );
+        require!(
+            merkle_proof::verify(
+                &proof,
+                offer.merkle_root,
+                trait_offer_leaf(&ctx.accounts.nft_mint.key())
+            ),
+            EscrowError::MintNotInTraitOffer
+        );
+
+        let royalty_bps = ctx.accounts.nft_metadata.seller_fee_basis_points;
+        let collection_entry = load_collection_entry(
+            &ctx.accounts.nft_metadata,
+            ctx.accounts.collection_entry.as_ref(),
+        )?;
+        let platform_fee_override_bps = match &collection_entry {
+            Some(entry) => {
+                require!(
+                    entry.status != CollectionStatus::Banned,
+                    EscrowError::CollectionBanned
+                );
+                entry
+                    .royalty_policy
+                    .check(royalty_bps, &ctx.accounts.royalty_destination.key())?;
+                entry.platform_fee_override_bps
+            }
+            None => None,
+        };
+        require_keys_eq!(
+            ctx.accounts.developer_wallet.key(),
+            DEVELOPER_WALLET,
+            EscrowError::InvalidDeveloperWallet
+        );
+        let split =
+            compute_sale_split(offer.price_per_item, royalty_bps, platform_fee_override_bps)?;
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.seller_nft_account.to_account_info(),
+                    to: ctx.accounts.buyer_nft_account.to_account_info(),
+                    authority: ctx.accounts.seller.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        let offer_info = ctx.accounts.trait_offer.to_account_info();
+        disburse(
+            &offer_info,
+            &ctx.accounts.seller.to_account_info(),
+            split.seller_payout,
+        )?;
+        disburse(
+            &offer_info,
+            &ctx.accounts.royalty_destination.to_account_info(),
+            split.royalty_cut,
+        )?;
+        disburse(
+            &offer_info,
+            &ctx.accounts.developer_wallet.to_account_info(),
+            split.developer_cut,
+        )?;
+        debit_lamports(&offer_info, split.reward_pool_cut)?;
+        credit_reward_pool(
+            &ctx.accounts.rewards_pool_destination.to_account_info(),
+            split.reward_pool_cut,
+        )?;
+        disburse(
+            &offer_info,
+            &ctx.accounts.ops_treasury_destination.to_account_info(),
+            split.ops_cut,
+        )?;
+
+        let offer = &mut ctx.accounts.trait_offer;
+        offer.filled += 1;
+        Ok(())
+    }
+
+    /// Fills an order the seller signed off-chain. The transaction must carry
+    /// an ed25519 program instruction, immediately before this one, that
+    /// verifies the seller's signature over `signed_order_message(order)`.
+    /// The NFT moves through the program's order authority, to which the
+    /// seller delegated it, and the buyer pays the price split directly.
+    /// The order's nonce account is created so the order cannot fill twice.
+    pub fn fill_signed_order(ctx: Context<FillSignedOrder>, order: SignedOrder) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp <= order.expiration_ts,
+            EscrowError::SignedOrderExpired
+        );
+        require!(order.price_lamports > 0, EscrowError::InvalidListingPrice);
+        require_keys_eq!(
+            ctx.accounts.seller.key(),
+            order.seller,
+            EscrowError::SellerMismatch
+        );
+        require_keys_eq!(
+            ctx.accounts.nft_mint.key(),
+            order.mint,
+            EscrowError::SignedOrderMismatch
+        );
+        require_keys_eq!(
+            ctx.accounts.royalty_destination.key(),
+            order.royalty_destination,
+            EscrowError::RoyaltyDestinationMismatch
+        );
+        require_keys_neq!(
+            ctx.accounts.buyer.key(),
+            order.seller,
+            EscrowError::SellerCannotFillOwnOrder
+        );
+        ensure_not_blocked(
+            &order.seller,
+            &ctx.accounts.seller_blocklist_page,
+            EscrowError::SellerBlocked,
+        )?;
+        ensure_not_blocked(
+            &ctx.accounts.buyer.key(),
+            &ctx.accounts.buyer_blocklist_page,
+            EscrowError::BuyerBlocked,
+        )?;
+
+        let instructions = ctx.accounts.instructions_sysvar.to_account_info();
+        let current_index = load_current_index_checked(&instructions)?;
+        require!(current_index > 0, EscrowError::InvalidOrderSignature);
+        let ed25519_ix = load_instruction_at_checked(current_index as usize - 1, &instructions)?;
+        verify_ed25519_instruction(&ed25519_ix, &order.seller, &signed_order_message(&order)?)?;
+
+        let collection_entry = load_collection_entry(
+            &ctx.accounts.nft_metadata,
+            ctx.accounts.collection_entry.as_ref(),
+        )?;
+        let platform_fee_override_bps = match &collection_entry {
+            Some(entry) => {
+                require!(
+                    entry.status != CollectionStatus::Banned,
+                    EscrowError::CollectionBanned
+                );
+                entry
+                    .royalty_policy
+                    .check(order.royalty_bps, &order.royalty_destination)?;
+                entry.platform_fee_override_bps
+            }
+            None => None,
+        };
+        require_keys_eq!(
+            ctx.accounts.developer_wallet.key(),
+            DEVELOPER_WALLET,
+            EscrowError::InvalidDeveloperWallet
+        );
+        let split = compute_sale_split(
+            order.price_lamports,
+            order.royalty_bps,
+            platform_fee_override_bps,
+        )?;
+
+        let authority_seeds: &[&[u8]] = &[ORDER_AUTHORITY_SEED, &[ctx.bumps.order_authority]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.seller_nft_account.to_account_info(),
+                    to: ctx.accounts.buyer_nft_account.to_account_info(),
+                    authority: ctx.accounts.order_authority.to_account_info(),
+                },
+                &[authority_seeds],
+            ),
+            1,
+        )?;
+
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let buyer = ctx.accounts.buyer.to_account_info();
+        for (recipient, amount) in [
+            (ctx.accounts.seller.to_account_info(), split.seller_payout),
+            (
+                ctx.accounts.royalty_destination.to_account_info(),
+                split.royalty_cut,
+            ),
+            (
+                ctx.accounts.developer_wallet.to_account_info(),
+                split.developer_cut,
+            ),
+            (
+                ctx.accounts.rewards_pool_destination.to_account_info(),
+                split.reward_pool_cut,
+            ),
+            (
+                ctx.accounts.ops_treasury_destination.to_account_info(),
+                split.ops_cut,
+            ),
+        ] {
+            if amount > 0 {
+                system_program::transfer(
+                    CpiContext::new(
+                        system_program.clone(),
+                        system_program::Transfer {
+                            from: buyer.clone(),
+                            to: recipient,
+                        },
+                    ),
+                    amount,
+                )?;
+            }
+        }
+
+        let order_nonce = &mut ctx.accounts.order_nonce;
+        order_nonce.bump = ctx.bumps.order_nonce;
+        order_nonce.seller = order.seller;
+        order_nonce.nonce = order.nonce;
+        order_nonce.status = OrderNonceStatus::Filled;
+        Ok(())
+    }
+
+    /// Seller cancels a signed order before it fills by consuming its nonce.
+    pub fn cancel_signed_order(ctx: Context<CancelSignedOrder>, nonce: u64) -> Result<()> {
+        let order_nonce = &mut ctx.accounts.order_nonce;
+        order_nonce.bump = ctx.bumps.order_nonce;
+        order_nonce.seller = ctx.accounts.seller.key();
+        order_nonce.nonce = nonce;
+        order_nonce.status = OrderNonceStatus::Cancelled;
+        Ok(())
+    }
+
+    /// Buyer closes a trait offer, recovering the escrow for unfilled items.
+    /// The account rent goes back to whoever paid it. Allowed at any time,
+    /// including after expiry.
+    pub fn cancel_trait_offer(ctx: Context<CancelTraitOffer>) -> Result<()> {
+        let offer = &ctx.accounts.trait_offer;
+        disburse(
+            &offer.to_account_info(),
+            &ctx.accounts.buyer.to_account_info(),
+            offer.unfilled_escrow()?,
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(listing_id: u64)]
+pub struct CreateListing<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Listing::LEN,
+        seeds = [LISTING_SEED, seller.key().as_ref(), nft_mint.key().as_ref(), &listing_id.to_le_bytes()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    pub seller: Signer<'info>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        seeds = [METADATA_SEED, metadata_program.key().as_ref(), nft_mint.key().as_ref()],
+        seeds::program = metadata_program.key(),
+        bump
+    )]
+    pub nft_metadata: Box<Account<'info, MetadataAccount>>,
+    /// CHECK: blocklist page for the seller; validated in `ensure_not_blocked`.
+    pub seller_blocklist_page: UncheckedAccount<'info>,
+    /// CHECK: registry entry PDA for the NFT's verified collection, required
+    /// when the NFT has one; validated in `load_collection_entry`. May be
+    /// uninitialized for collections the registry has not reviewed yet.
+    pub collection_entry: Option<UncheckedAccount<'info>>,
+    /// CHECK: stored and verified during settlement
+    pub royalty_destination: UncheckedAccount<'info>,
+    pub metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelListing<'info> {
+    #[account(mut, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteSale<'info> {
+    #[account(mut, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// CHECK: Seller receives payouts during settlement.
+    pub seller: UncheckedAccount<'info>,
+    /// Buyer's loyalty profile; qualifies the buyer during an early-access
+    /// window by tier.
+    pub buyer_loyalty_profile: Option<Account<'info, LoyaltyProfile>>,
+    /// Buyer's CLOUT stake in the market's early-access pool.
+    pub buyer_stake_position: Option<Account<'info, StakePosition>>,
+    /// Supplies the buyer cancellation window and qualifies the buyer by
+    /// stake.
+    #[account(seeds = [MARKET_CONFIG_SEED], bump = market_config.bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    /// CHECK: blocklist page for the seller; validated in `ensure_not_blocked`.
+    pub seller_blocklist_page: UncheckedAccount<'info>,
+    /// CHECK: blocklist page for the buyer; validated in `ensure_not_blocked`.
+    pub buyer_blocklist_page: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPurchase<'info> {
+    #[account(mut)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleSale<'info> {
+    #[account(mut, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    /// CHECK: buyer validated against listing.buyer
+    pub buyer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub developer_wallet: SystemAccount<'info>,
+    /// CHECK: the `clout_staking` revenue pool recorded in the market config.
+    #[account(
+        mut,
+        constraint = market_config.has_revenue_pool() @ EscrowError::RevenuePoolNotConfigured,
+        address = market_config.revenue_pool @ EscrowError::InvalidRevenuePool
+    )]
+    pub rewards_pool_destination: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub ops_treasury_destination: SystemAccount<'info>,
+    #[account(mut)]
+    pub royalty_destination: SystemAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = SaleReceipt::LEN,
+        seeds = [RECEIPT_SEED, listing.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, SaleReceipt>,
+    /// CHECK: the listed mint's metadata PDA, read by
+    /// `load_optional_metadata`; mints without metadata leave it empty.
+    #[account(
+        seeds = [METADATA_SEED, metadata_program.key().as_ref(), listing.mint.as_ref()],
+        seeds::program = metadata_program.key(),
+        bump
+    )]
+    pub nft_metadata: UncheckedAccount<'info>,
+    /// CHECK: the collection's `CollectionStats` PDA, required when the
+    /// listed NFT belongs to a verified collection and created on its first
+    /// sale.
+    #[account(mut)]
+    pub collection_stats: Option<UncheckedAccount<'info>>,
+    #[account(mut, seeds = [MARKET_CONFIG_SEED], bump = market_config.bump)]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    /// CHECK: buyer's `TradeHistory`, created on first use in `record_trade`.
+    #[account(mut, seeds = [TRADE_HISTORY_SEED, buyer.key().as_ref()], bump)]
+    pub buyer_trade_history: UncheckedAccount<'info>,
+    /// CHECK: seller's `TradeHistory`, created on first use in `record_trade`.
+    #[account(mut, seeds = [TRADE_HISTORY_SEED, seller.key().as_ref()], bump)]
+    pub seller_trade_history: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = MintSaleRecord::LEN,
+        seeds = [MINT_SALE_RECORD_SEED, listing.mint.as_ref()],
+        bump
+    )]
+    pub mint_sale_record: Box<Account<'info, MintSaleRecord>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PairVolume::LEN,
+        seeds = [
+            PAIR_VOLUME_SEED,
+            ordered_pair(seller.key, buyer.key).0.as_ref(),
+            ordered_pair(seller.key, buyer.key).1.as_ref()
+        ],
+        bump
+    )]
+    pub pair_volume: Box<Account<'info, PairVolume>>,
+    #[account(
+        mut,
+        has_one = reward_mint @ EscrowError::MismatchedRewardMint
+    )]
+    pub reward_vault: Account<'info, VaultConfig>,
+    /// CHECK: PDA signer validated via seeds.
+    #[account(
+        seeds = [VAULT_SIGNER_SEED, reward_mint.key().as_ref()],
+        bump = reward_vault.signer_bump
+    )]
+    pub vault_signer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub reward_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = buyer_reward_account.mint == reward_mint.key(),
+        constraint = buyer_reward_account.owner == buyer.key()
+    )]
+    pub buyer_reward_account: Account<'info, TokenAccount>,
+    pub reward_authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, buyer.key().as_ref()],
+        bump = loyalty_profile.bump,
+        seeds::program = loyalty_program.key()
+    )]
+    pub loyalty_profile: Account<'info, LoyaltyProfile>,
+    #[account(
+        mut,
+        seeds = [REGISTRY_CONFIG_SEED],
+        bump = loyalty_registry_config.bump,
+        seeds::program = loyalty_program.key(),
+        constraint = loyalty_registry_config.authority == loyalty_authority.key() @ EscrowError::UnauthorizedLoyaltyAuthority
+    )]
+    pub loyalty_registry_config: Account<'info, RegistryConfig>,
+    pub loyalty_authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub rewards_vault_program: Program<'info, RewardsVaultProgram>,
+    pub loyalty_program: Program<'info, LoyaltyRegistryProgram>,
+    pub metadata_program: Program<'info, Metadata>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleMany<'info> {
+    /// Pays rent for the sale receipts and wash-trading records created in
+    /// the batch.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, seeds = [MARKET_CONFIG_SEED], bump = market_config.bump)]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    #[account(mut)]
+    pub developer_wallet: SystemAccount<'info>,
+    /// CHECK: the `clout_staking` revenue pool recorded in the market config.
+    #[account(
+        mut,
+        constraint = market_config.has_revenue_pool() @ EscrowError::RevenuePoolNotConfigured,
+        address = market_config.revenue_pool @ EscrowError::InvalidRevenuePool
+    )]
+    pub rewards_pool_destination: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub ops_treasury_destination: SystemAccount<'info>,
+    #[account(
+        mut,
+        has_one = reward_mint @ EscrowError::MismatchedRewardMint
+    )]
+    pub reward_vault: Account<'info, VaultConfig>,
+    /// CHECK: PDA signer validated via seeds.
+    #[account(
+        seeds = [VAULT_SIGNER_SEED, reward_mint.key().as_ref()],
+        bump = reward_vault.signer_bump
+    )]
+    pub vault_signer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub reward_mint: Account<'info, Mint>,
+    pub reward_authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [REGISTRY_CONFIG_SEED],
+        bump = loyalty_registry_config.bump,
+        seeds::program = loyalty_program.key(),
+        constraint = loyalty_registry_config.authority == loyalty_authority.key() @ EscrowError::UnauthorizedLoyaltyAuthority
+    )]
+    pub loyalty_registry_config: Account<'info, RegistryConfig>,
+    pub loyalty_authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub rewards_vault_program: Program<'info, RewardsVaultProgram>,
+    pub loyalty_program: Program<'info, LoyaltyRegistryProgram>,
+    pub metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteSale<'info> {
+    pub listing: Account<'info, Listing>,
+    #[account(
+        seeds = [REGISTRY_CONFIG_SEED],
+        bump = loyalty_registry_config.bump,
+        seeds::program = loyalty_registry::ID
+    )]
+    pub loyalty_registry_config: Account<'info, RegistryConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireListing<'info> {
+    #[account(mut, has_one = rent_payer, close = rent_payer)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(seeds = [MARKET_CONFIG_SEED], bump = market_config.bump)]
+    pub market_config: Account<'info, MarketConfig>,
+    #[account(mut)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMarketConfig<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = MarketConfig::LEN,
+        seeds = [MARKET_CONFIG_SEED],
+        bump
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    /// Must be the program's upgrade authority.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key())
+            @ EscrowError::UnauthorizedConfigInitializer
+    )]
+    pub program: Program<'info, crate::program::MarketEscrow>,
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarketConfig<'info> {
+    #[account(
+        mut,
+        seeds = [MARKET_CONFIG_SEED],
+        bump = market_config.bump,
+        has_one = authority @ EscrowError::UnauthorizedMarketAuthority
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRevenuePool<'info> {
+    #[account(
+        mut,
+        seeds = [MARKET_CONFIG_SEED],
+        bump = market_config.bump,
+        has_one = authority @ EscrowError::UnauthorizedMarketAuthority
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub authority: Signer<'info>,
+    pub staking_pool: Account<'info, StakingPool>,
+    #[account(
+        seeds = [REVENUE_POOL_SEED, staking_pool.key().as_ref()],
+        bump = revenue_pool.bump,
+        seeds::program = clout_staking::ID
+    )]
+    pub revenue_pool: Account<'info, RevenuePool>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateListing<'info> {
+    /// CHECK: may still use the unversioned layout; validated in `migrate_account`.
+    #[account(mut, owner = crate::ID)]
+    pub listing: UncheckedAccount<'info>,
+    /// CHECK: may still be a program-owned `EscrowVault`; converted in
+    /// `convert_legacy_escrow_vault`.
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump
+    )]
+    pub escrow_vault: UncheckedAccount<'info>,
+    /// Receives the legacy vault's excess rent; must be the listing's rent
+    /// payer.
+    #[account(mut)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateReceipt<'info> {
+    /// CHECK: may still use the unversioned layout; validated in `migrate_account`.
+    #[account(mut, owner = crate::ID)]
+    pub receipt: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSealedAuction<'info> {
+    #[account(mut, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = SealedAuction::LEN,
+        seeds = [SEALED_AUCTION_SEED, listing.key().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, SealedAuction>,
+    pub seller: Signer<'info>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitBid<'info> {
+    #[account(mut)]
+    pub auction: Account<'info, SealedAuction>,
+    #[account(
+        init,
+        payer = payer,
+        space = SealedBid::LEN,
+        seeds = [SEALED_BID_SEED, auction.key().as_ref(), bidder.key().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, SealedBid>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: blocklist page for the auction seller; validated in
+    /// `ensure_not_blocked`.
+    pub seller_blocklist_page: UncheckedAccount<'info>,
+    /// CHECK: blocklist page for the bidder; validated in `ensure_not_blocked`.
+    pub bidder_blocklist_page: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealBid<'info> {
+    #[account(mut)]
+    pub auction: Account<'info, SealedAuction>,
+    #[account(
+        mut,
+        has_one = auction,
+        has_one = bidder,
+        seeds = [SEALED_BID_SEED, auction.key().as_ref(), bidder.key().as_ref()],
+        bump = bid.bump
+    )]
+    pub bid: Account<'info, SealedBid>,
+    pub bidder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeSealedAuction<'info> {
+    #[account(mut, has_one = listing)]
+    pub auction: Account<'info, SealedAuction>,
+    #[account(mut)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    /// Required when the auction has a winner.
+    #[account(mut)]
+    pub winning_bid: Option<Account<'info, SealedBid>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBid<'info> {
+    #[account(has_one = seller)]
+    pub auction: Account<'info, SealedAuction>,
+    #[account(
+        mut,
+        has_one = auction,
+        has_one = bidder,
+        has_one = rent_payer,
+        close = rent_payer
+    )]
+    pub bid: Account<'info, SealedBid>,
+    #[account(mut)]
+    pub bidder: SystemAccount<'info>,
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+    #[account(mut)]
+    pub rent_payer: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCollectionStats<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = CollectionStats::LEN,
+        seeds = [COLLECTION_STATS_SEED, collection_mint.key().as_ref()],
+        bump
+    )]
+    pub collection_stats: Account<'info, CollectionStats>,
+    pub collection_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterCollection<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = CollectionEntry::LEN,
+        seeds = [COLLECTION_ENTRY_SEED, collection_mint.key().as_ref()],
+        bump
+    )]
+    pub collection_entry: Account<'info, CollectionEntry>,
+    pub collection_mint: Account<'info, Mint>,
+    #[account(
+        seeds = [MARKET_CONFIG_SEED],
+        bump = market_config.bump,
+        has_one = authority @ EscrowError::UnauthorizedMarketAuthority
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub authority: Signer<'info>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCollection<'info> {
+    #[account(
+        mut,
+        seeds = [COLLECTION_ENTRY_SEED, collection_entry.collection.as_ref()],
+        bump = collection_entry.bump
+    )]
+    pub collection_entry: Account<'info, CollectionEntry>,
+    #[account(
+        seeds = [MARKET_CONFIG_SEED],
+        bump = market_config.bump,
+        has_one = authority @ EscrowError::UnauthorizedMarketAuthority
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct BlockWallet<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = BlocklistPage::LEN,
+        seeds = [BLOCKLIST_PAGE_SEED, &[blocklist_page_index(&wallet)]],
+        bump
+    )]
+    pub blocklist_page: Account<'info, BlocklistPage>,
+    #[account(
+        seeds = [MARKET_CONFIG_SEED],
+        bump = market_config.bump,
+        has_one = compliance_authority @ EscrowError::UnauthorizedComplianceAuthority
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub compliance_authority: Signer<'info>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct UnblockWallet<'info> {
+    #[account(
+        mut,
+        seeds = [BLOCKLIST_PAGE_SEED, &[blocklist_page_index(&wallet)]],
+        bump = blocklist_page.bump
+    )]
+    pub blocklist_page: Account<'info, BlocklistPage>,
+    #[account(
+        seeds = [MARKET_CONFIG_SEED],
+        bump = market_config.bump,
+        has_one = compliance_authority @ EscrowError::UnauthorizedComplianceAuthority
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub compliance_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EnablePhysicalRedemption<'info> {
+    #[account(mut, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmDelivery<'info> {
+    #[account(mut)]
+    pub listing: Account<'info, Listing>,
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    #[account(mut)]
+    pub listing: Account<'info, Listing>,
+    /// Buyer or seller of the listing.
+    pub party: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(mut, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+    #[account(
+        seeds = [MARKET_CONFIG_SEED],
+        bump = market_config.bump,
+        has_one = redemption_arbiter @ EscrowError::UnauthorizedArbiter
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub redemption_arbiter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCloutFeeOracle<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = CloutFeeOracle::LEN,
+        seeds = [CLOUT_FEE_ORACLE_SEED],
+        bump
+    )]
+    pub clout_fee_oracle: Account<'info, CloutFeeOracle>,
+    pub clout_mint: Account<'info, Mint>,
+    #[account(constraint = reward_pool_account.mint == clout_mint.key() @ EscrowError::MismatchedRewardMint)]
+    pub reward_pool_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [MARKET_CONFIG_SEED],
+        bump = market_config.bump,
+        has_one = authority @ EscrowError::UnauthorizedMarketAuthority
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub authority: Signer<'info>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetCloutFeeTerms<'info> {
+    #[account(mut, seeds = [CLOUT_FEE_ORACLE_SEED], bump = clout_fee_oracle.bump)]
+    pub clout_fee_oracle: Account<'info, CloutFeeOracle>,
+    #[account(
+        seeds = [MARKET_CONFIG_SEED],
+        bump = market_config.bump,
+        has_one = authority @ EscrowError::UnauthorizedMarketAuthority
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCloutRate<'info> {
+    #[account(
+        mut,
+        seeds = [CLOUT_FEE_ORACLE_SEED],
+        bump = clout_fee_oracle.bump,
+        has_one = oracle_authority @ EscrowError::UnauthorizedOracleAuthority
+    )]
+    pub clout_fee_oracle: Account<'info, CloutFeeOracle>,
+    pub oracle_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PayFeesInClout<'info> {
+    #[account(mut)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(
+        seeds = [CLOUT_FEE_ORACLE_SEED],
+        bump = clout_fee_oracle.bump,
+        has_one = clout_mint @ EscrowError::MismatchedRewardMint,
+        has_one = reward_pool_account @ EscrowError::InvalidPayoutAccount
+    )]
+    pub clout_fee_oracle: Account<'info, CloutFeeOracle>,
+    #[account(mut)]
+    pub clout_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = buyer_clout_account.mint == clout_mint.key() @ EscrowError::MismatchedRewardMint,
+        constraint = buyer_clout_account.owner == buyer.key()
+    )]
+    pub buyer_clout_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub reward_pool_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OfferInstallments<'info> {
+    #[account(has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        init,
+        payer = payer,
+        space = InstallmentPlan::LEN,
+        seeds = [INSTALLMENT_PLAN_SEED, listing.key().as_ref()],
+        bump
+    )]
+    pub installment_plan: Account<'info, InstallmentPlan>,
+    pub seller: Signer<'info>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawInstallments<'info> {
+    #[account(mut, has_one = seller, has_one = rent_payer, close = rent_payer)]
+    pub installment_plan: Account<'info, InstallmentPlan>,
+    pub seller: Signer<'info>,
+    #[account(mut)]
+    pub rent_payer: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StartInstallmentPlan<'info> {
+    #[account(mut)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [INSTALLMENT_PLAN_SEED, listing.key().as_ref()],
+        bump = installment_plan.bump
+    )]
+    pub installment_plan: Account<'info, InstallmentPlan>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// Buyer's loyalty profile; qualifies the buyer during an early-access
+    /// window by tier.
+    pub buyer_loyalty_profile: Option<Account<'info, LoyaltyProfile>>,
+    /// Buyer's CLOUT stake in the market's early-access pool.
+    pub buyer_stake_position: Option<Account<'info, StakePosition>>,
+    /// Names the early-access pool when the buyer qualifies by stake.
+    #[account(seeds = [MARKET_CONFIG_SEED], bump = market_config.bump)]
+    pub market_config: Option<Account<'info, MarketConfig>>,
+    /// CHECK: blocklist page for the seller; validated in `ensure_not_blocked`.
+    pub seller_blocklist_page: UncheckedAccount<'info>,
+    /// CHECK: blocklist page for the buyer; validated in `ensure_not_blocked`.
+    pub buyer_blocklist_page: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PayInstallment<'info> {
+    #[account(mut)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [INSTALLMENT_PLAN_SEED, listing.key().as_ref()],
+        bump = installment_plan.bump
+    )]
+    pub installment_plan: Account<'info, InstallmentPlan>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DefaultInstallmentPlan<'info> {
+    #[account(mut, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [INSTALLMENT_PLAN_SEED, listing.key().as_ref()],
+        bump = installment_plan.bump
+    )]
+    pub installment_plan: Account<'info, InstallmentPlan>,
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateRaffle<'info> {
+    #[account(mut, has_one = seller)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = Raffle::LEN,
+        seeds = [RAFFLE_SEED, listing.key().as_ref()],
+        bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+    pub seller: Signer<'info>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(page_index: u32)]
+pub struct BuyRaffleTickets<'info> {
+    #[account(mut, has_one = listing)]
+    pub raffle: Account<'info, Raffle>,
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = RaffleTicketPage::LEN,
+        seeds = [
+            RAFFLE_TICKET_PAGE_SEED,
+            raffle.key().as_ref(),
+            &page_index.to_le_bytes()
+        ],
+        bump
+    )]
+    pub ticket_page: Account<'info, RaffleTicketPage>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: blocklist page for the raffle seller; validated in
+    /// `ensure_not_blocked`.
+    pub seller_blocklist_page: UncheckedAccount<'info>,
+    /// CHECK: blocklist page for the buyer; validated in `ensure_not_blocked`.
+    pub buyer_blocklist_page: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealRaffleSeed<'info> {
+    #[account(mut, has_one = seller)]
+    pub raffle: Account<'info, Raffle>,
+    pub seller: Signer<'info>,
+    /// CHECK: the SlotHashes sysvar, read in `most_recent_slot_hash`.
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(page_index: u32)]
+pub struct AssignRaffleWinner<'info> {
+    #[account(mut, has_one = listing)]
+    pub raffle: Account<'info, Raffle>,
+    #[account(mut)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        seeds = [
+            RAFFLE_TICKET_PAGE_SEED,
+            raffle.key().as_ref(),
+            &page_index.to_le_bytes()
+        ],
+        bump = ticket_page.bump
+    )]
+    pub ticket_page: Account<'info, RaffleTicketPage>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRaffle<'info> {
+    #[account(mut, has_one = listing)]
+    pub raffle: Account<'info, Raffle>,
+    #[account(mut)]
+    pub listing: Account<'info, Listing>,
+}
+
+#[derive(Accounts)]
+#[instruction(page_index: u32)]
+pub struct ClaimRaffleRefund<'info> {
+    #[account(mut, has_one = listing)]
+    pub raffle: Account<'info, Raffle>,
+    #[account(mut)]
+    pub listing: Account<'info, Listing>,
+    #[account(
+        mut,
+        seeds = [ESCROW_VAULT_SEED, listing.key().as_ref()],
+        bump = listing.escrow_bump
+    )]
+    pub escrow_vault: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            RAFFLE_TICKET_PAGE_SEED,
+            raffle.key().as_ref(),
+            &page_index.to_le_bytes()
+        ],
+        bump = ticket_page.bump
+    )]
+    pub ticket_page: Account<'info, RaffleTicketPage>,
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(offer_id: u64)]
+pub struct CreateTraitOffer<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = TraitOffer::LEN,
+        seeds = [TRAIT_OFFER_SEED, buyer.key().as_ref(), &offer_id.to_le_bytes()],
+        bump
+    )]
+    pub trait_offer: Account<'info, TraitOffer>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: blocklist page for the buyer; validated in `ensure_not_blocked`.
+    pub buyer_blocklist_page: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FillTraitOffer<'info> {
+    #[account(
+        mut,
+        has_one = buyer,
+        seeds = [
+            TRAIT_OFFER_SEED,
+            buyer.key().as_ref(),
+            &trait_offer.offer_id.to_le_bytes()
+        ],
+        bump = trait_offer.bump
+    )]
+    pub trait_offer: Box<Account<'info, TraitOffer>>,
+    /// CHECK: offer buyer, validated by `has_one`; receives the NFT.
+    pub buyer: UncheckedAccount<'info>,
+    /// Holder selling into the offer.
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    /// Pays rent for the buyer's token account if it does not exist yet; may
+    /// be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub nft_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint = nft_mint,
+        token::authority = seller
+    )]
+    pub seller_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = nft_mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        seeds = [METADATA_SEED, metadata_program.key().as_ref(), nft_mint.key().as_ref()],
+        seeds::program = metadata_program.key(),
+        bump
+    )]
+    pub nft_metadata: Box<Account<'info, MetadataAccount>>,
+    /// CHECK: registry entry for the NFT's verified collection; validated in
+    /// `load_collection_entry`.
+    pub collection_entry: Option<UncheckedAccount<'info>>,
+    #[account(seeds = [MARKET_CONFIG_SEED], bump = market_config.bump)]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    #[account(mut)]
+    pub developer_wallet: SystemAccount<'info>,
+    /// CHECK: the `clout_staking` revenue pool recorded in the market config.
+    #[account(
+        mut,
+        constraint = market_config.has_revenue_pool() @ EscrowError::RevenuePoolNotConfigured,
+        address = market_config.revenue_pool @ EscrowError::InvalidRevenuePool
+    )]
+    pub rewards_pool_destination: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub ops_treasury_destination: SystemAccount<'info>,
+    #[account(mut)]
+    pub royalty_destination: SystemAccount<'info>,
+    /// CHECK: blocklist page for the seller; validated in `ensure_not_blocked`.
+    pub seller_blocklist_page: UncheckedAccount<'info>,
+    /// CHECK: blocklist page for the buyer; validated in `ensure_not_blocked`.
+    pub buyer_blocklist_page: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(order: SignedOrder)]
+pub struct FillSignedOrder<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = OrderNonce::LEN,
+        seeds = [ORDER_NONCE_SEED, order.seller.as_ref(), &order.nonce.to_le_bytes()],
+        bump
+    )]
+    pub order_nonce: Box<Account<'info, OrderNonce>>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(mut)]
+    pub seller: SystemAccount<'info>,
+    /// Pays rent for the order nonce and the buyer's token account; may be a
+    /// relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: PDA the seller delegated the NFT to; signs the transfer.
+    #[account(seeds = [ORDER_AUTHORITY_SEED], bump)]
+    pub order_authority: UncheckedAccount<'info>,
+    pub nft_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint = nft_mint,
+        token::authority = seller,
+        constraint = seller_nft_account.delegate == COption::Some(order_authority.key())
+            @ EscrowError::OrderNotDelegated
+    )]
+    pub seller_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = nft_mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        seeds = [METADATA_SEED, metadata_program.key().as_ref(), nft_mint.key().as_ref()],
+        seeds::program = metadata_program.key(),
+        bump
+    )]
+    pub nft_metadata: Box<Account<'info, MetadataAccount>>,
+    /// CHECK: registry entry for the NFT's verified collection; validated in
+    /// `load_collection_entry`.
+    pub collection_entry: Option<UncheckedAccount<'info>>,
+    #[account(seeds = [MARKET_CONFIG_SEED], bump = market_config.bump)]
+    pub market_config: Box<Account<'info, MarketConfig>>,
+    #[account(mut)]
+    pub developer_wallet: SystemAccount<'info>,
+    /// CHECK: the `clout_staking` revenue pool recorded in the market config.
+    #[account(
+        mut,
+        constraint = market_config.has_revenue_pool() @ EscrowError::RevenuePoolNotConfigured,
+        address = market_config.revenue_pool @ EscrowError::InvalidRevenuePool
+    )]
+    pub rewards_pool_destination: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub ops_treasury_destination: SystemAccount<'info>,
+    #[account(mut)]
+    pub royalty_destination: SystemAccount<'info>,
+    /// CHECK: blocklist page for the seller; validated in `ensure_not_blocked`.
+    pub seller_blocklist_page: UncheckedAccount<'info>,
+    /// CHECK: blocklist page for the buyer; validated in `ensure_not_blocked`.
+    pub buyer_blocklist_page: UncheckedAccount<'info>,
+    /// CHECK: the instructions sysvar, read to find the ed25519 check.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct CancelSignedOrder<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = OrderNonce::LEN,
+        seeds = [ORDER_NONCE_SEED, seller.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub order_nonce: Account<'info, OrderNonce>,
+    pub seller: Signer<'info>,
+    /// Pays rent for the accounts created here; may be a relayer.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTraitOffer<'info> {
+    #[account(mut, has_one = buyer, has_one = rent_payer, close = rent_payer)]
+    pub trait_offer: Account<'info, TraitOffer>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    #[account(mut)]
+    pub rent_payer: SystemAccount<'info>,
+}
+
+#[account]
+pub struct Listing {
+    pub version: u8,
+    pub bump: u8,
+    pub escrow_bump: u8,
+    pub seller: Pubkey,
+    pub buyer: Option<Pubkey>,
+    pub mint: Pubkey,
+    pub listing_id: u64,
+    pub price_lamports: u64,
+    pub creation_ts: i64,
+    pub expiration_ts: Option<i64>,
+    pub sale_ts: Option<i64>,
+    pub settlement_ts: Option<i64>,
+    pub status: ListingStatus,
+    pub royalty_bps: u16,
+    pub royalty_destination: Pubkey,
+    pub treasury_bps: u16,
+    pub marketplace_fee_bps: u16,
+    pub sale_mode: SaleMode,
+    pub early_access: Option<EarlyAccess>,
+    pub platform_fee_override_bps: Option<u16>,
+    pub redemption: Option<Redemption>,
+    pub clout_fee_payment: Option<CloutFeePayment>,
+    /// Wallets splitting the seller proceeds; empty pays the seller.
+    pub payout_destinations: Vec<PayoutDestination>,
+    /// Paid rent for the listing and escrow vault; refunded when they close.
+    pub rent_payer: Pubkey,
+    /// End of the buyer's window to cancel a direct purchase; settlement
+    /// waits until it has passed.
+    pub buyer_cancel_deadline_ts: Option<i64>,
+    pub reserved: [u8; Listing::RESERVED_LEN],
+}
+
+impl Listing {
+    pub const VERSION: u8 = 2;
+    pub const RESERVED_LEN: usize = 6;
+    pub const LEN: usize = 8 // discriminator
+        + 1 // version
+        + 1 // bump
+        + 1 // escrow bump
+        + 32 // seller
+        + 1 + 32 // buyer option
+        + 32 // mint
+        + 8 // listing id
+        + 8 // price
+        + 8 // creation ts
+        + 1 + 8 // expiration option
+        + 1 + 8 // sale ts option
+        + 1 + 8 // settlement ts option
+        + 1 // status enum
+        + 2 // royalty bps
+        + 32 // royalty destination
+        + 2 // treasury bps
+        + 2 // marketplace fee bps
+        + 1 // sale mode enum
+        + 1 + EarlyAccess::LEN // early access option
+        + 1 + 2 // platform fee override option
+        + 1 + Redemption::LEN // redemption option
+        + 1 + CloutFeePayment::LEN // clout fee payment option
+        + 4 + MAX_PAYOUT_DESTINATIONS * PayoutDestination::LEN // payout destinations vec
+        + 32 // rent payer
+        + 1 + 8 // buyer cancel deadline option
+        + Listing::RESERVED_LEN; // reserved for future fields
+}
+
+/// Escrow vault layout from before vaults became data-less system accounts.
+/// Only read by `migrate_listing`, which converts these accounts in place.
 #[account]
 pub struct EscrowVault {
+    pub version: u8,
+    pub bump: u8,
+    pub listing: Pubkey,
+    pub total_deposited: u64,
+    pub reserved: [u8; EscrowVault::RESERVED_LEN],
+}
+
+impl EscrowVault {
+    pub const VERSION: u8 = 1;
+    pub const RESERVED_LEN: usize = 32;
+    pub const LEN: usize = 8 // discriminator
+        + 1 // version
+        + 1 // bump
+        + 32 // listing pubkey
+        + 8 // lamports deposited
+        + EscrowVault::RESERVED_LEN; // reserved for future fields
+}
+
+#[account]
+pub struct SaleReceipt {
+    pub version: u8,
+    pub bump: u8,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub amount_paid: u64,
+    pub seller_proceeds: u64,
+    pub royalty_paid: u64,
+    pub treasury_paid: u64,
+    pub marketplace_fee_paid: u64,
+    pub developer_paid: u64,
+    pub rewards_minted: u64,
+    pub loyalty_points_awarded: u64,
+    pub timestamp: i64,
+    /// Why rewards and loyalty activity were withheld, if they were.
+    pub rewards_suppressed: Option<RewardSuppression>,
+    /// CLOUT paid in place of the SOL platform fee, including the burned part.
+    pub clout_fee_paid: u64,
+    pub clout_fee_burned: u64,
+    /// How `seller_proceeds` was split when the listing had payout
+    /// destinations; empty when it all went to the seller.
+    pub seller_payouts: Vec<SellerPayout>,
+    /// Position in the market-wide order of settled sales, counting from 1;
+    /// zero on receipts migrated from before sales were sequenced.
+    pub sequence: u64,
+    pub reserved: [u8; SaleReceipt::RESERVED_LEN],
+}
+
+impl SaleReceipt {
+    pub const VERSION: u8 = 2;
+    pub const RESERVED_LEN: usize = 6;
+    pub const LEN: usize = 8 // discriminator
+        + 1 // version
+        + 1 // bump
+        + 32 // listing
+        + 32 // buyer
+        + 32 // seller
+        + 8 // amount paid
+        + 8 // seller proceeds
+        + 8 // royalty paid
+        + 8 // treasury paid
+        + 8 // marketplace fee
+        + 8 // developer paid
+        + 8 // rewards minted
+        + 8 // loyalty points
+        + 8 // timestamp
+        + 1 + 1 // rewards suppressed option
+        + 8 // clout fee paid
+        + 8 // clout fee burned
+        + 4 + MAX_PAYOUT_DESTINATIONS * SellerPayout::LEN // seller payouts vec
+        + 8 // sequence
+        + SaleReceipt::RESERVED_LEN; // reserved for future fields
+}
+
+/// Exchange rate and terms for paying platform fees in CLOUT.
+#[account]
+pub struct CloutFeeOracle {
+    pub bump: u8,
+    /// Publishes `clout_per_sol`.
+    pub oracle_authority: Pubkey,
+    pub clout_mint: Pubkey,
+    /// CLOUT token account receiving the unburned part of fee payments.
+    pub reward_pool_account: Pubkey,
+    pub terms: CloutFeeTerms,
+    /// CLOUT base units per SOL.
+    pub clout_per_sol: u64,
+    pub rate_updated_ts: i64,
+}
+
+impl CloutFeeOracle {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // oracle authority
+        + 32 // clout mint
+        + 32 // reward pool account
+        + CloutFeeTerms::LEN // terms
+        + 8 // clout per sol
+        + 8; // rate updated ts
+
+    /// CLOUT owed for a platform fee of `fee_lamports` after the discount,
+    /// rounded up.
+    fn clout_fee_for(&self, fee_lamports: u64) -> Result<u64> {
+        let discounted_bps = BPS_DENOMINATOR - self.terms.discount_bps as u64;
+        let numerator = (fee_lamports as u128)
+            .checked_mul(self.clout_per_sol as u128)
+            .and_then(|value| value.checked_mul(discounted_bps as u128))
+            .ok_or(EscrowError::MathOverflow)?;
+        let denominator = LAMPORTS_PER_SOL as u128 * BPS_DENOMINATOR as u128;
+        let clout = numerator.div_ceil(denominator);
+        u64::try_from(clout).map_err(|_| error!(EscrowError::MathOverflow))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct CloutFeeTerms {
+    /// Discount on the platform fee when paid in CLOUT.
+    pub discount_bps: u16,
+    /// Share of the CLOUT payment that is burned; the rest goes to the
+    /// reward pool.
+    pub burn_bps: u16,
+    /// Oldest exchange rate `pay_fees_in_clout` accepts.
+    pub max_rate_age_secs: u32,
+}
+
+impl CloutFeeTerms {
+    pub const LEN: usize = 2 // discount bps
+        + 2 // burn bps
+        + 4; // max rate age secs
+}
+
+/// Last sale of a mint, used to spot NFTs that change hands within the
+/// wash-trading cooldown.
+#[account]
+#[derive(Default)]
+pub struct MintSaleRecord {
+    pub bump: u8,
+    pub mint: Pubkey,
+    pub last_seller: Pubkey,
+    pub last_buyer: Pubkey,
+    pub last_sale_ts: i64,
+}
+
+impl MintSaleRecord {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // mint
+        + 32 // last seller
+        + 32 // last buyer
+        + 8; // last sale ts
+}
+
+/// Volume traded between two wallets, in either direction, during the
+/// current window. `wallet_a` sorts before `wallet_b`.
+#[account]
+#[derive(Default)]
+pub struct PairVolume {
+    pub bump: u8,
+    pub wallet_a: Pubkey,
+    pub wallet_b: Pubkey,
+    pub window_start_ts: i64,
+    pub window_volume: u64,
+}
+
+impl PairVolume {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // wallet a
+        + 32 // wallet b
+        + 8 // window start ts
+        + 8; // window volume
+}
+
+/// A wallet's most recent settled trades, newest overwriting oldest once
+/// `TRADE_HISTORY_CAPACITY` is reached. Updated at settlement so wallets can
+/// list recent trades without an indexer; see `TradeHistory::recent`.
+#[account]
+#[derive(Default)]
+pub struct TradeHistory {
+    pub bump: u8,
+    pub wallet: Pubkey,
+    /// Trades recorded since the history was created.
+    pub total_trades: u64,
+    /// Slot the next trade is written to; the oldest entry once full.
+    pub next_index: u16,
+    pub entries: [TradeHistoryEntry; TRADE_HISTORY_CAPACITY],
+}
+
+impl TradeHistory {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // wallet
+        + 8 // total trades
+        + 2 // next index
+        + TRADE_HISTORY_CAPACITY * TradeHistoryEntry::LEN; // entries
+
+    fn record(&mut self, entry: TradeHistoryEntry) -> Result<()> {
+        self.entries[self.next_index as usize] = entry;
+        self.next_index = ((self.next_index as usize + 1) % TRADE_HISTORY_CAPACITY) as u16;
+        self.total_trades = self
+            .total_trades
+            .checked_add(1)
+            .ok_or(EscrowError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Recorded trades still in the buffer, newest first.
+    pub fn recent(&self) -> impl Iterator<Item = &TradeHistoryEntry> {
+        let len = self.total_trades.min(TRADE_HISTORY_CAPACITY as u64) as usize;
+        let next_index = self.next_index as usize;
+        (1..=len).map(move |age| {
+            &self.entries[(next_index + TRADE_HISTORY_CAPACITY - age) % TRADE_HISTORY_CAPACITY]
+        })
+    }
+}
+
+/// Market-wide anti-wash rules. A zero cooldown or cap disables that rule;
+/// self-trades never earn rewards.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WashTradingRules {
+    /// Sales of a mint within this many seconds of its previous sale earn
+    /// no rewards.
+    pub mint_cooldown_secs: u32,
+    /// Volume between a wallet pair above which further sales in the window
+    /// earn no rewards.
+    pub pair_volume_cap_lamports: u64,
+    pub pair_volume_window_secs: u32,
+}
+
+impl WashTradingRules {
+    pub const LEN: usize = 4 // mint cooldown secs
+        + 8 // pair volume cap
+        + 4; // pair volume window secs
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RewardSuppression {
+    SelfTrade,
+    MintCooldown,
+    PairVolumeCap,
+}
+
+/// Per-listing arguments for `settle_many`, in the same order as the
+/// remaining account groups.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SettleManyEntry {
+    pub reward_amount: u64,
+    pub loyalty_bonus_points: u64,
+    /// Payout destination wallets appended after this listing's fixed
+    /// accounts; must match the listing's `payout_destinations`.
+    pub payout_account_count: u8,
+}
+
+/// Result reported by `settle_many` for each listing in the batch.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SettlementOutcome {
+    pub listing: Pubkey,
+    pub settled: bool,
+    /// Error code that caused the listing to be skipped, or zero on success.
+    pub error_code: u32,
+}
+
+/// Exact payout breakdown returned by `quote_sale`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SaleQuote {
+    pub price: u64,
+    pub seller_proceeds: u64,
+    pub royalty: u64,
+    pub developer_fee: u64,
+    pub reward_pool_fee: u64,
+    pub ops_fee: u64,
+    pub rewards: u64,
+    pub loyalty_points: u64,
+}
+
+#[account]
+pub struct SealedAuction {
+    pub bump: u8,
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub pricing: AuctionPricing,
+    pub reserve_price: u64,
+    pub commit_end_ts: i64,
+    pub reveal_end_ts: i64,
+    pub bid_count: u32,
+    pub revealed_count: u32,
+    pub highest_bid: u64,
+    pub highest_bidder: Option<Pubkey>,
+    /// Second-highest eligible revealed bid, or zero if there is none.
+    pub second_price: u64,
+    pub status: AuctionStatus,
+}
+
+impl SealedAuction {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // listing
+        + 32 // seller
+        + 1 // pricing enum
+        + 8 // reserve price
+        + 8 // commit end ts
+        + 8 // reveal end ts
+        + 4 // bid count
+        + 4 // revealed count
+        + 8 // highest bid
+        + 1 + 32 // highest bidder option
+        + 8 // second price
+        + 1; // status enum
+
+    /// Tracks the top two eligible bids. Ties go to the earlier reveal.
+    fn record_revealed_bid(&mut self, bidder: Pubkey, amount: u64) {
+        if self.highest_bidder.is_none() || amount > self.highest_bid {
+            if self.highest_bidder.is_some() {
+                self.second_price = self.highest_bid;
+            }
+            self.highest_bid = amount;
+            self.highest_bidder = Some(bidder);
+        } else if amount > self.second_price {
+            self.second_price = amount;
+        }
+    }
+
+    /// Price the winner pays. Second-price auctions never clear below the
+    /// reserve.
+    pub fn clearing_price(&self) -> u64 {
+        match self.pricing {
+            AuctionPricing::FirstPrice => self.highest_bid,
+            AuctionPricing::SecondPrice => self.second_price.max(self.reserve_price),
+        }
+    }
+}
+
+#[account]
+pub struct SealedBid {
+    pub bump: u8,
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub commitment: [u8; 32],
+    pub deposit_lamports: u64,
+    pub revealed_amount: Option<u64>,
+    pub rent_payer: Pubkey,
+}
+
+impl SealedBid {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // auction
+        + 32 // bidder
+        + 32 // commitment
+        + 8 // deposit
+        + 1 + 8 // revealed amount option
+        + 32; // rent payer
+}
+
+#[account]
+pub struct MarketConfig {
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub expiry_bounty_bps: u16,
+    /// `clout_staking` pool used to qualify stakers for early access.
+    pub early_access_pool: Pubkey,
+    /// Manages the wallet blocklist.
+    pub compliance_authority: Pubkey,
+    pub wash_trading_rules: WashTradingRules,
+    /// Resolves physical-redemption disputes.
+    pub redemption_arbiter: Pubkey,
+    /// `clout_staking` revenue pool that receives the reward pool cut of
+    /// every sale.
+    pub revenue_pool: Pubkey,
+    /// Seconds after `execute_sale` during which the buyer may cancel.
+    pub buyer_cancel_window_secs: u32,
+    /// Sequence number of the most recent sale receipt.
+    pub last_sale_sequence: u64,
+    pub last_updated_ts: i64,
+}
+
+impl MarketConfig {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // authority
+        + 2 // expiry bounty bps
+        + 32 // early access pool
+        + 32 // compliance authority
+        + WashTradingRules::LEN // wash trading rules
+        + 32 // redemption arbiter
+        + 32 // revenue pool
+        + 4 // buyer cancel window secs
+        + 8 // last sale sequence
+        + 8; // last updated timestamp
+
+    /// Whether `set_revenue_pool` has configured a revenue pool. Sales cannot
+    /// settle without one.
+    pub fn has_revenue_pool(&self) -> bool {
+        self.revenue_pool != Pubkey::default()
+    }
+}
+
+/// One shard of the wallet blocklist: every blocked wallet whose key starts
+/// with `page_index`, kept sorted for binary search.
+#[account]
+pub struct BlocklistPage {
+    pub bump: u8,
+    pub page_index: u8,
+    pub entries: Vec<Pubkey>,
+}
+
+impl BlocklistPage {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 1 // page index
+        + 4 + 32 * BLOCKLIST_PAGE_CAPACITY; // entries
+
+    /// Adds `wallet`, keeping entries sorted for `ensure_not_blocked`.
+    fn insert(&mut self, wallet: Pubkey) -> Result<()> {
+        let position = self
+            .entries
+            .binary_search(&wallet)
+            .err()
+            .ok_or(EscrowError::WalletAlreadyBlocked)?;
+        require!(
+            self.entries.len() < BLOCKLIST_PAGE_CAPACITY,
+            EscrowError::BlocklistPageFull
+        );
+        self.entries.insert(position, wallet);
+        Ok(())
+    }
+
+    fn remove(&mut self, wallet: &Pubkey) -> Result<()> {
+        let position = self
+            .entries
+            .binary_search(wallet)
+            .map_err(|_| EscrowError::WalletNotBlocked)?;
+        self.entries.remove(position);
+        Ok(())
+    }
+}
+
+#[event]
+pub struct WalletBlocked {
+    pub wallet: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WalletUnblocked {
+    pub wallet: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Curated registry entry for a verified Metaplex collection, keyed by the
+/// collection mint.
+#[account]
+pub struct CollectionEntry {
+    pub bump: u8,
+    pub collection: Pubkey,
+    pub status: CollectionStatus,
+    pub royalty_policy: RoyaltyPolicy,
+    /// Replaces the default 1.5% platform fee for listings of this collection.
+    pub platform_fee_override_bps: Option<u16>,
+    pub last_updated_ts: i64,
+}
+
+impl CollectionEntry {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // collection
+        + 1 // status enum
+        + RoyaltyPolicy::LEN // royalty policy
+        + 1 + 2 // platform fee override option
+        + 8; // last updated timestamp
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionStatus {
+    Verified,
+    Unverified,
+    Banned,
+}
+
+/// Royalty bounds listings of a collection must respect, optionally pinning
+/// the royalty destination.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RoyaltyPolicy {
+    pub min_royalty_bps: u16,
+    pub max_royalty_bps: u16,
+    pub royalty_destination: Option<Pubkey>,
+}
+
+impl RoyaltyPolicy {
+    pub const LEN: usize = 2 // min royalty bps
+        + 2 // max royalty bps
+        + 1 + 32; // royalty destination option
+
+    fn check(&self, royalty_bps: u16, royalty_destination: &Pubkey) -> Result<()> {
+        require!(
+            royalty_bps >= self.min_royalty_bps && royalty_bps <= self.max_royalty_bps,
+            EscrowError::RoyaltyOutsidePolicy
+        );
+        if let Some(expected) = self.royalty_destination {
+            require_keys_eq!(
+                expected,
+                *royalty_destination,
+                EscrowError::RoyaltyDestinationMismatch
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Per-collection market statistics, keyed by the verified collection mint.
+/// Other programs may read `rolling_average_price` as a price reference.
+#[account]
+#[derive(Default)]
+pub struct CollectionStats {
+    pub bump: u8,
+    pub collection: Pubkey,
+    pub total_volume: u64,
+    pub sale_count: u64,
+    pub last_sale_price: u64,
+    pub last_sale_ts: i64,
+    pub rolling_average_price: u64,
+}
+
+impl CollectionStats {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // collection
+        + 8 // total volume
+        + 8 // sale count
+        + 8 // last sale price
+        + 8 // last sale ts
+        + 8; // rolling average price
+
+    /// Folds a settled sale into the running totals. The rolling average is an
+    /// exponential moving average weighted by `ROLLING_AVERAGE_WEIGHT_BPS`.
+    pub fn record_sale(&mut self, price: u64, timestamp: i64) -> Result<()> {
+        self.rolling_average_price = if self.sale_count == 0 {
+            price
+        } else if price >= self.rolling_average_price {
+            let delta = rolling_average_delta(price - self.rolling_average_price)?;
+            self.rolling_average_price
+                .checked_add(delta)
+                .ok_or(EscrowError::MathOverflow)?
+        } else {
+            let delta = rolling_average_delta(self.rolling_average_price - price)?;
+            self.rolling_average_price
+                .checked_sub(delta)
+                .ok_or(EscrowError::MathOverflow)?
+        };
+        self.total_volume = self
+            .total_volume
+            .checked_add(price)
+            .ok_or(EscrowError::MathOverflow)?;
+        self.sale_count = self
+            .sale_count
+            .checked_add(1)
+            .ok_or(EscrowError::MathOverflow)?;
+        self.last_sale_price = price;
+        self.last_sale_ts = timestamp;
+        Ok(())
+    }
+}
+
+/// Layout of `Listing` accounts created before versioning was introduced.
+#[derive(AnchorDeserialize)]
+pub struct ListingV0 {
+    pub bump: u8,
+    pub escrow_bump: u8,
+    pub seller: Pubkey,
+    pub buyer: Option<Pubkey>,
+    pub mint: Pubkey,
+    pub listing_id: u64,
+    pub price_lamports: u64,
+    pub creation_ts: i64,
+    pub expiration_ts: Option<i64>,
+    pub sale_ts: Option<i64>,
+    pub settlement_ts: Option<i64>,
+    pub status: ListingStatus,
+    pub royalty_bps: u16,
+    pub royalty_destination: Pubkey,
+    pub treasury_bps: u16,
+    pub marketplace_fee_bps: u16,
+}
+
+impl ListingV0 {
+    pub const LEN: usize = Listing::LEN
+        - 1 // version
+        - 1 // sale mode enum
+        - (1 + EarlyAccess::LEN) // early access option
+        - (1 + 2) // platform fee override option
+        - (1 + Redemption::LEN) // redemption option
+        - (1 + CloutFeePayment::LEN) // clout fee payment option
+        - (4 + MAX_PAYOUT_DESTINATIONS * PayoutDestination::LEN) // payout destinations vec
+        - 32 // rent payer
+        - (1 + 8) // buyer cancel deadline option
+        - Listing::RESERVED_LEN;
+}
+
+/// Fields every v1 `Listing` starts with. v1 programs later appended
+/// `payout_destinations`, `rent_payer` and `buyer_cancel_deadline_ts` ahead
+/// of the reserved bytes without bumping the version, so a v1 account's size tells which appended fields it has.
+#[derive(AnchorDeserialize)]
+pub struct ListingV1 {
+    pub version: u8,
+    pub bump: u8,
+    pub escrow_bump: u8,
+    pub seller: Pubkey,
+    pub buyer: Option<Pubkey>,
+    pub mint: Pubkey,
+    pub listing_id: u64,
+    pub price_lamports: u64,
+    pub creation_ts: i64,
+    pub expiration_ts: Option<i64>,
+    pub sale_ts: Option<i64>,
+    pub settlement_ts: Option<i64>,
+    pub status: ListingStatus,
+    pub royalty_bps: u16,
+    pub royalty_destination: Pubkey,
+    pub treasury_bps: u16,
+    pub marketplace_fee_bps: u16,
+    pub sale_mode: SaleMode,
+    pub early_access: Option<EarlyAccess>,
+    pub platform_fee_override_bps: Option<u16>,
+    pub redemption: Option<Redemption>,
+    pub clout_fee_payment: Option<CloutFeePayment>,
+}
+
+impl ListingV1 {
+    pub const RESERVED_LEN: usize = 15;
+    /// Size of v1 listings created before payout destinations.
+    pub const LEN: usize = ListingV0::LEN
+        + 1 // version
+        + 1 // sale mode enum
+        + (1 + EarlyAccess::LEN) // early access option
+        + (1 + 2) // platform fee override option
+        + (1 + Redemption::LEN) // redemption option
+        + (1 + CloutFeePayment::LEN) // clout fee payment option
+        + ListingV1::RESERVED_LEN;
+    /// Size of v1 listings created with payout destinations.
+    pub const PAYOUTS_LEN: usize =
+        ListingV1::LEN + 4 + MAX_PAYOUT_DESTINATIONS * PayoutDestination::LEN;
+    /// Size of v1 listings created with a separate rent payer.
+    pub const RENT_PAYER_LEN: usize = ListingV1::PAYOUTS_LEN + 32;
+    /// Size of v1 listings created with a buyer cancel deadline.
+    pub const CANCEL_DEADLINE_LEN: usize = ListingV1::RENT_PAYER_LEN + 1 + 8;
+}
+
+/// Layout of `EscrowVault` accounts created before versioning was introduced.
+#[derive(AnchorDeserialize)]
+pub struct EscrowVaultV0 {
     pub bump: u8,
     pub listing: Pubkey,
     pub total_deposited: u64,
 }
 
-impl EscrowVault {
-    pub const LEN: usize = 8 // discriminator
-        + 1 // bump
-        + 32 // listing pubkey
-        + 8; // lamports deposited
-}
+impl EscrowVaultV0 {
+    pub const LEN: usize = EscrowVault::LEN - 1 - EscrowVault::RESERVED_LEN;
+}
+
+/// Layout of `SaleReceipt` accounts created before versioning was introduced.
+#[derive(AnchorDeserialize)]
+pub struct SaleReceiptV0 {
+    pub bump: u8,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub amount_paid: u64,
+    pub seller_proceeds: u64,
+    pub royalty_paid: u64,
+    pub treasury_paid: u64,
+    pub marketplace_fee_paid: u64,
+    pub developer_paid: u64,
+    pub rewards_minted: u64,
+    pub loyalty_points_awarded: u64,
+    pub timestamp: i64,
+}
+
+impl SaleReceiptV0 {
+    pub const LEN: usize = SaleReceipt::LEN
+        - 1 // version
+        - (1 + 1) // rewards suppressed option
+        - 8 // clout fee paid
+        - 8 // clout fee burned
+        - (4 + MAX_PAYOUT_DESTINATIONS * SellerPayout::LEN) // seller payouts vec
+        - 8 // sequence
+        - SaleReceipt::RESERVED_LEN;
+}
+
+/// Fields every v1 `SaleReceipt` starts with. v1 programs later appended
+/// `seller_payouts` and then `sequence` ahead of the reserved bytes without
+/// bumping the version, so a v1 account's size tells which appended fields it has.
+#[derive(AnchorDeserialize)]
+pub struct SaleReceiptV1 {
+    pub version: u8,
+    pub bump: u8,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub amount_paid: u64,
+    pub seller_proceeds: u64,
+    pub royalty_paid: u64,
+    pub treasury_paid: u64,
+    pub marketplace_fee_paid: u64,
+    pub developer_paid: u64,
+    pub rewards_minted: u64,
+    pub loyalty_points_awarded: u64,
+    pub timestamp: i64,
+    pub rewards_suppressed: Option<RewardSuppression>,
+    pub clout_fee_paid: u64,
+    pub clout_fee_burned: u64,
+}
+
+impl SaleReceiptV1 {
+    pub const RESERVED_LEN: usize = 14;
+    /// Size of v1 receipts created before seller payouts were recorded.
+    pub const LEN: usize = SaleReceiptV0::LEN
+        + 1 // version
+        + (1 + 1) // rewards suppressed option
+        + 8 // clout fee paid
+        + 8 // clout fee burned
+        + SaleReceiptV1::RESERVED_LEN;
+    /// Size of v1 receipts created with seller payouts.
+    pub const PAYOUTS_LEN: usize =
+        SaleReceiptV1::LEN + 4 + MAX_PAYOUT_DESTINATIONS * SellerPayout::LEN;
+    /// Size of v1 receipts created with a sale sequence number.
+    pub const SEQUENCE_LEN: usize = SaleReceiptV1::PAYOUTS_LEN + 8;
+}
+
+/// Accounts that carry a layout version and can be upgraded in place from
+/// their original unversioned layout or an earlier versioned one.
+trait Versioned: AccountSerialize + Discriminator + Sized {
+    const VERSION: u8;
+    const CURRENT_LEN: usize;
+    const V0_LEN: usize;
+    /// Sizes v1 accounts were allocated with as fields were appended.
+    const V1_LENS: &'static [usize] = &[];
+
+    /// Builds the current layout from a v0 account body (after the
+    /// discriminator).
+    fn upgrade_v0(body: &[u8]) -> Result<Self>;
+
+    /// Builds the current layout from a v1 account body of one of the
+    /// `V1_LENS` sizes.
+    fn upgrade_v1(_body: &[u8]) -> Result<Self> {
+        err!(EscrowError::InvalidMigrationAccount)
+    }
+}
+
+/// Reads the next field of an account being migrated.
+fn read_migrated<T: AnchorDeserialize>(data: &mut &[u8]) -> Result<T> {
+    T::deserialize(data).map_err(|_| error!(EscrowError::InvalidMigrationAccount))
+}
+
+impl Versioned for Listing {
+    const VERSION: u8 = Listing::VERSION;
+    const CURRENT_LEN: usize = Listing::LEN;
+    const V0_LEN: usize = ListingV0::LEN;
+    const V1_LENS: &'static [usize] = &[
+        ListingV1::LEN,
+        ListingV1::PAYOUTS_LEN,
+        ListingV1::RENT_PAYER_LEN,
+        ListingV1::CANCEL_DEADLINE_LEN,
+    ];
+
+    fn upgrade_v0(body: &[u8]) -> Result<Self> {
+        let v0 = ListingV0::deserialize(&mut &body[..])
+            .map_err(|_| error!(EscrowError::InvalidMigrationAccount))?;
+        Ok(Listing {
+            version: Listing::VERSION,
+            bump: v0.bump,
+            escrow_bump: v0.escrow_bump,
+            seller: v0.seller,
+            buyer: v0.buyer,
+            mint: v0.mint,
+            listing_id: v0.listing_id,
+            price_lamports: v0.price_lamports,
+            creation_ts: v0.creation_ts,
+            expiration_ts: v0.expiration_ts,
+            sale_ts: v0.sale_ts,
+            settlement_ts: v0.settlement_ts,
+            status: v0.status,
+            royalty_bps: v0.royalty_bps,
+            royalty_destination: v0.royalty_destination,
+            treasury_bps: v0.treasury_bps,
+            marketplace_fee_bps: v0.marketplace_fee_bps,
+            sale_mode: SaleMode::FixedPrice,
+            early_access: None,
+            platform_fee_override_bps: None,
+            redemption: None,
+            clout_fee_payment: None,
+            payout_destinations: Vec::new(),
+            rent_payer: v0.seller,
+            buyer_cancel_deadline_ts: None,
+            reserved: [0; Listing::RESERVED_LEN],
+        })
+    }
+
+    fn upgrade_v1(body: &[u8]) -> Result<Self> {
+        let len = 8 + body.len();
+        let mut data = body;
+        let v1: ListingV1 = read_migrated(&mut data)?;
+        let payout_destinations = if len >= ListingV1::PAYOUTS_LEN {
+            read_migrated(&mut data)?
+        } else {
+            Vec::new()
+        };
+        let rent_payer = if len >= ListingV1::RENT_PAYER_LEN {
+            read_migrated(&mut data)?
+        } else {
+            v1.seller
+        };
+        let buyer_cancel_deadline_ts = if len >= ListingV1::CANCEL_DEADLINE_LEN {
+            read_migrated(&mut data)?
+        } else {
+            None
+        };
+        Ok(Listing {
+            version: Listing::VERSION,
+            bump: v1.bump,
+            escrow_bump: v1.escrow_bump,
+            seller: v1.seller,
+            buyer: v1.buyer,
+            mint: v1.mint,
+            listing_id: v1.listing_id,
+            price_lamports: v1.price_lamports,
+            creation_ts: v1.creation_ts,
+            expiration_ts: v1.expiration_ts,
+            sale_ts: v1.sale_ts,
+            settlement_ts: v1.settlement_ts,
+            status: v1.status,
+            royalty_bps: v1.royalty_bps,
+            royalty_destination: v1.royalty_destination,
+            treasury_bps: v1.treasury_bps,
+            marketplace_fee_bps: v1.marketplace_fee_bps,
+            sale_mode: v1.sale_mode,
+            early_access: v1.early_access,
+            platform_fee_override_bps: v1.platform_fee_override_bps,
+            redemption: v1.redemption,
+            clout_fee_payment: v1.clout_fee_payment,
+            payout_destinations,
+            rent_payer,
+            buyer_cancel_deadline_ts,
+            reserved: [0; Listing::RESERVED_LEN],
+        })
+    }
+}
+
+impl Versioned for EscrowVault {
+    const VERSION: u8 = EscrowVault::VERSION;
+    const CURRENT_LEN: usize = EscrowVault::LEN;
+    const V0_LEN: usize = EscrowVaultV0::LEN;
+
+    fn upgrade_v0(body: &[u8]) -> Result<Self> {
+        let v0 = EscrowVaultV0::deserialize(&mut &body[..])
+            .map_err(|_| error!(EscrowError::InvalidMigrationAccount))?;
+        Ok(EscrowVault {
+            version: EscrowVault::VERSION,
+            bump: v0.bump,
+            listing: v0.listing,
+            total_deposited: v0.total_deposited,
+            reserved: [0; EscrowVault::RESERVED_LEN],
+        })
+    }
+}
+
+impl Versioned for SaleReceipt {
+    const VERSION: u8 = SaleReceipt::VERSION;
+    const CURRENT_LEN: usize = SaleReceipt::LEN;
+    const V0_LEN: usize = SaleReceiptV0::LEN;
+    const V1_LENS: &'static [usize] = &[
+        SaleReceiptV1::LEN,
+        SaleReceiptV1::PAYOUTS_LEN,
+        SaleReceiptV1::SEQUENCE_LEN,
+    ];
+
+    fn upgrade_v0(body: &[u8]) -> Result<Self> {
+        let v0 = SaleReceiptV0::deserialize(&mut &body[..])
+            .map_err(|_| error!(EscrowError::InvalidMigrationAccount))?;
+        Ok(SaleReceipt {
+            version: SaleReceipt::VERSION,
+            bump: v0.bump,
+            listing: v0.listing,
+            buyer: v0.buyer,
+            seller: v0.seller,
+            amount_paid: v0.amount_paid,
+            seller_proceeds: v0.seller_proceeds,
+            royalty_paid: v0.royalty_paid,
+            treasury_paid: v0.treasury_paid,
+            marketplace_fee_paid: v0.marketplace_fee_paid,
+            developer_paid: v0.developer_paid,
+            rewards_minted: v0.rewards_minted,
+            loyalty_points_awarded: v0.loyalty_points_awarded,
+            timestamp: v0.timestamp,
+            rewards_suppressed: None,
+            clout_fee_paid: 0,
+            clout_fee_burned: 0,
+            seller_payouts: Vec::new(),
+            sequence: 0,
+            reserved: [0; SaleReceipt::RESERVED_LEN],
+        })
+    }
+
+    fn upgrade_v1(body: &[u8]) -> Result<Self> {
+        let len = 8 + body.len();
+        let mut data = body;
+        let v1: SaleReceiptV1 = read_migrated(&mut data)?;
+        let seller_payouts = if len >= SaleReceiptV1::PAYOUTS_LEN {
+            read_migrated(&mut data)?
+        } else {
+            Vec::new()
+        };
+        let sequence = if len >= SaleReceiptV1::SEQUENCE_LEN {
+            read_migrated(&mut data)?
+        } else {
+            0
+        };
+        Ok(SaleReceipt {
+            version: SaleReceipt::VERSION,
+            bump: v1.bump,
+            listing: v1.listing,
+            buyer: v1.buyer,
+            seller: v1.seller,
+            amount_paid: v1.amount_paid,
+            seller_proceeds: v1.seller_proceeds,
+            royalty_paid: v1.royalty_paid,
+            treasury_paid: v1.treasury_paid,
+            marketplace_fee_paid: v1.marketplace_fee_paid,
+            developer_paid: v1.developer_paid,
+            rewards_minted: v1.rewards_minted,
+            loyalty_points_awarded: v1.loyalty_points_awarded,
+            timestamp: v1.timestamp,
+            rewards_suppressed: v1.rewards_suppressed,
+            clout_fee_paid: v1.clout_fee_paid,
+            clout_fee_burned: v1.clout_fee_burned,
+            seller_payouts,
+            sequence,
+            reserved: [0; SaleReceipt::RESERVED_LEN],
+        })
+    }
+}
+
+/// Decodes raw account data for migration. Returns `None` when the account
+/// already uses the current layout.
+fn decode_for_migration<T: Versioned>(data: &[u8]) -> Result<Option<T>> {
+    require!(
+        data.len() >= 8 && &data[..8] == T::DISCRIMINATOR,
+        EscrowError::InvalidMigrationAccount
+    );
+    if data.len() == T::CURRENT_LEN && data[8] == T::VERSION {
+        return Ok(None);
+    }
+    if data.len() == T::V0_LEN {
+        return T::upgrade_v0(&data[8..]).map(Some);
+    }
+    require!(
+        T::V1_LENS.contains(&data.len()) && data[8] == 1,
+        EscrowError::InvalidMigrationAccount
+    );
+    T::upgrade_v1(&data[8..]).map(Some)
+}
+
+/// Turns a program-owned `EscrowVault` (either layout) into a data-less
+/// system account holding the rent-exempt floor plus the recorded deposit.
+/// Any other lamports go to `rent_payer`. Returns `false` if the vault was
+/// already converted.
+fn convert_legacy_escrow_vault(
+    escrow_vault: &AccountInfo,
+    rent_payer: &AccountInfo,
+) -> Result<bool> {
+    if escrow_vault.owner != &crate::ID {
+        return Ok(false);
+    }
+    let legacy = {
+        let data = escrow_vault.try_borrow_data()?;
+        match decode_for_migration::<EscrowVault>(&data)? {
+            Some(upgraded) => upgraded,
+            None => EscrowVault::try_deserialize(&mut &data[..])?,
+        }
+    };
+    let retained = Rent::get()?
+        .minimum_balance(0)
+        .checked_add(legacy.total_deposited)
+        .ok_or(EscrowError::MathOverflow)?;
+    let excess = escrow_vault
+        .lamports()
+        .checked_sub(retained)
+        .ok_or(EscrowError::InsufficientEscrowBalance)?;
+    disburse(escrow_vault, rent_payer, excess)?;
+
+    escrow_vault.try_borrow_mut_data()?.fill(0);
+    escrow_vault.resize(0)?;
+    escrow_vault.assign(&system_program::ID);
+    Ok(true)
+}
+
+/// Reallocs a v0 or v1 account to the current layout, topping up rent from
+/// `payer`. Returns `false` if the account was already current.
+fn migrate_account<'info, T: Versioned>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<bool> {
+    let Some(upgraded) = decode_for_migration::<T>(&account.try_borrow_data()?)? else {
+        return Ok(false);
+    };
+
+    let required_lamports = Rent::get()?.minimum_balance(T::CURRENT_LEN);
+    let top_up = required_lamports.saturating_sub(account.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    account.resize(T::CURRENT_LEN)?;
+    upgraded.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    Ok(true)
+}
+
+/// Restricts a listing to qualifying buyers for `window_secs` after
+/// creation: buyers need a loyalty tier of at least `min_tier`, or at least
+/// `min_stake` CLOUT staked in the market's early-access pool. A zero
+/// `min_stake` disables the stake path.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct EarlyAccess {
+    pub window_secs: u32,
+    pub min_tier: LoyaltyTier,
+    pub min_stake: u64,
+}
+
+impl EarlyAccess {
+    pub const LEN: usize = 4 // window secs
+        + 1 // min tier enum
+        + 8; // min stake
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SaleMode {
+    FixedPrice,
+    SealedBidAuction,
+    /// Reserved for the buyer of an active installment plan.
+    Installment,
+    /// Sold by raffle; the drawn ticket holder becomes the buyer.
+    Raffle,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuctionPricing {
+    FirstPrice,
+    SecondPrice,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuctionStatus {
+    Open,
+    Finalized,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ListingStatus {
+    Active,
+    PendingSettlement,
+    Settled,
+    Cancelled,
+    Expired,
+    Arbitrated,
+}
+
+/// Installment offer on a listing and, once a buyer starts it, the progress
+/// of their payments.
+#[account]
+pub struct InstallmentPlan {
+    pub bump: u8,
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub terms: InstallmentTerms,
+    pub status: InstallmentStatus,
+    pub buyer: Option<Pubkey>,
+    pub start_ts: i64,
+    pub installments_paid: u8,
+    /// Deposit plus installments paid so far.
+    pub amount_paid: u64,
+    pub rent_payer: Pubkey,
+}
+
+impl InstallmentPlan {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // listing
+        + 32 // seller
+        + InstallmentTerms::LEN // terms
+        + 1 // status enum
+        + 1 + 32 // buyer option
+        + 8 // start ts
+        + 1 // installments paid
+        + 8 // amount paid
+        + 32; // rent payer
+
+    /// Reopens the offer for a new buyer.
+    fn reset(&mut self) {
+        self.status = InstallmentStatus::Offered;
+        self.buyer = None;
+        self.start_ts = 0;
+        self.installments_paid = 0;
+        self.amount_paid = 0;
+    }
+
+    /// Deadline for the next unpaid installment.
+    fn next_due_ts(&self) -> Result<i64> {
+        let intervals = self.installments_paid as i64 + 1;
+        (self.terms.interval_secs as i64)
+            .checked_mul(intervals)
+            .and_then(|offset| self.start_ts.checked_add(offset))
+            .ok_or_else(|| error!(EscrowError::MathOverflow))
+    }
+
+    /// Equal share of the post-deposit balance; the final installment also
+    /// covers any rounding remainder.
+    fn next_installment_amount(&self, price: u64) -> Result<u64> {
+        let remaining = price
+            .checked_sub(self.amount_paid)
+            .ok_or(EscrowError::MathOverflow)?;
+        if self.installments_paid + 1 == self.terms.installment_count {
+            return Ok(remaining);
+        }
+        let deposit = compute_fee(price, self.terms.deposit_bps)?;
+        Ok((price - deposit) / self.terms.installment_count as u64)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct InstallmentTerms {
+    /// Share of the price paid upfront.
+    pub deposit_bps: u16,
+    pub installment_count: u8,
+    /// Time allowed for each installment after the previous deadline.
+    pub interval_secs: u32,
+    /// Share of the amount paid the seller keeps if the buyer defaults.
+    pub forfeit_bps: u16,
+}
+
+impl InstallmentTerms {
+    pub const LEN: usize = 2 // deposit bps
+        + 1 // installment count
+        + 4 // interval secs
+        + 2; // forfeit bps
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum InstallmentStatus {
+    Offered,
+    Active,
+    Completed,
+}
+
+#[account]
+pub struct Raffle {
+    pub bump: u8,
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub ticket_price: u64,
+    /// Tickets that must sell for the raffle to draw; otherwise it refunds.
+    pub min_tickets: u32,
+    pub sales_end_ts: i64,
+    pub reveal_deadline_ts: i64,
+    pub seed_commitment: [u8; 32],
+    pub tickets_sold: u32,
+    /// Tickets refunded after a cancellation.
+    pub tickets_refunded: u32,
+    /// Ledger pages created so far, numbered from zero.
+    pub page_count: u32,
+    pub status: RaffleStatus,
+    pub winning_ticket: Option<u32>,
+}
+
+impl Raffle {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // listing
+        + 32 // seller
+        + 8 // ticket price
+        + 4 // min tickets
+        + 8 // sales end ts
+        + 8 // reveal deadline ts
+        + 32 // seed commitment
+        + 4 // tickets sold
+        + 4 // tickets refunded
+        + 4 // page count
+        + 1 // status enum
+        + 1 + 4; // winning ticket option
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RaffleStatus {
+    Open,
+    Drawn,
+    Completed,
+    Cancelled,
+}
+
+/// One page of a raffle's ticket ledger. Each entry is a single purchase
+/// covering a contiguous range of ticket numbers.
+#[account]
+pub struct RaffleTicketPage {
+    pub bump: u8,
+    pub raffle: Pubkey,
+    pub index: u32,
+    pub entries: Vec<RaffleTicketEntry>,
+}
+
+impl RaffleTicketPage {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // raffle
+        + 4 // index
+        + 4 + RAFFLE_TICKET_PAGE_CAPACITY * RaffleTicketEntry::LEN; // entries vec
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RaffleTicketEntry {
+    pub buyer: Pubkey,
+    pub first_ticket: u32,
+    pub ticket_count: u32,
+    pub refunded: bool,
+}
+
+impl RaffleTicketEntry {
+    pub const LEN: usize = 32 // buyer
+        + 4 // first ticket
+        + 4 // ticket count
+        + 1; // refunded
+
+    fn holds(&self, ticket: u32) -> bool {
+        ticket >= self.first_ticket && ticket - self.first_ticket < self.ticket_count
+    }
+}
+
+/// Standing offer to buy NFTs from a merkle set of mints. The offer account
+/// itself holds the escrow for the unfilled quantity.
+#[account]
+pub struct TraitOffer {
+    pub bump: u8,
+    pub buyer: Pubkey,
+    pub offer_id: u64,
+    /// Verified collection every filled NFT must belong to.
+    pub collection: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub price_per_item: u64,
+    pub quantity: u32,
+    pub filled: u32,
+    pub expiration_ts: i64,
+    pub rent_payer: Pubkey,
+}
+
+impl TraitOffer {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // buyer
+        + 8 // offer id
+        + 32 // collection
+        + 32 // merkle root
+        + 8 // price per item
+        + 4 // quantity
+        + 4 // filled
+        + 8 // expiration ts
+        + 32; // rent payer
+
+    /// Lamports still escrowed for unfilled items. Everything else in the
+    /// offer account is rent, which goes back to `rent_payer` on close.
+    fn unfilled_escrow(&self) -> Result<u64> {
+        let unfilled = self
+            .quantity
+            .checked_sub(self.filled)
+            .ok_or(EscrowError::MathOverflow)?;
+        self.price_per_item
+            .checked_mul(unfilled as u64)
+            .ok_or_else(|| error!(EscrowError::MathOverflow))
+    }
+}
+
+/// Listing terms a seller signs off-chain instead of creating a `Listing`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SignedOrder {
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub price_lamports: u64,
+    pub royalty_bps: u16,
+    pub royalty_destination: Pubkey,
+    /// Unique per seller; its `OrderNonce` account marks the order used.
+    pub nonce: u64,
+    pub expiration_ts: i64,
+}
+
+/// Marks a signed order's nonce as filled or cancelled.
+#[account]
+pub struct OrderNonce {
+    pub bump: u8,
+    pub seller: Pubkey,
+    pub nonce: u64,
+    pub status: OrderNonceStatus,
+}
+
+impl OrderNonce {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // seller
+        + 8 // nonce
+        + 1; // status enum
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum OrderNonceStatus {
+    Filled,
+    Cancelled,
+}
+
+/// Share of a listing's seller proceeds routed to `wallet`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct PayoutDestination {
+    pub wallet: Pubkey,
+    pub share_bps: u16,
+}
+
+impl PayoutDestination {
+    pub const LEN: usize = 32 // wallet
+        + 2; // share bps
+}
+
+/// Seller proceeds paid to one payout destination, recorded on the receipt.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SellerPayout {
+    pub wallet: Pubkey,
+    pub amount: u64,
+}
+
+impl SellerPayout {
+    pub const LEN: usize = 32 // wallet
+        + 8; // amount
+}
+
+/// One settled trade in a wallet's `TradeHistory`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct TradeHistoryEntry {
+    pub receipt: Pubkey,
+    pub sequence: u64,
+    pub side: TradeSide,
+}
+
+impl TradeHistoryEntry {
+    pub const LEN: usize = 32 // receipt
+        + 8 // sequence
+        + 1; // side enum
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum TradeSide {
+    #[default]
+    Buy,
+    Sell,
+}
+
+/// Platform fee a buyer settled in CLOUT through `pay_fees_in_clout`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CloutFeePayment {
+    pub clout_paid: u64,
+    pub clout_burned: u64,
+}
+
+impl CloutFeePayment {
+    pub const LEN: usize = 8 // clout paid
+        + 8; // clout burned
+}
+
+/// Delivery state of a listing backed by physical goods.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Redemption {
+    pub status: RedemptionStatus,
+    pub confirmation_window_secs: u32,
+    /// Set when the sale executes; the escrow releases to the seller after
+    /// this time unless a dispute was opened.
+    pub confirmation_deadline_ts: i64,
+}
+
+impl Redemption {
+    pub const LEN: usize = 1 // status enum
+        + 4 // confirmation window secs
+        + 8; // confirmation deadline ts
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RedemptionStatus {
+    AwaitingSale,
+    AwaitingDelivery,
+    Delivered,
+    Disputed,
+    Resolved,
+}
+
+/// Starts the delivery confirmation window once a physical-redemption
+/// listing sells.
+fn start_redemption_window(listing: &mut Listing, now: i64) -> Result<()> {
+    if let Some(redemption) = listing.redemption.as_mut() {
+        redemption.status = RedemptionStatus::AwaitingDelivery;
+        redemption.confirmation_deadline_ts = now
+            .checked_add(redemption.confirmation_window_secs as i64)
+            .ok_or(EscrowError::MathOverflow)?;
+    }
+    Ok(())
+}
+
+/// Gate shared by `settle_sale` and `settle_many`: the sale is pending, any
+/// buyer cancellation window has closed and physical redemption released.
+fn ensure_ready_to_settle(listing: &Listing, now: i64) -> Result<()> {
+    require!(
+        listing.status == ListingStatus::PendingSettlement,
+        EscrowError::ListingNotPending
+    );
+    if let Some(deadline) = listing.buyer_cancel_deadline_ts {
+        require!(now > deadline, EscrowError::BuyerCancelWindowOpen);
+    }
+    ensure_redemption_released(listing, now)
+}
+
+/// Returns a direct purchase to an active listing when `buyer` cancels within
+/// their window. Sales paid partly in CLOUT, and redemptions already
+/// confirmed or disputed, cannot be cancelled.
+fn revert_pending_purchase(listing: &mut Listing, buyer: &Pubkey, now: i64) -> Result<()> {
+    require!(
+        listing.status == ListingStatus::PendingSettlement,
+        EscrowError::ListingNotPending
+    );
+    require!(listing.buyer == Some(*buyer), EscrowError::BuyerMismatch);
+    require!(
+        listing.sale_mode == SaleMode::FixedPrice && listing.clout_fee_payment.is_none(),
+        EscrowError::PurchaseNotCancellable
+    );
+    let deadline = listing
+        .buyer_cancel_deadline_ts
+        .ok_or(EscrowError::BuyerCancelWindowClosed)?;
+    require!(now <= deadline, EscrowError::BuyerCancelWindowClosed);
+    if let Some(mut redemption) = listing.redemption {
+        require!(
+            redemption.status == RedemptionStatus::AwaitingDelivery,
+            EscrowError::PurchaseNotCancellable
+        );
+        redemption.status = RedemptionStatus::AwaitingSale;
+        redemption.confirmation_deadline_ts = 0;
+        listing.redemption = Some(redemption);
+    }
+
+    listing.status = ListingStatus::Active;
+    listing.buyer = None;
+    listing.sale_ts = None;
+    listing.buyer_cancel_deadline_ts = None;
+    Ok(())
+}
+
+/// Physical-redemption sales settle once delivery is confirmed, or once the
+/// confirmation window lapses without a dispute.
+fn ensure_redemption_released(listing: &Listing, now: i64) -> Result<()> {
+    let Some(redemption) = listing.redemption else {
+        return Ok(());
+    };
+    let released = match redemption.status {
+        RedemptionStatus::Delivered => true,
+        RedemptionStatus::AwaitingDelivery => now > redemption.confirmation_deadline_ts,
+        _ => false,
+    };
+    require!(released, EscrowError::RedemptionNotReleased);
+    Ok(())
+}
+
+/// Cancels `raffle` if it closed below its minimum ticket count or missed its
+/// reveal deadline. The listing stays in raffle mode, so it cannot be bought
+/// or cancelled, until every sold ticket is refunded.
+fn cancel_raffle_listing(raffle: &mut Raffle, listing: &mut Listing, now: i64) -> Result<()> {
+    require!(
+        raffle.status == RaffleStatus::Open,
+        EscrowError::RaffleNotOpen
+    );
+    let below_minimum = now > raffle.sales_end_ts && raffle.tickets_sold < raffle.min_tickets;
+    require!(
+        below_minimum || now > raffle.reveal_deadline_ts,
+        EscrowError::RaffleNotCancellable
+    );
+
+    raffle.status = RaffleStatus::Cancelled;
+    if raffle.tickets_sold == 0 {
+        listing.sale_mode = SaleMode::FixedPrice;
+    }
+    Ok(())
+}
+
+/// Marks `entry` refunded and returns the lamports owed to its buyer. The
+/// refund that covers the last sold ticket returns the listing to
+/// fixed-price.
+fn refund_raffle_entry(
+    raffle: &mut Raffle,
+    listing: &mut Listing,
+    entry: &mut RaffleTicketEntry,
+) -> Result<u64> {
+    require!(
+        raffle.status == RaffleStatus::Cancelled,
+        EscrowError::RaffleNotCancelled
+    );
+    require!(!entry.refunded, EscrowError::RaffleTicketsRefunded);
+    entry.refunded = true;
+
+    raffle.tickets_refunded = raffle
+        .tickets_refunded
+        .checked_add(entry.ticket_count)
+        .ok_or(EscrowError::MathOverflow)?;
+    if raffle.tickets_refunded == raffle.tickets_sold {
+        listing.sale_mode = SaleMode::FixedPrice;
+    }
+    raffle
+        .ticket_price
+        .checked_mul(entry.ticket_count as u64)
+        .ok_or_else(|| error!(EscrowError::MathOverflow))
+}
+
+/// Enforces a listing's early-access window for `buyer`. Outside the window,
+/// or for listings without one, anyone may buy.
+fn check_early_access(
+    listing: &Listing,
+    buyer: &Pubkey,
+    now: i64,
+    loyalty_profile: Option<&LoyaltyProfile>,
+    stake_position: Option<&StakePosition>,
+    market_config: Option<&MarketConfig>,
+) -> Result<()> {
+    let Some(early_access) = listing.early_access else {
+        return Ok(());
+    };
+    let window_end = listing
+        .creation_ts
+        .checked_add(early_access.window_secs as i64)
+        .ok_or(EscrowError::MathOverflow)?;
+    if now >= window_end {
+        return Ok(());
+    }
+
+    let qualifies_by_tier = loyalty_profile.is_some_and(|profile| {
+        profile.owner == *buyer && profile.tier as u8 >= early_access.min_tier as u8
+    });
+    let qualifies_by_stake = early_access.min_stake > 0
+        && match (stake_position, market_config) {
+            (Some(position), Some(config)) => {
+                position.owner == *buyer
+                    && position.pool == config.early_access_pool
+                    && position.amount >= early_access.min_stake
+            }
+            _ => false,
+        };
+    require!(
+        qualifies_by_tier || qualifies_by_stake,
+        EscrowError::EarlyAccessRestricted
+    );
+    Ok(())
+}
+
+fn validate_collection_terms(
+    royalty_policy: &RoyaltyPolicy,
+    platform_fee_override_bps: Option<u16>,
+) -> Result<()> {
+    require!(
+        royalty_policy.min_royalty_bps <= royalty_policy.max_royalty_bps
+            && royalty_policy.max_royalty_bps as u64 <= BPS_DENOMINATOR,
+        EscrowError::InvalidRoyaltyPolicy
+    );
+    require!(
+        platform_fee_override_bps.is_none_or(|bps| bps <= MAX_PLATFORM_FEE_OVERRIDE_BPS),
+        EscrowError::InvalidFeeConfiguration
+    );
+    Ok(())
+}
+
+/// Resolves the registry entry for the NFT's collection. Collection fields
+/// that are present but unverified are rejected outright. NFTs outside any
+/// collection, and verified collections the registry has not reviewed yet,
+/// resolve to `None`.
+fn load_collection_entry(
+    metadata: &MetadataAccount,
+    collection_entry: Option<&UncheckedAccount>,
+) -> Result<Option<CollectionEntry>> {
+    let Some(collection) = metadata.collection.as_ref() else {
+        return Ok(None);
+    };
+    require!(collection.verified, EscrowError::UnverifiedCollection);
+    let entry_info = collection_entry.ok_or(EscrowError::MissingCollectionEntry)?;
+    let (expected, _) = Pubkey::find_program_address(
+        &[COLLECTION_ENTRY_SEED, collection.key.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(
+        entry_info.key(),
+        expected,
+        EscrowError::CollectionEntryMismatch
+    );
+    if entry_info.owner != &crate::ID {
+        return Ok(None);
+    }
+    let data = entry_info.try_borrow_data()?;
+    let entry = CollectionEntry::try_deserialize(&mut &data[..])?;
+    Ok(Some(entry))
+}
+
+/// Orders two wallets so a trading pair maps to one `PairVolume` account
+/// regardless of direction.
+pub fn ordered_pair<'a>(first: &'a Pubkey, second: &'a Pubkey) -> (&'a Pubkey, &'a Pubkey) {
+    if first <= second {
+        (first, second)
+    } else {
+        (second, first)
+    }
+}
+
+/// Records the sale against the mint and wallet-pair history and returns why
+/// its rewards must be withheld, if they must.
+fn assess_wash_trading(
+    rules: &WashTradingRules,
+    mint_sale_record: &mut MintSaleRecord,
+    pair_volume: &mut PairVolume,
+    seller: &Pubkey,
+    buyer: &Pubkey,
+    price: u64,
+    now: i64,
+) -> Result<Option<RewardSuppression>> {
+    let within_cooldown = mint_sale_record.last_sale_ts != 0
+        && now.saturating_sub(mint_sale_record.last_sale_ts) < rules.mint_cooldown_secs as i64;
+    mint_sale_record.last_seller = *seller;
+    mint_sale_record.last_buyer = *buyer;
+    mint_sale_record.last_sale_ts = now;
+
+    if now.saturating_sub(pair_volume.window_start_ts) >= rules.pair_volume_window_secs as i64 {
+        pair_volume.window_start_ts = now;
+        pair_volume.window_volume = 0;
+    }
+    pair_volume.window_volume = pair_volume
+        .window_volume
+        .checked_add(price)
+        .ok_or(EscrowError::MathOverflow)?;
+    let over_pair_cap = rules.pair_volume_cap_lamports > 0
+        && pair_volume.window_volume > rules.pair_volume_cap_lamports;
+
+    Ok(if seller == buyer {
+        Some(RewardSuppression::SelfTrade)
+    } else if within_cooldown {
+        Some(RewardSuppression::MintCooldown)
+    } else if over_pair_cap {
+        Some(RewardSuppression::PairVolumeCap)
+    } else {
+        None
+    })
+}
+
+/// Returns the index of `info` in a `settle_many` record cache, loading the
+/// record on first use or creating the PDA (rent paid by `payer`) when it
+/// does not exist yet. Cached records are written back once the batch ends.
+fn load_or_create_record<'info, T: AccountDeserialize>(
+    cache: &mut Vec<(AccountInfo<'info>, T)>,
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    space: usize,
+    init: impl FnOnce() -> T,
+) -> Result<usize> {
+    if let Some(index) = cache.iter().position(|(cached, _)| cached.key == info.key) {
+        return Ok(index);
+    }
+    let record = if info.owner == &crate::ID {
+        T::try_deserialize(&mut &info.try_borrow_data()?[..])?
+    } else {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: info.clone(),
+                },
+                &[signer_seeds],
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &crate::ID,
+        )?;
+        init()
+    };
+    cache.push((info.clone(), record));
+    Ok(cache.len() - 1)
+}
+
+/// Checks `info` is the `CollectionStats` PDA for `collection` and returns
+/// its bump.
+fn collection_stats_bump(info: &AccountInfo, collection: &Pubkey) -> Result<u8> {
+    let (expected, bump) =
+        Pubkey::find_program_address(&[COLLECTION_STATS_SEED, collection.as_ref()], &crate::ID);
+    require_keys_eq!(info.key(), expected, EscrowError::CollectionStatsMismatch);
+    Ok(bump)
+}
+
+/// Folds a sale into the collection's stats, creating the PDA on the
+/// collection's first sale when nobody has called
+/// `initialize_collection_stats` yet.
+#[allow(clippy::too_many_arguments)]
+fn record_collection_sale<'info>(
+    stats: &mut Vec<(AccountInfo<'info>, CollectionStats)>,
+    info: &AccountInfo<'info>,
+    collection: &Pubkey,
+    bump: u8,
+    price: u64,
+    now: i64,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let index = load_or_create_record(
+        stats,
+        info,
+        payer,
+        system_program,
+        &[COLLECTION_STATS_SEED, collection.as_ref(), &[bump]],
+        CollectionStats::LEN,
+        || CollectionStats {
+            bump,
+            collection: *collection,
+            ..Default::default()
+        },
+    )?;
+    stats[index].1.record_sale(price, now)
+}
+
+/// Fails unless `signer` is the upgrade authority recorded in the program's
+/// `ProgramData`. A program made immutable has no authority and rejects
+/// everyone.
+fn ensure_upgrade_authority(program_data: &ProgramData, signer: &Pubkey) -> Result<()> {
+    require!(
+        program_data.upgrade_authority_address == Some(*signer),
+        EscrowError::UnauthorizedConfigInitializer
+    );
+    Ok(())
+}
+
+/// Takes the next market-wide sale sequence number.
+fn next_sale_sequence(config: &mut MarketConfig) -> Result<u64> {
+    config.last_sale_sequence = config
+        .last_sale_sequence
+        .checked_add(1)
+        .ok_or(EscrowError::MathOverflow)?;
+    Ok(config.last_sale_sequence)
+}
+
+/// Appends a trade to `wallet`'s history, creating it on first use. Loaded
+/// histories are cached so a wallet on both sides of a trade, or in several
+/// trades of a batch, keeps every entry.
+fn record_trade<'info>(
+    histories: &mut Vec<(AccountInfo<'info>, TradeHistory)>,
+    info: &AccountInfo<'info>,
+    wallet: &Pubkey,
+    bump: u8,
+    entry: TradeHistoryEntry,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let index = load_or_create_record(
+        histories,
+        info,
+        payer,
+        system_program,
+        &[TRADE_HISTORY_SEED, wallet.as_ref(), &[bump]],
+        TradeHistory::LEN,
+        || TradeHistory {
+            bump,
+            wallet: *wallet,
+            ..Default::default()
+        },
+    )?;
+    histories[index].1.record(entry)
+}
+
+/// Blocklist page that holds `wallet`.
+pub fn blocklist_page_index(wallet: &Pubkey) -> u8 {
+    wallet.to_bytes()[0]
+}
+
+/// Fails with `blocked_error` when `wallet` is on the blocklist. `page` must
+/// be the blocklist page PDA for the wallet; a page that was never created
+/// blocks nobody.
+fn ensure_not_blocked(
+    wallet: &Pubkey,
+    page: &AccountInfo,
+    blocked_error: EscrowError,
+) -> Result<()> {
+    let (expected, _) = Pubkey::find_program_address(
+        &[BLOCKLIST_PAGE_SEED, &[blocklist_page_index(wallet)]],
+        &crate::ID,
+    );
+    require_keys_eq!(page.key(), expected, EscrowError::BlocklistPageMismatch);
+    if page.owner != &crate::ID {
+        return Ok(());
+    }
+    let data = page.try_borrow_data()?;
+    let page = BlocklistPage::try_deserialize(&mut &data[..])?;
+    if page.entries.binary_search(wallet).is_ok() {
+        return Err(error!(blocked_error));
+    }
+    Ok(())
+}
+
+fn validate_clout_fee_terms(terms: &CloutFeeTerms) -> Result<()> {
+    require!(
+        terms.discount_bps <= MAX_CLOUT_FEE_DISCOUNT_BPS
+            && terms.burn_bps as u64 <= BPS_DENOMINATOR,
+        EscrowError::InvalidCloutFeeTerms
+    );
+    Ok(())
+}
+
+fn validate_fee_configuration(
+    royalty_bps: u16,
+    treasury_bps: u16,
+    marketplace_fee_bps: u16,
+    platform_fee_override_bps: Option<u16>,
+) -> Result<()> {
+    require!(
+        treasury_bps == OPS_TREASURY_BPS && marketplace_fee_bps == REWARD_POOL_BPS,
+        EscrowError::UnsupportedFeeConfiguration
+    );
+    let platform_fee_bps = platform_fee_override_bps.unwrap_or(PLATFORM_FEE_BPS);
+    let total = royalty_bps as u64 + platform_fee_bps as u64;
+    require!(
+        total <= BPS_DENOMINATOR,
+        EscrowError::InvalidFeeConfiguration
+    );
+    Ok(())
+}
+
+/// A listing from a `settle_many` batch that passed validation.
+struct PendingSettlement<'info> {
+    listing: Account<'info, Listing>,
+    accounts: &'info [AccountInfo<'info>],
+    /// Verified collection of the NFT and the bump of its stats PDA.
+    collection: Option<(Pubkey, u8)>,
+    receipt_bump: u8,
+    mint_sale_record_bump: u8,
+    pair_volume_bump: u8,
+    buyer_trade_history_bump: u8,
+    seller_trade_history_bump: u8,
+    split: SaleSplit,
+    /// Filled in once the seller proceeds are paid out.
+    seller_payouts: Vec<SellerPayout>,
+    reward_amount: u64,
+    loyalty_bonus_points: u64,
+}
+
+/// Splits `settle_many` remaining accounts into one group per entry: the
+/// fixed per-listing accounts followed by `payout_account_count` payout
+/// wallets. The groups must use up every account.
+fn split_settlement_accounts<'a, T>(
+    mut accounts: &'a [T],
+    entries: &[SettleManyEntry],
+) -> Result<Vec<&'a [T]>> {
+    let mut groups = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let len = SETTLE_MANY_ACCOUNTS_PER_LISTING + entry.payout_account_count as usize;
+        require!(accounts.len() >= len, EscrowError::InvalidSettlementBatch);
+        let (group, rest) = accounts.split_at(len);
+        groups.push(group);
+        accounts = rest;
+    }
+    require!(accounts.is_empty(), EscrowError::InvalidSettlementBatch);
+    Ok(groups)
+}
+
+/// Validates every listing of a `settle_many` batch before any lamports move.
+/// A listing that fails `load`, or repeats a listing already accepted, is
+/// reported as unsettled and left out of the returned settlements.
+fn partition_settlement_batch<T>(
+    listing_keys: impl IntoIterator<Item = Pubkey>,
+    mut load: impl FnMut(usize) -> Result<T>,
+) -> (Vec<T>, Vec<SettlementOutcome>) {
+    let mut accepted = Vec::new();
+    let mut pending = Vec::new();
+    let mut outcomes = Vec::new();
+    for (index, listing_key) in listing_keys.into_iter().enumerate() {
+        let loaded = if accepted.contains(&listing_key) {
+            Err(error!(EscrowError::DuplicateListingInBatch))
+        } else {
+            load(index)
+        };
+        match loaded {
+            Ok(settlement) => {
+                accepted.push(listing_key);
+                pending.push(settlement);
+                outcomes.push(SettlementOutcome {
+                    listing: listing_key,
+                    settled: true,
+                    error_code: 0,
+                });
+            }
+            Err(error) => {
+                msg!("Skipping listing {}: {}", listing_key, error);
+                outcomes.push(SettlementOutcome {
+                    listing: listing_key,
+                    settled: false,
+                    error_code: error_code_number(&error),
+                });
+            }
+        }
+    }
+    (pending, outcomes)
+}
+
+/// Checks that a settlement record PDA either holds a `T` or is still empty,
+/// so loading or creating it cannot fail once payouts have started.
+fn ensure_record_loadable<T: AccountDeserialize>(
+    info: &AccountInfo,
+    mismatch_error: EscrowError,
+) -> Result<()> {
+    let loadable = if info.owner == &crate::ID {
+        T::try_deserialize(&mut &info.try_borrow_data()?[..]).is_ok()
+    } else {
+        info.owner == &system_program::ID && info.lamports() == 0
+    };
+    if !(info.is_writable && loadable) {
+        return Err(error!(mismatch_error));
+    }
+    Ok(())
+}
+
+/// Performs every check `settle_sale` applies to a single listing without
+/// mutating any state, so a failure only skips this listing. Every record
+/// written later in the batch is checked here as well.
+fn load_pending_settlement<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    entry: &SettleManyEntry,
+    reward_mint: &Pubkey,
+) -> Result<PendingSettlement<'info>> {
+    let Some((
+        [listing_info, escrow_info, seller, buyer, royalty_destination, receipt, buyer_reward_account, loyalty_profile, nft_metadata, collection_stats, mint_sale_record, pair_volume, buyer_trade_history, seller_trade_history],
+        payout_accounts,
+    )) = accounts.split_first_chunk::<SETTLE_MANY_ACCOUNTS_PER_LISTING>()
+    else {
+        return err!(EscrowError::InvalidSettlementBatch);
+    };
+
+    let listing: Account<'info, Listing> = Account::try_from(listing_info)?;
+    ensure_ready_to_settle(&listing, Clock::get()?.unix_timestamp)?;
+    check_payout_accounts(&listing.payout_destinations, payout_accounts)?;
+    let buyer_key = listing.buyer.ok_or(EscrowError::MissingBuyer)?;
+    require_keys_eq!(buyer_key, buyer.key(), EscrowError::BuyerMismatch);
+    require_keys_eq!(listing.seller, seller.key(), EscrowError::SellerMismatch);
+    require!(seller.is_signer, EscrowError::MissingSellerSignature);
+    require_keys_eq!(
+        listing.royalty_destination,
+        royalty_destination.key(),
+        EscrowError::RoyaltyDestinationMismatch
+    );
+    for payout in [seller, royalty_destination] {
+        require!(payout.is_writable, EscrowError::InvalidPayoutAccount);
+        require_keys_eq!(
+            *payout.owner,
+            system_program::ID,
+            EscrowError::InvalidPayoutAccount
+        );
+    }
+
+    let escrow_vault = SystemAccount::try_from(escrow_info)?;
+    require_keys_eq!(
+        escrow_vault.key(),
+        escrow_vault_address(&listing)?,
+        EscrowError::EscrowVaultMismatch
+    );
+    require!(escrow_vault.is_writable, EscrowError::EscrowVaultMismatch);
+
+    let (receipt_key, receipt_bump) = Pubkey::find_program_address(
+        &[RECEIPT_SEED, listing.key().as_ref(), buyer_key.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(
+        receipt.key(),
+        receipt_key,
+        EscrowError::InvalidReceiptAccount
+    );
+    require!(
+        receipt.lamports() == 0 && receipt.is_writable,
+        EscrowError::InvalidReceiptAccount
+    );
+
+    let reward_account: Account<'info, TokenAccount> = Account::try_from(buyer_reward_account)?;
+    require!(
+        reward_account.mint == *reward_mint
+            && reward_account.owner == buyer_key
+            && buyer_reward_account.is_writable,
+        EscrowError::InvalidBuyerRewardAccount
+    );
+    let profile: Account<'info, LoyaltyProfile> = Account::try_from(loyalty_profile)?;
+    require_keys_eq!(
+        profile.owner,
+        buyer_key,
+        EscrowError::LoyaltyProfileMismatch
+    );
+    require!(
+        loyalty_profile.is_writable,
+        EscrowError::LoyaltyProfileMismatch
+    );
+
+    let (metadata_key, _) = Pubkey::find_program_address(
+        &[
+            METADATA_SEED,
+            Metadata::id().as_ref(),
+            listing.mint.as_ref(),
+        ],
+        &Metadata::id(),
+    );
+    require_keys_eq!(
+        nft_metadata.key(),
+        metadata_key,
+        EscrowError::InvalidMetadataAccount
+    );
+    let metadata = load_optional_metadata(nft_metadata)?;
+    let collection = match metadata.as_ref().and_then(verified_collection) {
+        Some(collection) => {
+            require!(
+                collection_stats.key() != crate::ID,
+                EscrowError::MissingCollectionStats
+            );
+            let bump = collection_stats_bump(collection_stats, &collection)?;
+            ensure_record_loadable::<CollectionStats>(
+                collection_stats,
+                EscrowError::CollectionStatsMismatch,
+            )?;
+            Some((collection, bump))
+        }
+        None => None,
+    };
+
+    let (mint_sale_record_key, mint_sale_record_bump) =
+        Pubkey::find_program_address(&[MINT_SALE_RECORD_SEED, listing.mint.as_ref()], &crate::ID);
+    let (wallet_a, wallet_b) = ordered_pair(seller.key, buyer.key);
+    let (pair_volume_key, pair_volume_bump) = Pubkey::find_program_address(
+        &[PAIR_VOLUME_SEED, wallet_a.as_ref(), wallet_b.as_ref()],
+        &crate::ID,
+    );
+    require!(
+        mint_sale_record.key() == mint_sale_record_key && pair_volume.key() == pair_volume_key,
+        EscrowError::InvalidWashTradingRecord
+    );
+    ensure_record_loadable::<MintSaleRecord>(
+        mint_sale_record,
+        EscrowError::InvalidWashTradingRecord,
+    )?;
+    ensure_record_loadable::<PairVolume>(pair_volume, EscrowError::InvalidWashTradingRecord)?;
+    let (buyer_trade_history_key, buyer_trade_history_bump) =
+        Pubkey::find_program_address(&[TRADE_HISTORY_SEED, buyer.key.as_ref()], &crate::ID);
+    let (seller_trade_history_key, seller_trade_history_bump) =
+        Pubkey::find_program_address(&[TRADE_HISTORY_SEED, seller.key.as_ref()], &crate::ID);
+    require!(
+        buyer_trade_history.key() == buyer_trade_history_key
+            && seller_trade_history.key() == seller_trade_history_key,
+        EscrowError::InvalidTradeHistory
+    );
+    for history in [buyer_trade_history, seller_trade_history] {
+        ensure_record_loadable::<TradeHistory>(history, EscrowError::InvalidTradeHistory)?;
+    }
+
+    let split = listing_sale_split(&listing)?;
+    require!(
+        escrow_balance(&escrow_vault)? >= split.escrowed_amount()?,
+        EscrowError::InsufficientEscrowBalance
+    );
+    Ok(PendingSettlement {
+        listing,
+        accounts,
+        collection,
+        receipt_bump,
+        mint_sale_record_bump,
+        pair_volume_bump,
+        buyer_trade_history_bump,
+        seller_trade_history_bump,
+        split,
+        seller_payouts: Vec::new(),
+        reward_amount: entry.reward_amount,
+        loyalty_bonus_points: entry.loyalty_bonus_points,
+    })
+}
+
+fn error_code_number(error: &Error) -> u32 {
+    match error {
+        Error::AnchorError(error) => error.error_code_number,
+        Error::ProgramError(error) => u64::from(error.program_error.clone()) as u32,
+    }
+}
+
+/// How a sale price is divided between the seller, the royalty destination
+/// and the platform fee recipients.
+#[derive(Clone, Copy)]
+struct SaleSplit {
+    seller_payout: u64,
+    royalty_cut: u64,
+    developer_cut: u64,
+    reward_pool_cut: u64,
+    ops_cut: u64,
+}
+
+impl SaleSplit {
+    /// Developer, reward pool and ops cuts combined.
+    fn platform_fee(&self) -> Result<u64> {
+        self.developer_cut
+            .checked_add(self.reward_pool_cut)
+            .and_then(|total| total.checked_add(self.ops_cut))
+            .ok_or_else(|| error!(EscrowError::MathOverflow))
+    }
+
+    /// Lamports the escrow must hold to pay out this split.
+    fn escrowed_amount(&self) -> Result<u64> {
+        self.platform_fee()?
+            .checked_add(self.seller_payout)
+            .and_then(|total| total.checked_add(self.royalty_cut))
+            .ok_or_else(|| error!(EscrowError::MathOverflow))
+    }
+}
+
+/// Developer, reward pool and ops cuts summed over a `settle_many` batch.
+/// Seller proceeds and royalties are paid per listing and stay zero.
+fn sum_platform_cuts(splits: impl IntoIterator<Item = SaleSplit>) -> Result<SaleSplit> {
+    let mut total = SaleSplit {
+        seller_payout: 0,
+        royalty_cut: 0,
+        developer_cut: 0,
+        reward_pool_cut: 0,
+        ops_cut: 0,
+    };
+    for split in splits {
+        total.developer_cut = total
+            .developer_cut
+            .checked_add(split.developer_cut)
+            .ok_or(EscrowError::MathOverflow)?;
+        total.reward_pool_cut = total
+            .reward_pool_cut
+            .checked_add(split.reward_pool_cut)
+            .ok_or(EscrowError::MathOverflow)?;
+        total.ops_cut = total
+            .ops_cut
+            .checked_add(split.ops_cut)
+            .ok_or(EscrowError::MathOverflow)?;
+    }
+    Ok(total)
+}
+
+/// Sale split for a listing, without the SOL platform fee when the buyer
+/// already paid it in CLOUT.
+fn listing_sale_split(listing: &Listing) -> Result<SaleSplit> {
+    let mut split = compute_sale_split(
+        listing.price_lamports,
+        listing.royalty_bps,
+        listing.platform_fee_override_bps,
+    )?;
+    if listing.clout_fee_payment.is_some() {
+        split.developer_cut = 0;
+        split.reward_pool_cut = 0;
+        split.ops_cut = 0;
+    }
+    Ok(split)
+}
+
+/// Allocates every cut from the price in one step with `fee_engine`: the
+/// combined fee rounds down once and is apportioned by largest remainder,
+/// and the seller receives the rest, so the split always sums to the price.
+fn compute_sale_split(
+    price: u64,
+    royalty_bps: u16,
+    platform_fee_override_bps: Option<u16>,
+) -> Result<SaleSplit> {
+    let (developer_fee_bps, remaining_fee_bps) = platform_fee_shares(platform_fee_override_bps);
+    let remaining_fee_rate = Rate::from_bps(remaining_fee_bps);
+    let reward_pool_rate = remaining_fee_rate
+        .checked_mul_div(REWARD_FUND_RATIO_NUMERATOR, RATIO_DENOMINATOR)
+        .ok_or(EscrowError::MathOverflow)?;
+    let ops_rate = remaining_fee_rate
+        .checked_sub(reward_pool_rate)
+        .ok_or(EscrowError::MathOverflow)?;
+    let Allocation {
+        cuts: [royalty_cut, developer_cut, reward_pool_cut, ops_cut],
+        residual: seller_payout,
+    } = fee_engine::allocate(
+        price,
+        [
+            Rate::from_bps(royalty_bps),
+            Rate::from_bps(developer_fee_bps),
+            reward_pool_rate,
+            ops_rate,
+        ],
+    )
+    .map_err(|_| error!(EscrowError::InvalidFeeConfiguration))?;
+    Ok(SaleSplit {
+        seller_payout,
+        royalty_cut,
+        developer_cut,
+        reward_pool_cut,
+        ops_cut,
+    })
+}
+
+/// Developer and remaining (reward pool + ops) shares of the platform fee.
+/// An override keeps the default 2:1 developer-to-remaining proportion.
+fn platform_fee_shares(platform_fee_override_bps: Option<u16>) -> (u16, u16) {
+    match platform_fee_override_bps {
+        None => (DEVELOPER_FEE_BPS, REMAINING_FEE_BPS),
+        Some(total_bps) => {
+            let developer_bps =
+                (total_bps as u32 * DEVELOPER_FEE_BPS as u32 / PLATFORM_FEE_BPS as u32) as u16;
+            (developer_bps, total_bps - developer_bps)
+        }
+    }
+}
+
+fn compute_fee(amount: u64, bps: u16) -> Result<u64> {
+    let fee = amount
+        .checked_mul(bps as u64)
+        .ok_or_else(|| error!(EscrowError::MathOverflow))?
+        .checked_div(BPS_DENOMINATOR)
+        .ok_or_else(|| error!(EscrowError::MathOverflow))?;
+    Ok(fee)
+}
+
+fn rolling_average_delta(delta: u64) -> Result<u64> {
+    let weighted = delta
+        .checked_mul(ROLLING_AVERAGE_WEIGHT_BPS)
+        .ok_or_else(|| error!(EscrowError::MathOverflow))?
+        .checked_div(BPS_DENOMINATOR)
+        .ok_or_else(|| error!(EscrowError::MathOverflow))?;
+    Ok(weighted)
+}
+
+/// Reads the Metaplex metadata at `info`, which callers have already matched
+/// to the mint's metadata PDA. Mints that never had metadata created resolve
+/// to `None` and settle like NFTs outside any collection.
+fn load_optional_metadata(info: &AccountInfo) -> Result<Option<MetadataAccount>> {
+    if info.owner != &Metadata::id() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    Ok(Some(MetadataAccount::try_deserialize(&mut &data[..])?))
+}
+
+/// Returns the collection mint of `metadata` if the collection is verified.
+fn verified_collection(metadata: &MetadataAccount) -> Option<Pubkey> {
+    metadata
+        .collection
+        .as_ref()
+        .filter(|collection| collection.verified)
+        .map(|collection| collection.key)
+}
+
+/// Hash a bidder commits to in `commit_bid`: sha256 of the little-endian bid
+/// amount, the salt and the bidder key.
+pub fn bid_commitment(bidder: &Pubkey, amount: u64, salt: &[u8; 32]) -> [u8; 32] {
+    hashv(&[&amount.to_le_bytes(), salt, bidder.as_ref()]).to_bytes()
+}
+
+/// Hash a seller commits to in `create_raffle`: sha256 of the seed and the
+/// listing key.
+pub fn raffle_seed_commitment(listing: &Pubkey, seed: &[u8; 32]) -> [u8; 32] {
+    hashv(&[seed, listing.as_ref()]).to_bytes()
+}
+
+/// Bytes a seller signs for `order`: the domain separator, this program's id
+/// and the Borsh-encoded order.
+pub fn signed_order_message(order: &SignedOrder) -> Result<Vec<u8>> {
+    let mut message = Vec::with_capacity(SIGNED_ORDER_DOMAIN.len() + 32 + 128);
+    message.extend_from_slice(SIGNED_ORDER_DOMAIN);
+    message.extend_from_slice(crate::ID.as_ref());
+    order
+        .serialize(&mut message)
+        .map_err(|_| error!(EscrowError::SignedOrderMismatch))?;
+    Ok(message)
+}
+
+/// Checks that `ix` is an ed25519 program instruction verifying exactly one
+/// signature by `signer` over `message`, with all data inline.
+fn verify_ed25519_instruction(ix: &Instruction, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    const OFFSETS_START: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+    require_keys_eq!(
+        ix.program_id,
+        ed25519_program::ID,
+        EscrowError::InvalidOrderSignature
+    );
+    let data = &ix.data;
+    require!(
+        data.len() >= OFFSETS_START + OFFSETS_LEN && data[0] == 1,
+        EscrowError::InvalidOrderSignature
+    );
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let offsets = OFFSETS_START;
+    let public_key_offset = read_u16(offsets + 4) as usize;
+    let message_offset = read_u16(offsets + 8) as usize;
+    let message_size = read_u16(offsets + 10) as usize;
+    // Each data reference must point into this instruction, not another one.
+    for index_at in [offsets + 2, offsets + 6, offsets + 12] {
+        require!(
+            read_u16(index_at) == u16::MAX,
+            EscrowError::InvalidOrderSignature
+        );
+    }
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(EscrowError::InvalidOrderSignature)?;
+    let signed_message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(EscrowError::InvalidOrderSignature)?;
+    require!(
+        public_key == signer.as_ref() && signed_message == message,
+        EscrowError::InvalidOrderSignature
+    );
+    Ok(())
+}
+
+/// Merkle leaf committing a mint to a trait offer.
+pub fn trait_offer_leaf(mint: &Pubkey) -> [u8; 32] {
+    hashv(&[mint.as_ref()]).to_bytes()
+}
+
+/// Reads the newest entry of the SlotHashes sysvar without deserializing
+/// the whole account: a u64 entry count followed by (slot, hash) pairs.
+fn most_recent_slot_hash(slot_hashes: &AccountInfo) -> Result<[u8; 32]> {
+    let data = slot_hashes.try_borrow_data()?;
+    let hash = data.get(16..48).ok_or(EscrowError::SlotHashUnavailable)?;
+    let mut slot_hash = [0u8; 32];
+    slot_hash.copy_from_slice(hash);
+    Ok(slot_hash)
+}
+
+/// Payout destinations must be distinct wallets whose shares sum to 100%.
+fn validate_payout_destinations(destinations: &[PayoutDestination]) -> Result<()> {
+    if destinations.is_empty() {
+        return Ok(());
+    }
+    require!(
+        destinations.len() <= MAX_PAYOUT_DESTINATIONS,
+        EscrowError::InvalidPayoutDestinations
+    );
+    let mut total_bps: u64 = 0;
+    for (index, destination) in destinations.iter().enumerate() {
+        require!(
+            destination.share_bps > 0 && destination.wallet != Pubkey::default(),
+            EscrowError::InvalidPayoutDestinations
+        );
+        require!(
+            destinations[..index]
+                .iter()
+                .all(|earlier| earlier.wallet != destination.wallet),
+            EscrowError::InvalidPayoutDestinations
+        );
+        total_bps += destination.share_bps as u64;
+    }
+    require!(
+        total_bps == BPS_DENOMINATOR,
+        EscrowError::InvalidPayoutDestinations
+    );
+    Ok(())
+}
+
+/// Splits `amount` by share with `fee_engine`. The shares cover the whole
+/// amount, so rounding dust goes by largest remainder and nothing is left in
+/// escrow.
+fn split_seller_proceeds(destinations: &[PayoutDestination], amount: u64) -> Result<Vec<u64>> {
+    require!(
+        destinations.len() <= MAX_PAYOUT_DESTINATIONS,
+        EscrowError::InvalidPayoutDestinations
+    );
+    let mut rates = [Rate::ZERO; MAX_PAYOUT_DESTINATIONS];
+    for (rate, destination) in rates.iter_mut().zip(destinations) {
+        *rate = Rate::from_bps(destination.share_bps);
+    }
+    let allocation = fee_engine::allocate(amount, rates)
+        .map_err(|_| error!(EscrowError::InvalidPayoutDestinations))?;
+    require!(
+        allocation.residual == 0,
+        EscrowError::InvalidPayoutDestinations
+    );
+    Ok(allocation.cuts[..destinations.len()].to_vec())
+}
+
+/// Checks `payout_accounts` are the listing's payout destination wallets, in
+/// order and writable. Listings without destinations take no payout accounts.
+fn check_payout_accounts(
+    destinations: &[PayoutDestination],
+    payout_accounts: &[AccountInfo],
+) -> Result<()> {
+    require!(
+        payout_accounts.len() == destinations.len(),
+        EscrowError::PayoutAccountsMismatch
+    );
+    for (destination, account) in destinations.iter().zip(payout_accounts) {
+        require_keys_eq!(
+            account.key(),
+            destination.wallet,
+            EscrowError::PayoutAccountsMismatch
+        );
+        require!(account.is_writable, EscrowError::InvalidPayoutAccount);
+    }
+    Ok(())
+}
+
+/// Pays seller proceeds out of escrow: to the seller, or split across the
+/// listing's payout destinations, passed in order as `payout_accounts`.
+fn pay_seller_proceeds<'info>(
+    escrow: &ListingEscrow<'info>,
+    seller: &AccountInfo<'info>,
+    destinations: &[PayoutDestination],
+    payout_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<Vec<SellerPayout>> {
+    if destinations.is_empty() {
+        escrow.pay(seller, amount)?;
+        return Ok(Vec::new());
+    }
+    check_payout_accounts(destinations, payout_accounts)?;
+    let amounts = split_seller_proceeds(destinations, amount)?;
+    let mut payouts = Vec::with_capacity(destinations.len());
+    for ((destination, account), amount) in destinations.iter().zip(payout_accounts).zip(amounts) {
+        escrow.pay(account, amount)?;
+        payouts.push(SellerPayout {
+            wallet: destination.wallet,
+            amount,
+        });
+    }
+    Ok(payouts)
+}
+
+/// Transfers `amount` from the buyer into a listing's escrow vault.
+fn pay_into_escrow<'info>(
+    buyer: &Signer<'info>,
+    escrow_vault: &SystemAccount<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: buyer.to_account_info(),
+                to: escrow_vault.to_account_info(),
+            },
+        ),
+        amount,
+    )
+}
+
+/// Lamports escrowed in a listing's vault: its balance above the
+/// rent-exempt floor funded when the listing was created.
+fn escrow_balance(escrow_vault: &AccountInfo) -> Result<u64> {
+    Ok(escrow_vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0)))
+}
+
+fn escrow_vault_address(listing: &Account<Listing>) -> Result<Pubkey> {
+    Pubkey::create_program_address(
+        &[
+            ESCROW_VAULT_SEED,
+            listing.key().as_ref(),
+            &[listing.escrow_bump],
+        ],
+        &crate::ID,
+    )
+    .map_err(|_| error!(EscrowError::EscrowVaultMismatch))
+}
+
+/// A listing's escrow vault: a data-less system account whose balance is
+/// the deposit, so there is no separate total to drift from it. Lamports
+/// leave through system transfers signed with the vault's seeds.
+struct ListingEscrow<'info> {
+    vault: AccountInfo<'info>,
+    listing: Pubkey,
+    bump: u8,
+    system_program: AccountInfo<'info>,
+}
+
+impl<'info> ListingEscrow<'info> {
+    fn new(
+        vault: &AccountInfo<'info>,
+        listing: &Account<'info, Listing>,
+        system_program: &AccountInfo<'info>,
+    ) -> Self {
+        ListingEscrow {
+            vault: vault.clone(),
+            listing: listing.key(),
+            bump: listing.escrow_bump,
+            system_program: system_program.clone(),
+        }
+    }
+
+    fn balance(&self) -> Result<u64> {
+        escrow_balance(&self.vault)
+    }
+
+    /// Pays `amount` of the deposit to `to`; the rent-exempt floor stays.
+    fn pay(&self, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        require!(
+            self.balance()? >= amount,
+            EscrowError::InsufficientEscrowBalance
+        );
+        self.transfer(to, amount)
+    }
+
+    /// Empties the vault, rent-exempt floor included, into `to`.
+    fn close(&self, to: &AccountInfo<'info>) -> Result<()> {
+        let lamports = self.vault.lamports();
+        if lamports == 0 {
+            return Ok(());
+        }
+        self.transfer(to, lamports)
+    }
+
+    fn transfer(&self, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        let bump = [self.bump];
+        let signer_seeds: &[&[u8]] = &[ESCROW_VAULT_SEED, self.listing.as_ref(), &bump];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                self.system_program.clone(),
+                system_program::Transfer {
+                    from: self.vault.clone(),
+                    to: to.clone(),
+                },
+                &[signer_seeds],
+            ),
+            amount,
+        )
+    }
+}
+
+fn disburse(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    debit_lamports(from, amount)?;
+    credit_lamports(to, amount)
+}
+
+fn debit_lamports(from: &AccountInfo, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    require_keys_eq!(*from.owner, crate::ID, EscrowError::InvalidPayoutAccount);
+    **from.try_borrow_mut_lamports()? = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(EscrowError::MathOverflow)?;
+    Ok(())
+}
+
+fn credit_lamports(to: &AccountInfo, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    require_keys_eq!(
+        *to.owner,
+        system_program::ID,
+        EscrowError::InvalidPayoutAccount
+    );
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(EscrowError::MathOverflow)?;
+    Ok(())
+}
+
+/// Credits the reward pool destination. Unlike `credit_lamports` the owner is
+/// not checked: the `clout_staking` revenue pool is a program account, and
+/// callers have already matched it against `MarketConfig::revenue_pool`.
+fn credit_reward_pool(to: &AccountInfo, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(EscrowError::MathOverflow)?;
+    Ok(())
+}
+
+#[error_code]
+pub enum EscrowError {
+    #[msg("Listing is not active.")]
+    ListingNotActive,
+    #[msg("Listing must be pending settlement for this operation.")]
+    ListingNotPending,
+    #[msg("Listing price must be greater than zero.")]
+    InvalidListingPrice,
+    #[msg("Total fee configuration exceeds 100%.")]
+    InvalidFeeConfiguration,
+    #[msg("Provided fee split is not supported by the protocol.")]
+    UnsupportedFeeConfiguration,
+    #[msg("Arithmetic overflow encountered.")]
+    MathOverflow,
+    #[msg("No buyer recorded for this listing.")]
+    MissingBuyer,
+    #[msg("Provided buyer account does not match listing state.")]
+    BuyerMismatch,
+    #[msg("Provided seller account does not match listing state.")]
+    SellerMismatch,
+    #[msg("Royalty destination does not match listing configuration.")]
+    RoyaltyDestinationMismatch,
+    #[msg("Escrow vault balance is insufficient.")]
+    InsufficientEscrowBalance,
+    #[msg("Escrow vault still holds funds; cancel not allowed.")]
+    OutstandingEscrowBalance,
+    #[msg("Escrow vault already funded for this listing.")]
+    EscrowAlreadyFunded,
+    #[msg("Loyalty authority does not match registry configuration.")]
+    UnauthorizedLoyaltyAuthority,
+    #[msg("Reward authority does not match vault configuration.")]
+    UnauthorizedRewardAuthority,
+    #[msg("Destination account must be a system account.")]
+    InvalidPayoutAccount,
+    #[msg("The listing has expired.")]
+    ListingExpired,
+    #[msg("Reward mint does not match vault configuration.")]
+    MismatchedRewardMint,
+    #[msg("Developer wallet does not match the configured address.")]
+    InvalidDeveloperWallet,
+    #[msg("Collection stats account is required for verified collections.")]
+    MissingCollectionStats,
+    #[msg("Collection stats account does not match the NFT's collection.")]
+    CollectionStatsMismatch,
+    #[msg("The listing has not expired yet.")]
+    ListingNotExpired,
+    #[msg("Expiry bounty exceeds the maximum allowed share of rent.")]
+    InvalidExpiryBounty,
+    #[msg("Caller is not the market configuration authority.")]
+    UnauthorizedMarketAuthority,
+    #[msg("Settlement batch accounts do not match the provided entries.")]
+    InvalidSettlementBatch,
+    #[msg("Listing appears more than once in the settlement batch.")]
+    DuplicateListingInBatch,
+    #[msg("Seller must sign to settle the listing.")]
+    MissingSellerSignature,
+    #[msg("Escrow vault does not belong to the listing.")]
+    EscrowVaultMismatch,
+    #[msg("Receipt account is not the expected uninitialized PDA.")]
+    InvalidReceiptAccount,
+    #[msg("Buyer reward account must be owned by the buyer and hold the reward mint.")]
+    InvalidBuyerRewardAccount,
+    #[msg("Loyalty profile does not belong to the buyer.")]
+    LoyaltyProfileMismatch,
+    #[msg("Metadata account does not match the listing mint.")]
+    InvalidMetadataAccount,
+    #[msg("Account data does not match a known layout version.")]
+    InvalidMigrationAccount,
+    #[msg("Account already uses the current layout.")]
+    AccountAlreadyMigrated,
+    #[msg("Listing is reserved by an auction, installment plan or raffle.")]
+    ListingInAuction,
+    #[msg("Auction commit and reveal deadlines are invalid.")]
+    InvalidAuctionSchedule,
+    #[msg("Auction is not open.")]
+    AuctionNotOpen,
+    #[msg("Auction has not been finalized.")]
+    AuctionNotFinalized,
+    #[msg("The commit phase has ended.")]
+    CommitPhaseClosed,
+    #[msg("Auction is not in its reveal phase.")]
+    NotInRevealPhase,
+    #[msg("The reveal phase has not ended.")]
+    RevealPhaseOpen,
+    #[msg("Bid deposit does not cover the bid.")]
+    InsufficientBidDeposit,
+    #[msg("Seller cannot bid on their own auction.")]
+    SellerCannotBid,
+    #[msg("Bid has already been revealed.")]
+    BidAlreadyRevealed,
+    #[msg("Revealed bid does not match the commitment.")]
+    CommitmentMismatch,
+    #[msg("Winning bid account does not match the auction winner.")]
+    WinningBidMismatch,
+    #[msg("Early-access window must be longer than zero seconds.")]
+    InvalidEarlyAccessWindow,
+    #[msg("Listing is restricted to qualifying buyers during early access.")]
+    EarlyAccessRestricted,
+    #[msg("NFT collection field is present but not verified.")]
+    UnverifiedCollection,
+    #[msg("Collection is banned from the marketplace.")]
+    CollectionBanned,
+    #[msg("Collection registry entry is required for verified collections.")]
+    MissingCollectionEntry,
+    #[msg("Collection registry entry does not match the NFT collection.")]
+    CollectionEntryMismatch,
+    #[msg("Royalty policy bounds are invalid.")]
+    InvalidRoyaltyPolicy,
+    #[msg("Royalty is outside the collection's royalty policy.")]
+    RoyaltyOutsidePolicy,
+    #[msg("Seller wallet is blocked from trading on this marketplace.")]
+    SellerBlocked,
+    #[msg("Buyer wallet is blocked from trading on this marketplace.")]
+    BuyerBlocked,
+    #[msg("Blocklist page does not match the wallet.")]
+    BlocklistPageMismatch,
+    #[msg("Blocklist page is full.")]
+    BlocklistPageFull,
+    #[msg("Wallet is already blocked.")]
+    WalletAlreadyBlocked,
+    #[msg("Wallet is not blocked.")]
+    WalletNotBlocked,
+    #[msg("Signer is not the compliance authority.")]
+    UnauthorizedComplianceAuthority,
+    #[msg("Wash-trading record account does not match the sale.")]
+    InvalidWashTradingRecord,
+    #[msg("Delivery confirmation window is too short.")]
+    InvalidRedemptionWindow,
+    #[msg("Listing is not a physical-redemption listing.")]
+    NotPhysicalRedemption,
+    #[msg("Sale is not awaiting delivery confirmation.")]
+    RedemptionNotAwaitingDelivery,
+    #[msg("Delivery confirmation window has closed.")]
+    ConfirmationWindowClosed,
+    #[msg("Escrow is held until delivery is confirmed or the window lapses.")]
+    RedemptionNotReleased,
+    #[msg("Sale is not under dispute.")]
+    RedemptionNotDisputed,
+    #[msg("Signer is neither the buyer nor the seller.")]
+    NotSaleParty,
+    #[msg("Buyer share must not exceed 100%.")]
+    InvalidDisputeSplit,
+    #[msg("Signer is not the redemption arbiter.")]
+    UnauthorizedArbiter,
+    #[msg("CLOUT fee discount or burn share is out of range.")]
+    InvalidCloutFeeTerms,
+    #[msg("CLOUT exchange rate must be greater than zero.")]
+    InvalidCloutRate,
+    #[msg("CLOUT exchange rate is missing or stale.")]
+    StaleCloutRate,
+    #[msg("CLOUT fee exceeds the buyer's maximum.")]
+    CloutFeeSlippageExceeded,
+    #[msg("Platform fee was already paid in CLOUT.")]
+    FeesAlreadyPaidInClout,
+    #[msg("Signer is not the CLOUT oracle authority.")]
+    UnauthorizedOracleAuthority,
+    #[msg("Installment terms are out of range.")]
+    InvalidInstallmentTerms,
+    #[msg("Installment plan is not open to a new buyer.")]
+    InstallmentPlanNotOffered,
+    #[msg("Installment plan is not active.")]
+    InstallmentPlanNotActive,
+    #[msg("Installment is past due.")]
+    InstallmentOverdue,
+    #[msg("No installment is past due yet.")]
+    InstallmentNotOverdue,
+    #[msg("Raffle terms are invalid.")]
+    InvalidRaffleTerms,
+    #[msg("Raffle is not open.")]
+    RaffleNotOpen,
+    #[msg("Raffle ticket sales have closed.")]
+    RaffleSalesClosed,
+    #[msg("Raffle ticket sales are still open.")]
+    RaffleSalesOpen,
+    #[msg("Raffle seed reveal window has closed.")]
+    RaffleRevealClosed,
+    #[msg("Raffle did not sell its minimum ticket count.")]
+    RaffleMinimumNotMet,
+    #[msg("Raffle can only be cancelled below its minimum or after a missed reveal.")]
+    RaffleNotCancellable,
+    #[msg("Raffle has not been cancelled.")]
+    RaffleNotCancelled,
+    #[msg("Raffle winner has not been drawn.")]
+    RaffleNotDrawn,
+    #[msg("Seller cannot buy tickets in their own raffle.")]
+    SellerCannotBuyTickets,
+    #[msg("Ticket ledger page is not the current page.")]
+    InvalidRaffleTicketPage,
+    #[msg("Ticket ledger page is full; use the next page.")]
+    RaffleTicketPageFull,
+    #[msg("Ticket ledger entry does not match.")]
+    RaffleEntryMismatch,
+    #[msg("Raffle tickets were already refunded.")]
+    RaffleTicketsRefunded,
+    #[msg("SlotHashes sysvar has no entries.")]
+    SlotHashUnavailable,
+    #[msg("Payout destinations must be distinct wallets with shares summing to 100%.")]
+    InvalidPayoutDestinations,
+    #[msg("Payout accounts do not match the listing's payout destinations.")]
+    PayoutAccountsMismatch,
+    /// No longer returned now that `settle_many` pays payout splits; kept so
+    /// later error codes keep their numbers.
+    #[msg("Listings with payout destinations must settle through settle_sale.")]
+    PayoutSplitNotBatchable,
+    #[msg("Trait offer price, quantity or expiry is invalid.")]
+    InvalidTraitOffer,
+    #[msg("Trait offer has expired.")]
+    TraitOfferExpired,
+    #[msg("Trait offer is completely filled.")]
+    TraitOfferFilled,
+    #[msg("Buyer cannot fill their own trait offer.")]
+    SellerCannotFillOwnOffer,
+    #[msg("NFT is not in the trait offer's collection.")]
+    TraitOfferCollectionMismatch,
+    #[msg("Merkle proof does not include this mint.")]
+    MintNotInTraitOffer,
+    #[msg("Signed order is missing a valid ed25519 signature by the seller.")]
+    InvalidOrderSignature,
+    #[msg("Signed order has expired.")]
+    SignedOrderExpired,
+    #[msg("Accounts do not match the signed order.")]
+    SignedOrderMismatch,
+    #[msg("Seller cannot fill their own order.")]
+    SellerCannotFillOwnOrder,
+    #[msg("NFT is not delegated to the order authority.")]
+    OrderNotDelegated,
+    #[msg("Reward pool destination is not the market's revenue pool.")]
+    InvalidRevenuePool,
+    #[msg("Buyer cancellation window exceeds the maximum.")]
+    InvalidBuyerCancelWindow,
+    #[msg("Buyer may still cancel; settle after the cancellation window.")]
+    BuyerCancelWindowOpen,
+    #[msg("Buyer cancellation window has closed.")]
+    BuyerCancelWindowClosed,
+    #[msg("This purchase cannot be cancelled by the buyer.")]
+    PurchaseNotCancellable,
+    #[msg("Account is not the listing's rent payer.")]
+    RentPayerMismatch,
+    #[msg("Trade history account does not match the wallet.")]
+    InvalidTradeHistory,
+    #[msg("Only the program upgrade authority may initialize the market configuration.")]
+    UnauthorizedConfigInitializer,
+    #[msg("No revenue pool is configured for the reward pool cut.")]
+    RevenuePoolNotConfigured,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_spl::metadata::mpl_token_metadata::types::DataV2;
+    use test_utils::assert_error;
+
+    fn pubkey(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    /// Raw bytes of a pending-settlement listing as written by the
+    /// unversioned program, padded to the allocated v0 size.
+    fn listing_v0_fixture() -> Vec<u8> {
+        let mut data = Listing::DISCRIMINATOR.to_vec();
+        push_listing_v0_fields(&mut data);
+        data.resize(ListingV0::LEN, 0);
+        data
+    }
+
+    /// Appends the fixture listing's v0 fields, which later layouts keep.
+    fn push_listing_v0_fields(data: &mut Vec<u8>) {
+        data.extend_from_slice(&[254, 253]); // bump, escrow bump
+        data.extend_from_slice(&[1; 32]); // seller
+        data.push(1); // buyer: Some
+        data.extend_from_slice(&[2; 32]);
+        data.extend_from_slice(&[3; 32]); // mint
+        data.extend_from_slice(&7u64.to_le_bytes()); // listing id
+        data.extend_from_slice(&1_500_000_000u64.to_le_bytes()); // price
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes()); // creation ts
+        data.push(0); // expiration ts: None
+        data.push(1); // sale ts: Some
+        data.extend_from_slice(&1_700_000_100i64.to_le_bytes());
+        data.push(0); // settlement ts: None
+        data.push(1); // status: PendingSettlement
+        data.extend_from_slice(&500u16.to_le_bytes()); // royalty bps
+        data.extend_from_slice(&[4; 32]); // royalty destination
+        data.extend_from_slice(&OPS_TREASURY_BPS.to_le_bytes());
+        data.extend_from_slice(&REWARD_POOL_BPS.to_le_bytes());
+    }
+
+    fn escrow_vault_v0_fixture() -> Vec<u8> {
+        let mut data = EscrowVault::DISCRIMINATOR.to_vec();
+        data.push(252); // bump
+        data.extend_from_slice(&[5; 32]); // listing
+        data.extend_from_slice(&1_500_000_000u64.to_le_bytes()); // total deposited
+        assert_eq!(data.len(), EscrowVaultV0::LEN);
+        data
+    }
+
+    fn sale_receipt_v0_fixture() -> Vec<u8> {
+        let mut data = SaleReceipt::DISCRIMINATOR.to_vec();
+        data.push(251); // bump
+        data.extend_from_slice(&[5; 32]); // listing
+        data.extend_from_slice(&[2; 32]); // buyer
+        data.extend_from_slice(&[1; 32]); // seller
+        for amount in [1_000u64, 900, 50, 12, 38, 10, 25, 40] {
+            data.extend_from_slice(&amount.to_le_bytes());
+        }
+        data.extend_from_slice(&1_700_000_200i64.to_le_bytes()); // timestamp
+        assert_eq!(data.len(), SaleReceiptV0::LEN);
+        data
+    }
+
+    /// Raw bytes of the fixture listing as written by a v1 program, with a
+    /// platform fee override, followed by `appended` and padded to `len`.
+    fn listing_v1_fixture(appended: &[u8], len: usize) -> Vec<u8> {
+        let mut data = Listing::DISCRIMINATOR.to_vec();
+        data.push(1); // version
+        push_listing_v0_fields(&mut data);
+        data.push(0); // sale mode: FixedPrice
+        data.push(0); // early access: None
+        data.push(1); // platform fee override: Some
+        data.extend_from_slice(&250u16.to_le_bytes());
+        data.extend_from_slice(&[0, 0]); // redemption, clout fee payment: None
+        data.extend_from_slice(appended);
+        assert!(data.len() <= len, "fixture exceeds allocated space");
+        data.resize(len, 0);
+        data
+    }
+
+    fn payout_destinations_fixture() -> Vec<u8> {
+        let mut data = 2u32.to_le_bytes().to_vec();
+        for (wallet, share_bps) in [(6u8, 7_000u16), (7, 3_000)] {
+            data.extend_from_slice(&[wallet; 32]);
+            data.extend_from_slice(&share_bps.to_le_bytes());
+        }
+        data
+    }
+
+    /// Raw bytes of the fixture receipt as written by a v1 program, followed
+    /// by `appended` and padded to `len`.
+    fn sale_receipt_v1_fixture(appended: &[u8], len: usize) -> Vec<u8> {
+        let mut data = SaleReceipt::DISCRIMINATOR.to_vec();
+        data.push(1); // version
+        data.extend_from_slice(&sale_receipt_v0_fixture()[8..]);
+        data.extend_from_slice(&[1, 0]); // rewards suppressed: Some(SelfTrade)
+        data.extend_from_slice(&300u64.to_le_bytes()); // clout fee paid
+        data.extend_from_slice(&30u64.to_le_bytes()); // clout fee burned
+        data.extend_from_slice(appended);
+        assert!(data.len() <= len, "fixture exceeds allocated space");
+        data.resize(len, 0);
+        data
+    }
+
+    fn seller_payouts_fixture() -> Vec<u8> {
+        let mut data = 2u32.to_le_bytes().to_vec();
+        for (wallet, amount) in [(6u8, 630u64), (7, 270)] {
+            data.extend_from_slice(&[wallet; 32]);
+            data.extend_from_slice(&amount.to_le_bytes());
+        }
+        data
+    }
+
+    fn serialized<T: AccountSerialize>(account: &T, len: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        assert!(data.len() <= len, "layout exceeds allocated space");
+        data.resize(len, 0);
+        data
+    }
+
+    #[test]
+    fn listing_v0_fixture_migrates_to_current_layout() {
+        let listing = decode_for_migration::<Listing>(&listing_v0_fixture())
+            .unwrap()
+            .expect("v0 listing should be upgraded");
+
+        assert_eq!(listing.version, Listing::VERSION);
+        assert_eq!(listing.bump, 254);
+        assert_eq!(listing.escrow_bump, 253);
+        assert_eq!(listing.seller, pubkey(1));
+        assert_eq!(listing.buyer, Some(pubkey(2)));
+        assert_eq!(listing.mint, pubkey(3));
+        assert_eq!(listing.listing_id, 7);
+        assert_eq!(listing.price_lamports, 1_500_000_000);
+        assert_eq!(listing.creation_ts, 1_700_000_000);
+        assert_eq!(listing.expiration_ts, None);
+        assert_eq!(listing.sale_ts, Some(1_700_000_100));
+        assert_eq!(listing.settlement_ts, None);
+        assert!(listing.status == ListingStatus::PendingSettlement);
+        assert_eq!(listing.royalty_bps, 500);
+        assert_eq!(listing.royalty_destination, pubkey(4));
+        assert_eq!(listing.treasury_bps, OPS_TREASURY_BPS);
+        assert_eq!(listing.marketplace_fee_bps, REWARD_POOL_BPS);
+
+        let migrated = serialized(&listing, Listing::LEN);
+        let reloaded = Listing::try_deserialize(&mut &migrated[..]).unwrap();
+        assert_eq!(reloaded.version, Listing::VERSION);
+        assert_eq!(reloaded.price_lamports, listing.price_lamports);
+        assert!(decode_for_migration::<Listing>(&migrated)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn escrow_vault_v0_fixture_migrates_to_current_layout() {
+        let vault = decode_for_migration::<EscrowVault>(&escrow_vault_v0_fixture())
+            .unwrap()
+            .expect("v0 escrow vault should be upgraded");
+
+        assert_eq!(vault.version, EscrowVault::VERSION);
+        assert_eq!(vault.bump, 252);
+        assert_eq!(vault.listing, pubkey(5));
+        assert_eq!(vault.total_deposited, 1_500_000_000);
+
+        let migrated = serialized(&vault, EscrowVault::LEN);
+        assert_eq!(migrated.len(), EscrowVault::LEN);
+        assert!(decode_for_migration::<EscrowVault>(&migrated)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn sale_receipt_v0_fixture_migrates_to_current_layout() {
+        let receipt = decode_for_migration::<SaleReceipt>(&sale_receipt_v0_fixture())
+            .unwrap()
+            .expect("v0 receipt should be upgraded");
+
+        assert_eq!(receipt.version, SaleReceipt::VERSION);
+        assert_eq!(receipt.bump, 251);
+        assert_eq!(receipt.listing, pubkey(5));
+        assert_eq!(receipt.buyer, pubkey(2));
+        assert_eq!(receipt.seller, pubkey(1));
+        assert_eq!(receipt.amount_paid, 1_000);
+        assert_eq!(receipt.seller_proceeds, 900);
+        assert_eq!(receipt.royalty_paid, 50);
+        assert_eq!(receipt.treasury_paid, 12);
+        assert_eq!(receipt.marketplace_fee_paid, 38);
+        assert_eq!(receipt.developer_paid, 10);
+        assert_eq!(receipt.rewards_minted, 25);
+        assert_eq!(receipt.loyalty_points_awarded, 40);
+        assert_eq!(receipt.timestamp, 1_700_000_200);
+
+        let migrated = serialized(&receipt, SaleReceipt::LEN);
+        assert_eq!(migrated.len(), SaleReceipt::LEN);
+    }
+
+    #[test]
+    fn listing_v1_fixtures_migrate_to_current_layout() {
+        for (appended, len) in [
+            (Vec::new(), ListingV1::LEN),
+            (payout_destinations_fixture(), ListingV1::PAYOUTS_LEN),
+        ] {
+            let listing = decode_for_migration::<Listing>(&listing_v1_fixture(&appended, len))
+                .unwrap()
+                .expect("v1 listing should be upgraded");
+
+            assert_eq!(listing.version, Listing::VERSION);
+            assert_eq!(listing.bump, 254);
+            assert_eq!(listing.seller, pubkey(1));
+            assert_eq!(listing.buyer, Some(pubkey(2)));
+            assert_eq!(listing.price_lamports, 1_500_000_000);
+            assert!(listing.status == ListingStatus::PendingSettlement);
+            assert!(listing.sale_mode == SaleMode::FixedPrice);
+            assert_eq!(listing.platform_fee_override_bps, Some(250));
+            assert!(listing.clout_fee_payment.is_none());
+            let destinations: Vec<_> = listing
+                .payout_destinations
+                .iter()
+                .map(|destination| (destination.wallet, destination.share_bps))
+                .collect();
+            if appended.is_empty() {
+                assert!(destinations.is_empty());
+            } else {
+                assert_eq!(destinations, [(pubkey(6), 7_000), (pubkey(7), 3_000)]);
+            }
+
+            let migrated = serialized(&listing, Listing::LEN);
+            assert!(decode_for_migration::<Listing>(&migrated)
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn sale_receipt_v1_fixtures_migrate_to_current_layout() {
+        for (appended, len) in [
+            (Vec::new(), SaleReceiptV1::LEN),
+            (seller_payouts_fixture(), SaleReceiptV1::PAYOUTS_LEN),
+        ] {
+            let receipt =
+                decode_for_migration::<SaleReceipt>(&sale_receipt_v1_fixture(&appended, len))
+                    .unwrap()
+                    .expect("v1 receipt should be upgraded");
+
+            assert_eq!(receipt.version, SaleReceipt::VERSION);
+            assert_eq!(receipt.bump, 251);
+            assert_eq!(receipt.listing, pubkey(5));
+            assert_eq!(receipt.amount_paid, 1_000);
+            assert_eq!(receipt.timestamp, 1_700_000_200);
+            assert_eq!(
+                receipt.rewards_suppressed,
+                Some(RewardSuppression::SelfTrade)
+            );
+            assert_eq!(receipt.clout_fee_paid, 300);
+            assert_eq!(receipt.clout_fee_burned, 30);
+            let payouts: Vec<_> = receipt
+                .seller_payouts
+                .iter()
+                .map(|payout| (payout.wallet, payout.amount))
+                .collect();
+            if appended.is_empty() {
+                assert!(payouts.is_empty());
+            } else {
+                assert_eq!(payouts, [(pubkey(6), 630), (pubkey(7), 270)]);
+            }
+
+            let migrated = serialized(&receipt, SaleReceipt::LEN);
+            assert!(decode_for_migration::<SaleReceipt>(&migrated)
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn migration_rejects_unknown_layouts() {
+        let mut truncated = listing_v0_fixture();
+        truncated.pop();
+        assert!(decode_for_migration::<Listing>(&truncated).is_err());
+
+        let mut wrong_discriminator = escrow_vault_v0_fixture();
+        wrong_discriminator[0] ^= 0xff;
+        assert!(decode_for_migration::<EscrowVault>(&wrong_discriminator).is_err());
+
+        assert!(decode_for_migration::<SaleReceipt>(&listing_v0_fixture()).is_err());
+    }
+
+    fn sealed_auction(pricing: AuctionPricing, reserve_price: u64) -> SealedAuction {
+        SealedAuction {
+            bump: 255,
+            listing: pubkey(30),
+            seller: pubkey(1),
+            pricing,
+            reserve_price,
+            commit_end_ts: 1_700_000_000,
+            reveal_end_ts: 1_700_003_600,
+            bid_count: 0,
+            revealed_count: 0,
+            highest_bid: 0,
+            highest_bidder: None,
+            second_price: 0,
+            status: AuctionStatus::Open,
+        }
+    }
+
+    #[test]
+    fn bid_commitment_binds_amount_salt_and_bidder() {
+        let bidder = pubkey(31);
+        let salt = [7u8; 32];
+        let commitment = bid_commitment(&bidder, 5_000, &salt);
+        assert_eq!(
+            commitment,
+            hashv(&[&5_000u64.to_le_bytes(), &salt, bidder.as_ref()]).to_bytes()
+        );
+
+        let mut other_salt = salt;
+        other_salt[31] ^= 1;
+        for other in [
+            bid_commitment(&bidder, 5_001, &salt),
+            bid_commitment(&bidder, 5_000, &other_salt),
+            bid_commitment(&pubkey(32), 5_000, &salt),
+        ] {
+            assert_ne!(other, commitment);
+        }
+    }
+
+    #[test]
+    fn sealed_auction_clears_at_first_or_second_price() {
+        let reveals = [
+            (pubkey(33), 400),
+            (pubkey(34), 900),
+            (pubkey(35), 900),
+            (pubkey(36), 700),
+        ];
+        let mut first = sealed_auction(AuctionPricing::FirstPrice, 500);
+        let mut second = sealed_auction(AuctionPricing::SecondPrice, 500);
+        for (bidder, amount) in reveals {
+            // reveal_bid only records bids that meet the reserve.
+            if amount >= 500 {
+                first.record_revealed_bid(bidder, amount);
+                second.record_revealed_bid(bidder, amount);
+            }
+        }
+
+        // The tie at 900 goes to the earlier reveal.
+        assert_eq!(first.highest_bidder, Some(pubkey(34)));
+        assert_eq!(first.clearing_price(), 900);
+        assert_eq!(second.second_price, 900);
+        assert_eq!(second.clearing_price(), 900);
+
+        let mut lone = sealed_auction(AuctionPricing::SecondPrice, 500);
+        lone.record_revealed_bid(pubkey(37), 800);
+        assert_eq!(lone.clearing_price(), 500);
+    }
+
+    fn market_config() -> MarketConfig {
+        MarketConfig {
+            bump: 255,
+            authority: pubkey(40),
+            expiry_bounty_bps: 0,
+            early_access_pool: pubkey(41),
+            compliance_authority: pubkey(40),
+            wash_trading_rules: WashTradingRules::default(),
+            redemption_arbiter: pubkey(40),
+            revenue_pool: pubkey(42),
+            buyer_cancel_window_secs: 0,
+            last_sale_sequence: 0,
+            last_updated_ts: 0,
+        }
+    }
+
+    #[test]
+    fn early_access_admits_qualified_buyers_until_the_window_closes() {
+        let mut listing = decode_for_migration::<Listing>(&listing_v0_fixture())
+            .unwrap()
+            .unwrap();
+        listing.early_access = Some(EarlyAccess {
+            window_secs: 600,
+            min_tier: LoyaltyTier::Gold,
+            min_stake: 1_000,
+        });
+        let opened = listing.creation_ts;
+        let closes = opened + 600;
+        let buyer = pubkey(43);
+        let profile = |tier| LoyaltyProfile {
+            bump: 255,
+            owner: buyer,
+            total_volume: 0,
+            points: 0,
+            tier,
+            last_activity_ts: 0,
+            delegate: None,
+        };
+        let position = |pool, amount| StakePosition {
+            bump: 255,
+            owner: buyer,
+            pool,
+            amount,
+            reward_per_token_paid: 0,
+            pending_rewards: 0,
+            last_stake_ts: 0,
+        };
+        let config = market_config();
+        let check = |now, profile: Option<&LoyaltyProfile>, position: Option<&StakePosition>| {
+            check_early_access(&listing, &buyer, now, profile, position, Some(&config))
+        };
+
+        assert_error(
+            check(opened, None, None),
+            EscrowError::EarlyAccessRestricted,
+        );
+        check(closes, None, None).unwrap();
+
+        check(opened, Some(&profile(LoyaltyTier::Gold)), None).unwrap();
+        check(opened, Some(&profile(LoyaltyTier::Diamond)), None).unwrap();
+        assert_error(
+            check(closes - 1, Some(&profile(LoyaltyTier::Silver)), None),
+            EscrowError::EarlyAccessRestricted,
+        );
+
+        check(
+            opened,
+            None,
+            Some(&position(config.early_access_pool, 1_000)),
+        )
+        .unwrap();
+        for wrong in [
+            position(config.early_access_pool, 999),
+            position(pubkey(44), 5_000),
+        ] {
+            assert_error(
+                check(opened, None, Some(&wrong)),
+                EscrowError::EarlyAccessRestricted,
+            );
+        }
+        let mut borrowed = profile(LoyaltyTier::Diamond);
+        borrowed.owner = pubkey(45);
+        assert_error(
+            check(opened, Some(&borrowed), None),
+            EscrowError::EarlyAccessRestricted,
+        );
+    }
+
+    #[test]
+    fn registry_royalty_policy_bounds_royalty_and_pins_destination() {
+        let pinned = pubkey(50);
+        let policy = RoyaltyPolicy {
+            min_royalty_bps: 250,
+            max_royalty_bps: 750,
+            royalty_destination: Some(pinned),
+        };
+        policy.check(250, &pinned).unwrap();
+        policy.check(750, &pinned).unwrap();
+        for royalty_bps in [249, 751] {
+            assert_error(
+                policy.check(royalty_bps, &pinned),
+                EscrowError::RoyaltyOutsidePolicy,
+            );
+        }
+        assert_error(
+            policy.check(500, &pubkey(51)),
+            EscrowError::RoyaltyDestinationMismatch,
+        );
+
+        let unpinned = RoyaltyPolicy {
+            royalty_destination: None,
+            ..policy
+        };
+        unpinned.check(500, &pubkey(51)).unwrap();
+    }
+
+    #[test]
+    fn registry_terms_and_fee_override_are_validated() {
+        let policy = |min_royalty_bps, max_royalty_bps| RoyaltyPolicy {
+            min_royalty_bps,
+            max_royalty_bps,
+            royalty_destination: None,
+        };
+        validate_collection_terms(&policy(0, 10_000), Some(MAX_PLATFORM_FEE_OVERRIDE_BPS)).unwrap();
+        assert_error(
+            validate_collection_terms(&policy(600, 500), None),
+            EscrowError::InvalidRoyaltyPolicy,
+        );
+        assert_error(
+            validate_collection_terms(&policy(0, 10_001), None),
+            EscrowError::InvalidRoyaltyPolicy,
+        );
+        assert_error(
+            validate_collection_terms(&policy(0, 500), Some(MAX_PLATFORM_FEE_OVERRIDE_BPS + 1)),
+            EscrowError::InvalidFeeConfiguration,
+        );
+
+        // The override replaces the default platform fee in the royalty cap.
+        let max_royalty = 10_000 - PLATFORM_FEE_BPS;
+        validate_fee_configuration(max_royalty, OPS_TREASURY_BPS, REWARD_POOL_BPS, None).unwrap();
+        validate_fee_configuration(9_900, OPS_TREASURY_BPS, REWARD_POOL_BPS, Some(100)).unwrap();
+        assert_error(
+            validate_fee_configuration(max_royalty + 1, OPS_TREASURY_BPS, REWARD_POOL_BPS, None),
+            EscrowError::InvalidFeeConfiguration,
+        );
+        assert_error(
+            validate_fee_configuration(9_901, OPS_TREASURY_BPS, REWARD_POOL_BPS, Some(100)),
+            EscrowError::InvalidFeeConfiguration,
+        );
+    }
+
+    #[test]
+    fn blocklist_page_keeps_entries_sorted_and_bounded() {
+        let mut page = BlocklistPage {
+            bump: 255,
+            page_index: 0,
+            entries: Vec::new(),
+        };
+        for byte in [9, 3, 6] {
+            page.insert(pubkey(byte)).unwrap();
+        }
+        assert_eq!(page.entries, vec![pubkey(3), pubkey(6), pubkey(9)]);
+        assert_error(page.insert(pubkey(6)), EscrowError::WalletAlreadyBlocked);
+
+        page.remove(&pubkey(6)).unwrap();
+        assert_error(page.remove(&pubkey(6)), EscrowError::WalletNotBlocked);
+
+        page.entries = (0..BLOCKLIST_PAGE_CAPACITY)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        page.entries.sort();
+        assert_error(
+            page.insert(Pubkey::new_unique()),
+            EscrowError::BlocklistPageFull,
+        );
+    }
+
+    #[test]
+    fn blocklist_screens_wallets_on_their_own_page() {
+        let blocked = pubkey(60);
+        let mut cleared_bytes = [60; 32];
+        cleared_bytes[31] = 61;
+        let cleared = Pubkey::new_from_array(cleared_bytes);
+        let page_index = blocklist_page_index(&blocked);
+        let (page_key, bump) =
+            Pubkey::find_program_address(&[BLOCKLIST_PAGE_SEED, &[page_index]], &crate::ID);
+        let mut page = BlocklistPage {
+            bump,
+            page_index,
+            entries: Vec::new(),
+        };
+        page.insert(blocked).unwrap();
+        let mut data = serialized(&page, BlocklistPage::LEN);
+        let mut lamports = 1;
+        let program_id = crate::ID;
+        let info = AccountInfo::new(
+            &page_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &program_id,
+            false,
+            0,
+        );
+
+        assert_error(
+            ensure_not_blocked(&blocked, &info, EscrowError::BuyerBlocked),
+            EscrowError::BuyerBlocked,
+        );
+        assert_eq!(blocklist_page_index(&cleared), page_index);
+        ensure_not_blocked(&cleared, &info, EscrowError::BuyerBlocked).unwrap();
+
+        // Wallets on another page must present that page's PDA.
+        assert_error(
+            ensure_not_blocked(&pubkey(61), &info, EscrowError::BuyerBlocked),
+            EscrowError::BlocklistPageMismatch,
+        );
+
+        let mut empty_lamports = 0;
+        let uncreated = AccountInfo::new(
+            &page_key,
+            false,
+            false,
+            &mut empty_lamports,
+            &mut [],
+            &system_program::ID,
+            false,
+            0,
+        );
+        ensure_not_blocked(&blocked, &uncreated, EscrowError::BuyerBlocked).unwrap();
+    }
+
+    #[test]
+    fn wash_trading_rules_suppress_self_trades_cooldowns_and_pair_volume() {
+        let rules = WashTradingRules {
+            mint_cooldown_secs: 3_600,
+            pair_volume_cap_lamports: 10_000,
+            pair_volume_window_secs: 86_400,
+        };
+        let (seller, buyer) = (pubkey(70), pubkey(71));
+        let mut record = MintSaleRecord::default();
+        let mut pair = PairVolume::default();
+        let mut sale = |price, now, buyer: &Pubkey| {
+            assess_wash_trading(&rules, &mut record, &mut pair, &seller, buyer, price, now).unwrap()
+        };
+
+        let start = 1_700_000_000;
+        assert_eq!(sale(4_000, start, &buyer), None);
+        assert_eq!(
+            sale(4_000, start + 3_599, &buyer),
+            Some(RewardSuppression::MintCooldown)
+        );
+        // The cooldown restarts from every sale, suppressed or not.
+        assert_eq!(
+            sale(2_000, start + 7_198, &buyer),
+            Some(RewardSuppression::MintCooldown)
+        );
+        // 4_000 + 4_000 + 2_000 reaches the cap; only exceeding it suppresses.
+        assert_eq!(
+            sale(1, start + 10_800, &buyer),
+            Some(RewardSuppression::PairVolumeCap)
+        );
+        // A new window resets the pair's running volume.
+        assert_eq!(sale(9_000, start + 86_400, &buyer), None);
+        assert_eq!(
+            sale(9_000, start + 90_000, &seller),
+            Some(RewardSuppression::SelfTrade)
+        );
+    }
+
+    #[test]
+    fn wash_trading_rules_default_to_only_blocking_self_trades() {
+        let rules = WashTradingRules::default();
+        let mut record = MintSaleRecord::default();
+        let mut pair = PairVolume::default();
+        for now in [1_700_000_000, 1_700_000_000, 1_700_000_001] {
+            let suppression = assess_wash_trading(
+                &rules,
+                &mut record,
+                &mut pair,
+                &pubkey(72),
+                &pubkey(73),
+                u64::MAX / 4,
+                now,
+            )
+            .unwrap();
+            assert_eq!(suppression, None);
+        }
+        assert_eq!(pair.window_volume, u64::MAX / 4);
+    }
+
+    #[test]
+    fn trait_offer_proofs_cover_exactly_the_committed_mints() {
+        let mints: Vec<Pubkey> = (100..108).map(pubkey).collect();
+        let leaves: Vec<[u8; 32]> = mints.iter().map(trait_offer_leaf).collect();
+        assert_eq!(leaves[0], hashv(&[mints[0].as_ref()]).to_bytes());
+
+        let level1: Vec<_> = leaves
+            .chunks(2)
+            .map(|pair| merkle_proof::hash_pair(pair[0], pair[1]))
+            .collect();
+        let level2: Vec<_> = level1
+            .chunks(2)
+            .map(|pair| merkle_proof::hash_pair(pair[0], pair[1]))
+            .collect();
+        let root = merkle_proof::hash_pair(level2[0], level2[1]);
 
-#[account]
-pub struct SaleReceipt {
-    pub bump: u8,
-    pub listing: Pubkey,
-    pub buyer: Pubkey,
-    pub seller: Pubkey,
-    pub amount_paid: u64,
-    pub seller_proceeds: u64,
-    pub royalty_paid: u64,
-    pub treasury_paid: u64,
-    pub marketplace_fee_paid: u64,
-    pub developer_paid: u64,
-    pub rewards_minted: u64,
-    pub loyalty_points_awarded: u64,
-    pub timestamp: i64,
-}
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = [
+                leaves[index ^ 1],
+                level1[(index / 2) ^ 1],
+                level2[(index / 4) ^ 1],
+            ];
+            assert!(merkle_proof::verify(&proof, root, *leaf));
+            // A proof for one mint never admits another.
+            assert!(!merkle_proof::verify(&proof, root, leaves[(index + 2) % 8]));
+        }
 
-impl SaleReceipt {
-    pub const LEN: usize = 8 // discriminator
-        + 1 // bump
-        + 32 // listing
-        + 32 // buyer
-        + 32 // seller
-        + 8 // amount paid
-        + 8 // seller proceeds
-        + 8 // royalty paid
-        + 8 // treasury paid
-        + 8 // marketplace fee
-        + 8 // developer paid
-        + 8 // rewards minted
-        + 8 // loyalty points
-        + 8; // timestamp
-}
+        let proof = [leaves[1], level1[1], level2[1]];
+        assert!(!merkle_proof::verify(&proof[..2], root, leaves[0]));
+        assert!(!merkle_proof::verify(
+            &proof,
+            root,
+            trait_offer_leaf(&pubkey(99))
+        ));
+        // Skipping the leaf level of the proof fails.
+        assert!(!merkle_proof::verify(
+            &[level1[1], level2[1]],
+            root,
+            trait_offer_leaf(&mints[0])
+        ));
+        assert!(merkle_proof::verify(&[], leaves[3], leaves[3]));
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum ListingStatus {
-    Active,
-    PendingSettlement,
-    Settled,
-    Cancelled,
-}
+    #[test]
+    fn relayer_paid_rent_returns_to_rent_payer_not_buyer() {
+        let rent = Rent::default().minimum_balance(TraitOffer::LEN);
+        let mut offer = TraitOffer {
+            bump: 255,
+            buyer: pubkey(110),
+            offer_id: 1,
+            collection: pubkey(111),
+            merkle_root: [0; 32],
+            price_per_item: 1_000,
+            quantity: 3,
+            filled: 1,
+            expiration_ts: 1_700_000_000,
+            rent_payer: pubkey(112),
+        };
+        assert_eq!(offer.unfilled_escrow().unwrap(), 2_000);
 
-fn validate_fee_configuration(
-    royalty_bps: u16,
-    treasury_bps: u16,
-    marketplace_fee_bps: u16,
-) -> Result<()> {
-    require!(
-        treasury_bps == OPS_TREASURY_BPS && marketplace_fee_bps == REWARD_POOL_BPS,
-        EscrowError::UnsupportedFeeConfiguration
-    );
-    let total = royalty_bps as u64 + PLATFORM_FEE_BPS as u64;
-    require!(
-        total <= BPS_DENOMINATOR as u64,
-        EscrowError::InvalidFeeConfiguration
-    );
-    Ok(())
-}
+        let program_id = crate::ID;
+        let offer_key = pubkey(113);
+        let mut offer_lamports = rent + 2_000;
+        let mut offer_data = [];
+        let offer_info = AccountInfo::new(
+            &offer_key,
+            false,
+            true,
+            &mut offer_lamports,
+            &mut offer_data,
+            &program_id,
+            false,
+            0,
+        );
+        let mut buyer_lamports = 0;
+        let mut buyer_data = [];
+        let buyer_info = AccountInfo::new(
+            &offer.buyer,
+            false,
+            true,
+            &mut buyer_lamports,
+            &mut buyer_data,
+            &system_program::ID,
+            false,
+            0,
+        );
 
-fn compute_fee(amount: u64, bps: u16) -> Result<u64> {
-    let fee = amount
-        .checked_mul(bps as u64)
-        .ok_or_else(|| error!(EscrowError::MathOverflow))?
-        .checked_div(BPS_DENOMINATOR)
-        .ok_or_else(|| error!(EscrowError::MathOverflow))?;
-    Ok(fee)
-}
+        disburse(&offer_info, &buyer_info, offer.unfilled_escrow().unwrap()).unwrap();
+        assert_eq!(buyer_info.lamports(), 2_000);
+        // Only the rent is left for `close = rent_payer`.
+        assert_eq!(offer_info.lamports(), rent);
 
-fn disburse(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
-    if amount == 0 {
-        return Ok(());
+        offer.filled = 3;
+        assert_eq!(offer.unfilled_escrow().unwrap(), 0);
+        offer.filled = 4;
+        assert_error(
+            offer.unfilled_escrow().map(|_| ()),
+            EscrowError::MathOverflow,
+        );
     }
-    require_keys_eq!(*from.owner, crate::ID, EscrowError::InvalidPayoutAccount);
-    require_keys_eq!(
-        *to.owner,
-        system_program::ID,
-        EscrowError::InvalidPayoutAccount
-    );
-    **from.try_borrow_mut_lamports()? = from
-        .lamports()
-        .checked_sub(amount)
-        .ok_or(EscrowError::MathOverflow)?;
-    **to.try_borrow_mut_lamports()? = to
-        .lamports()
-        .checked_add(amount)
-        .ok_or(EscrowError::MathOverflow)?;
-    Ok(())
-}
 
-#[error_code]
-pub enum EscrowError {
-    #[msg("Listing is not active.")]
-    ListingNotActive,
-    #[msg("Listing must be pending settlement for this operation.")]
-    ListingNotPending,
-    #[msg("Listing price must be greater than zero.")]
-    InvalidListingPrice,
-    #[msg("Total fee configuration exceeds 100%.")]
-    InvalidFeeConfiguration,
-    #[msg("Provided fee split is not supported by the protocol.")]
-    UnsupportedFeeConfiguration,
-    #[msg("Arithmetic overflow encountered.")]
-    MathOverflow,
-    #[msg("No buyer recorded for this listing.")]
-    MissingBuyer,
-    #[msg("Provided buyer account does not match listing state.")]
-    BuyerMismatch,
-    #[msg("Provided seller account does not match listing state.")]
-    SellerMismatch,
-    #[msg("Royalty destination does not match listing configuration.")]
-    RoyaltyDestinationMismatch,
-    #[msg("Escrow vault balance is insufficient.")]
-    InsufficientEscrowBalance,
-    #[msg("Escrow vault still holds funds; cancel not allowed.")]
-    OutstandingEscrowBalance,
-    #[msg("Escrow vault already funded for this listing.")]
-    EscrowAlreadyFunded,
-    #[msg("Loyalty authority does not match registry configuration.")]
-    UnauthorizedLoyaltyAuthority,
-    #[msg("Reward authority does not match vault configuration.")]
-    UnauthorizedRewardAuthority,
-    #[msg("Destination account must be a system account.")]
-    InvalidPayoutAccount,
-    #[msg("The listing has expired.")]
-    ListingExpired,
-    #[msg("Reward mint does not match vault configuration.")]
-    MismatchedRewardMint,
-    #[msg("Developer wallet does not match the configured address.")]
-    InvalidDeveloperWallet,
+    #[test]
+    fn legacy_listings_treat_the_seller_as_rent_payer() {
+        for data in [
+            listing_v0_fixture(),
+            listing_v1_fixture(&[], ListingV1::LEN),
+            listing_v1_fixture(&payout_destinations_fixture(), ListingV1::PAYOUTS_LEN),
+        ] {
+            let listing = decode_for_migration::<Listing>(&data).unwrap().unwrap();
+            assert_eq!(listing.rent_payer, listing.seller);
+        }
+
+        let mut appended = payout_destinations_fixture();
+        appended.extend_from_slice(&[8; 32]); // rent payer
+        let listing = decode_for_migration::<Listing>(&listing_v1_fixture(
+            &appended,
+            ListingV1::RENT_PAYER_LEN,
+        ))
+        .unwrap()
+        .expect("v1 listing should be upgraded");
+        assert_eq!(listing.rent_payer, pubkey(8));
+        assert_eq!(listing.payout_destinations.len(), 2);
+    }
+
+    fn pending_listing(cancel_deadline_ts: Option<i64>) -> Listing {
+        let mut listing = decode_for_migration::<Listing>(&listing_v0_fixture())
+            .unwrap()
+            .expect("v0 listing should be upgraded");
+        listing.buyer_cancel_deadline_ts = cancel_deadline_ts;
+        listing
+    }
+
+    const SALE_TS: i64 = 1_700_000_100;
+    const CANCEL_DEADLINE_TS: i64 = SALE_TS + 600;
+
+    #[test]
+    fn settlement_waits_for_buyer_cancel_window() {
+        let listing = pending_listing(Some(CANCEL_DEADLINE_TS));
+
+        assert_error(
+            ensure_ready_to_settle(&listing, SALE_TS),
+            EscrowError::BuyerCancelWindowOpen,
+        );
+        assert_error(
+            ensure_ready_to_settle(&listing, CANCEL_DEADLINE_TS),
+            EscrowError::BuyerCancelWindowOpen,
+        );
+        assert!(ensure_ready_to_settle(&listing, CANCEL_DEADLINE_TS + 1).is_ok());
+    }
+
+    #[test]
+    fn buyer_cancel_and_settlement_never_both_succeed() {
+        for now in [
+            SALE_TS,
+            CANCEL_DEADLINE_TS - 1,
+            CANCEL_DEADLINE_TS,
+            CANCEL_DEADLINE_TS + 1,
+        ] {
+            let mut listing = pending_listing(Some(CANCEL_DEADLINE_TS));
+            let can_settle = ensure_ready_to_settle(&listing, now).is_ok();
+            let cancelled = revert_pending_purchase(&mut listing, &pubkey(2), now).is_ok();
+            assert!(
+                can_settle != cancelled,
+                "exactly one of cancel or settle must win at {now}"
+            );
+            if cancelled {
+                // Settlement arriving after the cancel finds an active listing.
+                assert_error(
+                    ensure_ready_to_settle(&listing, now + 1_000),
+                    EscrowError::ListingNotPending,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn buyer_cancel_returns_listing_to_active() {
+        let mut listing = pending_listing(Some(CANCEL_DEADLINE_TS));
+        revert_pending_purchase(&mut listing, &pubkey(2), SALE_TS + 10).unwrap();
+
+        assert!(listing.status == ListingStatus::Active);
+        assert_eq!(listing.buyer, None);
+        assert_eq!(listing.sale_ts, None);
+        assert_eq!(listing.buyer_cancel_deadline_ts, None);
+        assert_error(
+            revert_pending_purchase(&mut listing, &pubkey(2), SALE_TS + 20),
+            EscrowError::ListingNotPending,
+        );
+    }
+
+    #[test]
+    fn buyer_cancel_rejects_other_signers_and_closed_windows() {
+        let mut listing = pending_listing(Some(CANCEL_DEADLINE_TS));
+        assert_error(
+            revert_pending_purchase(&mut listing, &pubkey(9), SALE_TS),
+            EscrowError::BuyerMismatch,
+        );
+        assert_error(
+            revert_pending_purchase(&mut listing, &pubkey(2), CANCEL_DEADLINE_TS + 1),
+            EscrowError::BuyerCancelWindowClosed,
+        );
+
+        let mut without_window = pending_listing(None);
+        assert_error(
+            revert_pending_purchase(&mut without_window, &pubkey(2), SALE_TS),
+            EscrowError::BuyerCancelWindowClosed,
+        );
+        assert!(ensure_ready_to_settle(&without_window, SALE_TS).is_ok());
+
+        let mut auction_sale = pending_listing(Some(CANCEL_DEADLINE_TS));
+        auction_sale.sale_mode = SaleMode::SealedBidAuction;
+        assert_error(
+            revert_pending_purchase(&mut auction_sale, &pubkey(2), SALE_TS),
+            EscrowError::PurchaseNotCancellable,
+        );
+    }
+
+    #[test]
+    fn trade_history_keeps_most_recent_trades_newest_first() {
+        let mut history = TradeHistory::default();
+        assert_eq!(history.recent().count(), 0);
+
+        let total = TRADE_HISTORY_CAPACITY as u64 + 5;
+        for sequence in 1..=total {
+            history
+                .record(TradeHistoryEntry {
+                    receipt: pubkey(sequence as u8),
+                    sequence,
+                    side: TradeSide::Buy,
+                })
+                .unwrap();
+        }
+
+        assert_eq!(history.total_trades, total);
+        let sequences: Vec<u64> = history.recent().map(|entry| entry.sequence).collect();
+        let expected: Vec<u64> = (6..=total).rev().collect();
+        assert_eq!(sequences, expected);
+        let mut data = Vec::new();
+        history.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), TradeHistory::LEN);
+    }
+
+    #[test]
+    fn small_price_sale_split_reconciles_to_price() {
+        for price in [1, 7, 399, 1_000, 12_345] {
+            for override_bps in [None, Some(0), Some(500)] {
+                let split = compute_sale_split(price, 250, override_bps).unwrap();
+                assert_eq!(split.escrowed_amount().unwrap(), price);
+            }
+        }
+
+        // 2.5% royalty, 1% developer, 0.375% reward pool and 0.125% ops of
+        // 1,000 lamports: the fractional pool and ops shares round to 4 and 1
+        // instead of both truncating.
+        let split = compute_sale_split(1_000, 250, None).unwrap();
+        assert_eq!(
+            [
+                split.seller_payout,
+                split.royalty_cut,
+                split.developer_cut,
+                split.reward_pool_cut,
+                split.ops_cut,
+            ],
+            [960, 25, 10, 4, 1]
+        );
+    }
+
+    #[test]
+    fn seller_proceeds_split_leaves_nothing_in_escrow() {
+        let destinations = [3_333u16, 3_333, 3_334].map(|share_bps| PayoutDestination {
+            wallet: pubkey(share_bps as u8),
+            share_bps,
+        });
+        let amounts = split_seller_proceeds(&destinations, 10).unwrap();
+        assert_eq!(amounts.iter().sum::<u64>(), 10);
+        assert_eq!(amounts, vec![3, 3, 4]);
+    }
+
+    #[test]
+    fn collection_stats_rolling_average_moves_a_fifth_toward_each_sale() {
+        let mut stats = CollectionStats {
+            collection: pubkey(7),
+            ..Default::default()
+        };
+        stats.record_sale(1_000, 10).unwrap();
+        assert_eq!(stats.rolling_average_price, 1_000);
+
+        stats.record_sale(2_000, 20).unwrap();
+        assert_eq!(stats.rolling_average_price, 1_200);
+        stats.record_sale(200, 30).unwrap();
+        assert_eq!(stats.rolling_average_price, 1_000);
+        // Deltas below five lamports round away and leave the average put.
+        stats.record_sale(1_004, 40).unwrap();
+        assert_eq!(stats.rolling_average_price, 1_000);
+
+        assert_eq!(stats.sale_count, 4);
+        assert_eq!(stats.total_volume, 4_204);
+        assert_eq!(stats.last_sale_price, 1_004);
+        assert_eq!(stats.last_sale_ts, 40);
+    }
+
+    #[test]
+    fn rolling_average_delta_weights_and_rejects_overflow() {
+        assert_eq!(rolling_average_delta(0).unwrap(), 0);
+        assert_eq!(rolling_average_delta(4).unwrap(), 0);
+        assert_eq!(rolling_average_delta(5).unwrap(), 1);
+        assert_eq!(rolling_average_delta(1_000_000_000).unwrap(), 200_000_000);
+        assert!(rolling_average_delta(u64::MAX).is_err());
+
+        let mut stats = CollectionStats {
+            sale_count: 1,
+            rolling_average_price: 1,
+            ..Default::default()
+        };
+        assert!(stats.record_sale(u64::MAX, 0).is_err());
+    }
+
+    #[test]
+    fn mint_without_metadata_settles_outside_any_collection() {
+        let key = pubkey(9);
+        let mut lamports = 0;
+        let mut data = [];
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &system_program::ID,
+            false,
+            0,
+        );
+        assert!(load_optional_metadata(&info).unwrap().is_none());
+    }
+
+    #[test]
+    fn market_config_init_requires_the_upgrade_authority() {
+        let upgrade_authority = pubkey(11);
+        let program_data = ProgramData {
+            slot: 1,
+            upgrade_authority_address: Some(upgrade_authority),
+        };
+        ensure_upgrade_authority(&program_data, &upgrade_authority).unwrap();
+        assert_error(
+            ensure_upgrade_authority(&program_data, &pubkey(12)),
+            EscrowError::UnauthorizedConfigInitializer,
+        );
+
+        let immutable = ProgramData {
+            slot: 1,
+            upgrade_authority_address: None,
+        };
+        assert_error(
+            ensure_upgrade_authority(&immutable, &upgrade_authority),
+            EscrowError::UnauthorizedConfigInitializer,
+        );
+    }
+
+    #[test]
+    fn settle_many_batch_skips_bad_and_duplicate_listings() {
+        let keys = [pubkey(21), pubkey(22), pubkey(21), pubkey(23), pubkey(22)];
+        let mut loaded = Vec::new();
+        let (pending, outcomes) = partition_settlement_batch(keys, |index| {
+            loaded.push(index);
+            if index == 1 {
+                err!(EscrowError::InsufficientEscrowBalance)
+            } else {
+                Ok(index)
+            }
+        });
+
+        // Index 2 repeats an accepted listing and is never loaded; index 4
+        // repeats a rejected one and gets its own attempt.
+        assert_eq!(loaded, vec![0, 1, 3, 4]);
+        assert_eq!(pending, vec![0, 3, 4]);
+        let settled: Vec<_> = outcomes.iter().map(|outcome| outcome.settled).collect();
+        assert_eq!(settled, vec![true, false, false, true, true]);
+        let codes: Vec<_> = outcomes.iter().map(|outcome| outcome.error_code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                0,
+                u32::from(EscrowError::InsufficientEscrowBalance),
+                u32::from(EscrowError::DuplicateListingInBatch),
+                0,
+                0,
+            ]
+        );
+        assert!(outcomes
+            .iter()
+            .zip(keys)
+            .all(|(outcome, key)| outcome.listing == key));
+    }
+
+    #[test]
+    fn settle_many_rejects_records_that_would_fail_after_payout() {
+        let key = pubkey(24);
+        let program_id = crate::ID;
+        let check = |owner: &Pubkey, writable: bool, lamports: u64, data: &mut [u8]| {
+            let mut lamports = lamports;
+            let info =
+                AccountInfo::new(&key, false, writable, &mut lamports, data, owner, false, 0);
+            ensure_record_loadable::<MintSaleRecord>(&info, EscrowError::InvalidWashTradingRecord)
+        };
+
+        let mut record = serialized(&MintSaleRecord::default(), MintSaleRecord::LEN);
+        check(&program_id, true, 1, &mut record).unwrap();
+        check(&system_program::ID, true, 0, &mut []).unwrap();
+
+        let mut wrong_type = serialized(&PairVolume::default(), PairVolume::LEN);
+        for result in [
+            check(&program_id, false, 1, &mut record),
+            check(&program_id, true, 1, &mut wrong_type),
+            check(&system_program::ID, true, 1, &mut []),
+        ] {
+            assert_error(result, EscrowError::InvalidWashTradingRecord);
+        }
+    }
+
+    #[test]
+    fn cancelled_raffle_returns_listing_to_fixed_price_after_last_refund() {
+        let mut listing = decode_for_migration::<Listing>(&listing_v0_fixture())
+            .unwrap()
+            .unwrap();
+        listing.status = ListingStatus::Active;
+        listing.buyer = None;
+        listing.sale_mode = SaleMode::Raffle;
+        let sales_end_ts = 1_700_001_000;
+        let mut raffle = Raffle {
+            bump: 255,
+            listing: pubkey(80),
+            seller: listing.seller,
+            ticket_price: 1_000,
+            min_tickets: 10,
+            sales_end_ts,
+            reveal_deadline_ts: sales_end_ts + RAFFLE_REVEAL_WINDOW_SECS,
+            seed_commitment: [0; 32],
+            tickets_sold: 5,
+            tickets_refunded: 0,
+            page_count: 1,
+            status: RaffleStatus::Open,
+            winning_ticket: None,
+        };
+        let mut entries = [
+            RaffleTicketEntry {
+                buyer: pubkey(81),
+                first_ticket: 0,
+                ticket_count: 3,
+                refunded: false,
+            },
+            RaffleTicketEntry {
+                buyer: pubkey(82),
+                first_ticket: 3,
+                ticket_count: 2,
+                refunded: false,
+            },
+        ];
+
+        assert_error(
+            cancel_raffle_listing(&mut raffle, &mut listing, sales_end_ts),
+            EscrowError::RaffleNotCancellable,
+        );
+        cancel_raffle_listing(&mut raffle, &mut listing, sales_end_ts + 1).unwrap();
+        assert!(raffle.status == RaffleStatus::Cancelled);
+        assert!(listing.sale_mode == SaleMode::Raffle);
+
+        assert_eq!(
+            refund_raffle_entry(&mut raffle, &mut listing, &mut entries[0]).unwrap(),
+            3_000
+        );
+        assert!(listing.sale_mode == SaleMode::Raffle);
+        assert_error(
+            refund_raffle_entry(&mut raffle, &mut listing, &mut entries[0]).map(|_| ()),
+            EscrowError::RaffleTicketsRefunded,
+        );
+        assert_eq!(
+            refund_raffle_entry(&mut raffle, &mut listing, &mut entries[1]).unwrap(),
+            2_000
+        );
+        assert!(listing.sale_mode == SaleMode::FixedPrice);
+        assert!(listing.status == ListingStatus::Active);
+    }
+
+    #[test]
+    fn raffle_without_tickets_relists_on_cancel() {
+        let mut listing = decode_for_migration::<Listing>(&listing_v0_fixture())
+            .unwrap()
+            .unwrap();
+        listing.sale_mode = SaleMode::Raffle;
+        let mut raffle = Raffle {
+            bump: 255,
+            listing: pubkey(83),
+            seller: listing.seller,
+            ticket_price: 1_000,
+            min_tickets: 1,
+            sales_end_ts: 1_700_001_000,
+            reveal_deadline_ts: 1_700_001_000 + RAFFLE_REVEAL_WINDOW_SECS,
+            seed_commitment: [0; 32],
+            tickets_sold: 0,
+            tickets_refunded: 0,
+            page_count: 0,
+            status: RaffleStatus::Open,
+            winning_ticket: None,
+        };
+        cancel_raffle_listing(&mut raffle, &mut listing, 1_700_001_001).unwrap();
+        assert!(listing.sale_mode == SaleMode::FixedPrice);
+        assert_error(
+            cancel_raffle_listing(&mut raffle, &mut listing, 1_700_001_002),
+            EscrowError::RaffleNotOpen,
+        );
+    }
+
+    #[test]
+    fn settle_many_groups_payout_accounts_with_their_listing() {
+        let entry = |payout_account_count| SettleManyEntry {
+            reward_amount: 0,
+            loyalty_bonus_points: 0,
+            payout_account_count,
+        };
+        let entries = [entry(0), entry(3), entry(1)];
+        let fixed = SETTLE_MANY_ACCOUNTS_PER_LISTING;
+        let accounts: Vec<usize> = (0..3 * fixed + 4).collect();
+
+        let groups = split_settlement_accounts(&accounts, &entries).unwrap();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0], &accounts[..fixed]);
+        assert_eq!(groups[1], &accounts[fixed..2 * fixed + 3]);
+        assert_eq!(groups[2], &accounts[2 * fixed + 3..]);
+        assert_eq!(
+            groups[1][fixed..],
+            [2 * fixed, 2 * fixed + 1, 2 * fixed + 2]
+        );
+
+        // Missing payout wallets and unclaimed trailing accounts both reject
+        // the whole batch, since later groups would be misaligned.
+        assert!(split_settlement_accounts(&accounts[..accounts.len() - 1], &entries).is_err());
+        assert!(split_settlement_accounts(&accounts, &entries[..2]).is_err());
+    }
+
+    #[test]
+    fn settle_many_payout_accounts_must_match_destinations_in_order() {
+        let wallets = [pubkey(90), pubkey(91)];
+        let destinations = [
+            PayoutDestination {
+                wallet: wallets[0],
+                share_bps: 6_000,
+            },
+            PayoutDestination {
+                wallet: wallets[1],
+                share_bps: 4_000,
+            },
+        ];
+        let mut lamports = [0u64; 2];
+        let [first_lamports, second_lamports] = &mut lamports;
+        let owner = system_program::ID;
+        let account = |key, lamports, writable| {
+            AccountInfo::new(key, false, writable, lamports, &mut [], &owner, false, 0)
+        };
+        let first = account(&wallets[0], first_lamports, true);
+        let second = account(&wallets[1], second_lamports, true);
+
+        check_payout_accounts(&destinations, &[first.clone(), second.clone()]).unwrap();
+        check_payout_accounts(&[], &[]).unwrap();
+        for payout_accounts in [
+            vec![second.clone(), first.clone()],
+            vec![first.clone()],
+            vec![first.clone(), second.clone(), second.clone()],
+        ] {
+            assert_error(
+                check_payout_accounts(&destinations, &payout_accounts),
+                EscrowError::PayoutAccountsMismatch,
+            );
+        }
+        assert_error(
+            check_payout_accounts(&[], std::slice::from_ref(&first)),
+            EscrowError::PayoutAccountsMismatch,
+        );
+
+        let mut read_only = second.clone();
+        read_only.is_writable = false;
+        assert_error(
+            check_payout_accounts(&destinations, &[first, read_only]),
+            EscrowError::InvalidPayoutAccount,
+        );
+    }
+
+    #[test]
+    fn trait_offer_fills_credit_a_program_owned_revenue_pool() {
+        let staking_program = pubkey(44);
+        let mut config = market_config();
+        let pool_key = config.revenue_pool;
+        let offer_key = pubkey(45);
+        let mut offer_lamports = 10_000;
+        let mut offer_data = [0u8; 8];
+        let offer_info = AccountInfo::new(
+            &offer_key,
+            false,
+            true,
+            &mut offer_lamports,
+            &mut offer_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        let mut pool_lamports = 1_000;
+        let mut pool_data = [0u8; 8];
+        let pool_info = AccountInfo::new(
+            &pool_key,
+            false,
+            true,
+            &mut pool_lamports,
+            &mut pool_data,
+            &staking_program,
+            false,
+            0,
+        );
+        assert!(config.has_revenue_pool());
+
+        // The system-owned-only credit path cannot pay the pool.
+        assert_error(
+            credit_lamports(&pool_info, 250),
+            EscrowError::InvalidPayoutAccount,
+        );
+        debit_lamports(&offer_info, 250).unwrap();
+        credit_reward_pool(&pool_info, 250).unwrap();
+        assert_eq!(offer_info.lamports(), 9_750);
+        assert_eq!(pool_info.lamports(), 1_250);
+
+        // Settlement is rejected until a revenue pool is configured.
+        config.revenue_pool = Pubkey::default();
+        assert!(!config.has_revenue_pool());
+    }
+
+    #[test]
+    fn settle_many_sums_platform_cuts_across_the_batch() {
+        let first = compute_sale_split(1_000_000, 500, None).unwrap();
+        let second = compute_sale_split(3_000_000, 0, Some(100)).unwrap();
+        let total = sum_platform_cuts([first, second]).unwrap();
+        assert_eq!(
+            total.developer_cut,
+            first.developer_cut + second.developer_cut
+        );
+        assert_eq!(
+            total.reward_pool_cut,
+            first.reward_pool_cut + second.reward_pool_cut
+        );
+        assert_eq!(total.ops_cut, first.ops_cut + second.ops_cut);
+        assert_eq!(
+            total.platform_fee().unwrap(),
+            first.platform_fee().unwrap() + second.platform_fee().unwrap()
+        );
+        assert_eq!(total.seller_payout, 0);
+        assert_eq!(total.royalty_cut, 0);
+
+        let huge = SaleSplit {
+            ops_cut: u64::MAX,
+            ..first
+        };
+        assert_error(
+            sum_platform_cuts([first, huge]).map(|_| ()),
+            EscrowError::MathOverflow,
+        );
+    }
+
+    #[test]
+    fn buyer_cancel_deadline_fits_in_the_reserved_bytes() {
+        // Carving the deadline out of the reserved bytes kept v2 listings
+        // the size of v1 listings with a rent payer.
+        assert_eq!(Listing::LEN, ListingV1::RENT_PAYER_LEN);
+
+        let mut appended = payout_destinations_fixture();
+        appended.extend_from_slice(&[8; 32]); // rent payer
+        appended.push(1); // buyer cancel deadline: Some
+        appended.extend_from_slice(&CANCEL_DEADLINE_TS.to_le_bytes());
+        let listing = decode_for_migration::<Listing>(&listing_v1_fixture(
+            &appended,
+            ListingV1::CANCEL_DEADLINE_LEN,
+        ))
+        .unwrap()
+        .expect("v1 listing should be upgraded");
+        assert_eq!(listing.rent_payer, pubkey(8));
+        assert_eq!(listing.buyer_cancel_deadline_ts, Some(CANCEL_DEADLINE_TS));
+
+        let migrated = serialized(&listing, Listing::LEN);
+        let reloaded = Listing::try_deserialize(&mut &migrated[..]).unwrap();
+        assert_eq!(reloaded.buyer_cancel_deadline_ts, Some(CANCEL_DEADLINE_TS));
+        assert!(decode_for_migration::<Listing>(&migrated)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn sale_sequence_fits_in_the_reserved_bytes() {
+        // Carving the sequence out of the reserved bytes kept v2 receipts
+        // the size of v1 receipts with seller payouts.
+        assert_eq!(SaleReceipt::LEN, SaleReceiptV1::PAYOUTS_LEN);
+
+        let mut appended = seller_payouts_fixture();
+        appended.extend_from_slice(&41u64.to_le_bytes()); // sequence
+        let receipt = decode_for_migration::<SaleReceipt>(&sale_receipt_v1_fixture(
+            &appended,
+            SaleReceiptV1::SEQUENCE_LEN,
+        ))
+        .unwrap()
+        .expect("v1 receipt should be upgraded");
+        assert_eq!(receipt.sequence, 41);
+        assert_eq!(receipt.seller_payouts.len(), 2);
+
+        let migrated = serialized(&receipt, SaleReceipt::LEN);
+        let reloaded = SaleReceipt::try_deserialize(&mut &migrated[..]).unwrap();
+        assert_eq!(reloaded.sequence, 41);
+        assert!(decode_for_migration::<SaleReceipt>(&migrated)
+            .unwrap()
+            .is_none());
+    }
+
+    /// Raw bytes of a token metadata account holding `data`, laid out the way
+    /// the metadata program writes it.
+    fn metadata_account(update_authority: Pubkey, mint: Pubkey, data: &DataV2) -> MetadataAccount {
+        let mut bytes = vec![4]; // key: MetadataV1
+        bytes.extend_from_slice(update_authority.as_ref());
+        bytes.extend_from_slice(mint.as_ref());
+        data.name.serialize(&mut bytes).unwrap();
+        data.symbol.serialize(&mut bytes).unwrap();
+        data.uri.serialize(&mut bytes).unwrap();
+        bytes.extend_from_slice(&data.seller_fee_basis_points.to_le_bytes());
+        let creators = data.creators.as_deref().unwrap_or_default();
+        bytes.push(1); // creators: Some
+        bytes.extend_from_slice(&(creators.len() as u32).to_le_bytes());
+        for creator in creators {
+            bytes.extend_from_slice(creator.address.as_ref());
+            bytes.push(creator.verified as u8);
+            bytes.push(creator.share);
+        }
+        bytes.extend_from_slice(&[0, 1]); // primary sale happened, is mutable
+        bytes.extend_from_slice(&[1, 255]); // edition nonce
+        bytes.extend_from_slice(&[1, 0]); // token standard: NonFungible
+        match &data.collection {
+            Some(collection) => {
+                bytes.extend_from_slice(&[1, collection.verified as u8]);
+                bytes.extend_from_slice(collection.key.as_ref());
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(&[0, 0, 0]); // uses, collection details, programmable config
+        MetadataAccount::try_deserialize(&mut &bytes[..]).unwrap()
+    }
+
+    #[test]
+    fn launchpad_editions_pass_the_collection_check_once_verified() {
+        let launch = launchpad::Launch {
+            bump: 255,
+            creator: pubkey(60),
+            launch_id: 1,
+            collection_mint: pubkey(61),
+            name: "Clout Genesis".to_string(),
+            symbol: "CLOUT".to_string(),
+            base_uri: "https://example.com/genesis/".to_string(),
+            seller_fee_bps: 500,
+            max_supply: 1_000,
+            minted: 1,
+            phase_count: 1,
+            creation_ts: SALE_TS,
+        };
+        let launch_key = pubkey(62);
+        let mut data = launchpad::edition_data(&launch, 1);
+
+        let entry_key = Pubkey::find_program_address(
+            &[COLLECTION_ENTRY_SEED, launch.collection_mint.as_ref()],
+            &crate::ID,
+        )
+        .0;
+        let entry = CollectionEntry {
+            bump: 254,
+            collection: launch.collection_mint,
+            status: CollectionStatus::Verified,
+            royalty_policy: RoyaltyPolicy {
+                min_royalty_bps: 0,
+                max_royalty_bps: 1_000,
+                royalty_destination: None,
+            },
+            platform_fee_override_bps: None,
+            last_updated_ts: SALE_TS,
+        };
+        let mut entry_data = Vec::new();
+        entry.try_serialize(&mut entry_data).unwrap();
+        let mut entry_lamports = 1_000_000;
+        let entry_info = AccountInfo::new(
+            &entry_key,
+            false,
+            false,
+            &mut entry_lamports,
+            &mut entry_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        let entry_account = UncheckedAccount::try_from(&entry_info);
+
+        // As created by `create_metadata_accounts_v3`, before verification.
+        let unverified = metadata_account(launch_key, pubkey(63), &data);
+        assert_error(
+            load_collection_entry(&unverified, Some(&entry_account)).map(|_| ()),
+            EscrowError::UnverifiedCollection,
+        );
+
+        // `mint_edition` verifies the collection right after creating it.
+        data.collection.as_mut().unwrap().verified = true;
+        let minted = metadata_account(launch_key, pubkey(63), &data);
+        let resolved = load_collection_entry(&minted, Some(&entry_account))
+            .unwrap()
+            .expect("registered collection resolves");
+        assert_eq!(resolved.collection, launch.collection_mint);
+        assert!(resolved.status == CollectionStatus::Verified);
+    }
 }
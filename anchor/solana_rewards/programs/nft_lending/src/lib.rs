@@ -0,0 +1,820 @@
+#![allow(unexpected_cfgs)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::metadata::{Metadata, MetadataAccount};
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount};
+
+use loyalty_registry::program::LoyaltyRegistry as LoyaltyRegistryProgram;
+use loyalty_registry::{self, LoyaltyProfile, RegistryConfig, PROFILE_SEED, REGISTRY_CONFIG_SEED};
+
+pub const LOAN_OFFER_SEED: &[u8] = b"loan-offer";
+pub const LOAN_SEED: &[u8] = b"loan";
+pub const COLLATERAL_VAULT_SEED: &[u8] = b"collateral";
+const METADATA_SEED: &[u8] = b"metadata";
+const BPS_DENOMINATOR: u64 = 10_000;
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+const MAX_APR_BPS: u16 = 50_000; // 500% APR
+
+declare_id!("FHkZ6Qaszjh4HUg1akJrncfN3vQYNvmprKbFKzdPmzpU");
+
+#[program]
+pub mod nft_lending {
+    use super::*;
+
+    /// Lender posts a loan offer against a verified collection and escrows the
+    /// principal inside the offer PDA until a borrower takes it.
+    pub fn create_loan_offer(
+        ctx: Context<CreateLoanOffer>,
+        offer_id: u64,
+        principal_lamports: u64,
+        apr_bps: u16,
+        duration_secs: i64,
+    ) -> Result<()> {
+        require!(principal_lamports > 0, LendingError::InvalidPrincipal);
+        require!(apr_bps <= MAX_APR_BPS, LendingError::InvalidApr);
+        require!(duration_secs > 0, LendingError::InvalidDuration);
+
+        let offer = &mut ctx.accounts.offer;
+        offer.bump = ctx.bumps.offer;
+        offer.lender = ctx.accounts.lender.key();
+        offer.collection = ctx.accounts.collection_mint.key();
+        offer.offer_id = offer_id;
+        offer.principal_lamports = principal_lamports;
+        offer.apr_bps = apr_bps;
+        offer.duration_secs = duration_secs;
+        offer.creation_ts = Clock::get()?.unix_timestamp;
+        offer.status = OfferStatus::Open;
+        offer.loan = None;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.lender.to_account_info(),
+                    to: ctx.accounts.offer.to_account_info(),
+                },
+            ),
+            principal_lamports,
+        )
+    }
+
+    /// Withdraws an untaken offer, returning the escrowed principal and rent
+    /// to the lender.
+    pub fn cancel_loan_offer(ctx: Context<CancelLoanOffer>) -> Result<()> {
+        require!(
+            ctx.accounts.offer.status == OfferStatus::Open,
+            LendingError::OfferNotOpen
+        );
+        Ok(())
+    }
+
+    /// Borrower escrows an NFT from the offer's collection as collateral and
+    /// receives the offer principal.
+    pub fn take_loan(ctx: Context<TakeLoan>) -> Result<()> {
+        let offer = &mut ctx.accounts.offer;
+        require!(
+            offer.status == OfferStatus::Open,
+            LendingError::OfferNotOpen
+        );
+
+        let collection = ctx
+            .accounts
+            .collateral_metadata
+            .collection
+            .as_ref()
+            .ok_or(LendingError::CollectionNotVerified)?;
+        require!(collection.verified, LendingError::CollectionNotVerified);
+        require_keys_eq!(
+            collection.key,
+            offer.collection,
+            LendingError::CollectionMismatch
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.borrower_collateral.to_account_info(),
+                    to: ctx.accounts.collateral_vault.to_account_info(),
+                    authority: ctx.accounts.borrower.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+        disburse(
+            &offer.to_account_info(),
+            &ctx.accounts.borrower.to_account_info(),
+            offer.principal_lamports,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let loan = &mut ctx.accounts.loan;
+        loan.bump = ctx.bumps.loan;
+        loan.vault_bump = ctx.bumps.collateral_vault;
+        loan.offer = offer.key();
+        loan.lender = offer.lender;
+        loan.borrower = ctx.accounts.borrower.key();
+        loan.collateral_mint = ctx.accounts.collateral_mint.key();
+        loan.principal_lamports = offer.principal_lamports;
+        loan.apr_bps = offer.apr_bps;
+        loan.start_ts = now;
+        loan.due_ts = now
+            .checked_add(offer.duration_secs)
+            .ok_or(LendingError::MathOverflow)?;
+        loan.closed_ts = None;
+        loan.repaid_lamports = 0;
+        loan.status = LoanStatus::Active;
+
+        offer.status = OfferStatus::Taken;
+        offer.loan = Some(loan.key());
+        Ok(())
+    }
+
+    /// Borrower repays principal plus accrued interest before the due date and
+    /// receives the collateral back. Repaid volume is recorded with the
+    /// loyalty registry. The loan account is closed and its rent returned to
+    /// the borrower, so a reopened offer can take a new loan.
+    pub fn repay_loan(ctx: Context<RepayLoan>, loyalty_bonus_points: u64) -> Result<()> {
+        let loan = &ctx.accounts.loan;
+        let now = Clock::get()?.unix_timestamp;
+        let repayment = loan.repayment_due(now)?;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.borrower.to_account_info(),
+                    to: ctx.accounts.lender.to_account_info(),
+                },
+            ),
+            repayment,
+        )?;
+
+        release_collateral(
+            loan,
+            &ctx.accounts.collateral_vault,
+            &ctx.accounts.borrower_collateral.to_account_info(),
+            &ctx.accounts.borrower.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        let loyalty_ctx = CpiContext::new(
+            ctx.accounts.loyalty_program.to_account_info(),
+            loyalty_registry::cpi::accounts::RecordActivity {
+                actor: ctx.accounts.borrower.to_account_info(),
+                profile: ctx.accounts.loyalty_profile.to_account_info(),
+                registry_config: ctx.accounts.loyalty_registry_config.to_account_info(),
+                authority: ctx.accounts.loyalty_authority.to_account_info(),
+            },
+        );
+        loyalty_registry::cpi::record_activity(loyalty_ctx, repayment, loyalty_bonus_points)?;
+
+        let loan = &mut ctx.accounts.loan;
+        loan.repaid_lamports = repayment;
+        loan.closed_ts = Some(now);
+        loan.status = LoanStatus::Repaid;
+        Ok(())
+    }
+
+    /// Lender claims the collateral once the loan is past due without
+    /// repayment. The defaulted principal is recorded as lender activity, and
+    /// the loan account is closed to the lender.
+    pub fn foreclose_loan(ctx: Context<ForecloseLoan>, loyalty_bonus_points: u64) -> Result<()> {
+        let loan = &ctx.accounts.loan;
+        let now = Clock::get()?.unix_timestamp;
+        loan.ensure_foreclosable(now)?;
+
+        release_collateral(
+            loan,
+            &ctx.accounts.collateral_vault,
+            &ctx.accounts.lender_collateral.to_account_info(),
+            &ctx.accounts.lender.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        let loyalty_ctx = CpiContext::new(
+            ctx.accounts.loyalty_program.to_account_info(),
+            loyalty_registry::cpi::accounts::RecordActivity {
+                actor: ctx.accounts.lender.to_account_info(),
+                profile: ctx.accounts.loyalty_profile.to_account_info(),
+                registry_config: ctx.accounts.loyalty_registry_config.to_account_info(),
+                authority: ctx.accounts.loyalty_authority.to_account_info(),
+            },
+        );
+        loyalty_registry::cpi::record_activity(
+            loyalty_ctx,
+            loan.principal_lamports,
+            loyalty_bonus_points,
+        )?;
+
+        let loan = &mut ctx.accounts.loan;
+        loan.closed_ts = Some(now);
+        loan.status = LoanStatus::Foreclosed;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(offer_id: u64)]
+pub struct CreateLoanOffer<'info> {
+    #[account(
+        init,
+        payer = lender,
+        space = LoanOffer::LEN,
+        seeds = [LOAN_OFFER_SEED, lender.key().as_ref(), collection_mint.key().as_ref(), &offer_id.to_le_bytes()],
+        bump
+    )]
+    pub offer: Account<'info, LoanOffer>,
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    pub collection_mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLoanOffer<'info> {
+    #[account(mut, has_one = lender, close = lender)]
+    pub offer: Account<'info, LoanOffer>,
+    #[account(mut)]
+    pub lender: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TakeLoan<'info> {
+    #[account(mut)]
+    pub offer: Account<'info, LoanOffer>,
+    #[account(
+        init,
+        payer = borrower,
+        space = Loan::LEN,
+        seeds = [LOAN_SEED, offer.key().as_ref()],
+        bump
+    )]
+    pub loan: Account<'info, Loan>,
+    #[account(
+        init,
+        payer = borrower,
+        seeds = [COLLATERAL_VAULT_SEED, loan.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = loan
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    #[account(
+        mut,
+        constraint = borrower_collateral.owner == borrower.key(),
+        constraint = borrower_collateral.mint == collateral_mint.key() @ LendingError::CollateralMintMismatch
+    )]
+    pub borrower_collateral: Account<'info, TokenAccount>,
+    #[account(
+        constraint = collateral_mint.decimals == 0 && collateral_mint.supply == 1 @ LendingError::InvalidCollateralMint
+    )]
+    pub collateral_mint: Account<'info, Mint>,
+    #[account(
+        seeds = [METADATA_SEED, metadata_program.key().as_ref(), collateral_mint.key().as_ref()],
+        seeds::program = metadata_program.key(),
+        bump
+    )]
+    pub collateral_metadata: Account<'info, MetadataAccount>,
+    pub token_program: Program<'info, Token>,
+    pub metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RepayLoan<'info> {
+    #[account(
+        mut,
+        has_one = borrower,
+        has_one = lender,
+        has_one = offer,
+        close = borrower
+    )]
+    pub loan: Account<'info, Loan>,
+    #[account(mut, has_one = lender, close = lender)]
+    pub offer: Account<'info, LoanOffer>,
+    #[account(
+        mut,
+        seeds = [COLLATERAL_VAULT_SEED, loan.key().as_ref()],
+        bump = loan.vault_bump
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+    #[account(
+        mut,
+        constraint = borrower_collateral.owner == borrower.key(),
+        constraint = borrower_collateral.mint == loan.collateral_mint @ LendingError::CollateralMintMismatch
+    )]
+    pub borrower_collateral: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, borrower.key().as_ref()],
+        bump = loyalty_profile.bump,
+        seeds::program = loyalty_program.key()
+    )]
+    pub loyalty_profile: Account<'info, LoyaltyProfile>,
+    #[account(
+        mut,
+        seeds = [REGISTRY_CONFIG_SEED],
+        bump = loyalty_registry_config.bump,
+        seeds::program = loyalty_program.key(),
+        constraint = loyalty_registry_config.authority == loyalty_authority.key() @ LendingError::UnauthorizedLoyaltyAuthority
+    )]
+    pub loyalty_registry_config: Account<'info, RegistryConfig>,
+    pub loyalty_authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub loyalty_program: Program<'info, LoyaltyRegistryProgram>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ForecloseLoan<'info> {
+    #[account(mut, has_one = lender, has_one = offer, close = lender)]
+    pub loan: Account<'info, Loan>,
+    #[account(mut, has_one = lender, close = lender)]
+    pub offer: Account<'info, LoanOffer>,
+    #[account(
+        mut,
+        seeds = [COLLATERAL_VAULT_SEED, loan.key().as_ref()],
+        bump = loan.vault_bump
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender: Signer<'info>,
+    #[account(
+        mut,
+        constraint = lender_collateral.owner == lender.key(),
+        constraint = lender_collateral.mint == loan.collateral_mint @ LendingError::CollateralMintMismatch
+    )]
+    pub lender_collateral: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [PROFILE_SEED, lender.key().as_ref()],
+        bump = loyalty_profile.bump,
+        seeds::program = loyalty_program.key()
+    )]
+    pub loyalty_profile: Account<'info, LoyaltyProfile>,
+    #[account(
+        mut,
+        seeds = [REGISTRY_CONFIG_SEED],
+        bump = loyalty_registry_config.bump,
+        seeds::program = loyalty_program.key(),
+        constraint = loyalty_registry_config.authority == loyalty_authority.key() @ LendingError::UnauthorizedLoyaltyAuthority
+    )]
+    pub loyalty_registry_config: Account<'info, RegistryConfig>,
+    pub loyalty_authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub loyalty_program: Program<'info, LoyaltyRegistryProgram>,
+}
+
+#[account]
+pub struct LoanOffer {
+    pub bump: u8,
+    pub lender: Pubkey,
+    pub collection: Pubkey,
+    pub offer_id: u64,
+    pub principal_lamports: u64,
+    pub apr_bps: u16,
+    pub duration_secs: i64,
+    pub creation_ts: i64,
+    pub status: OfferStatus,
+    pub loan: Option<Pubkey>,
+}
+
+impl LoanOffer {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // lender
+        + 32 // collection
+        + 8 // offer id
+        + 8 // principal
+        + 2 // apr bps
+        + 8 // duration
+        + 8 // creation ts
+        + 1 // status enum
+        + 1 + 32; // loan option
+}
+
+#[account]
+pub struct Loan {
+    pub bump: u8,
+    pub vault_bump: u8,
+    pub offer: Pubkey,
+    pub lender: Pubkey,
+    pub borrower: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub principal_lamports: u64,
+    pub apr_bps: u16,
+    pub start_ts: i64,
+    pub due_ts: i64,
+    pub closed_ts: Option<i64>,
+    pub repaid_lamports: u64,
+    pub status: LoanStatus,
+}
+
+impl Loan {
+    pub const LEN: usize = 8 // discriminator
+        + 1 // bump
+        + 1 // vault bump
+        + 32 // offer
+        + 32 // lender
+        + 32 // borrower
+        + 32 // collateral mint
+        + 8 // principal
+        + 2 // apr bps
+        + 8 // start ts
+        + 8 // due ts
+        + 1 + 8 // closed ts option
+        + 8 // repaid lamports
+        + 1; // status enum
+
+    /// Principal plus interest accrued up to `now`, failing once the loan is
+    /// no longer active or has passed its due date.
+    fn repayment_due(&self, now: i64) -> Result<u64> {
+        require!(
+            self.status == LoanStatus::Active,
+            LendingError::LoanNotActive
+        );
+        require!(now <= self.due_ts, LendingError::LoanPastDue);
+
+        let elapsed = now.saturating_sub(self.start_ts).max(0) as u64;
+        let interest = compute_interest(self.principal_lamports, self.apr_bps, elapsed)?;
+        self.principal_lamports
+            .checked_add(interest)
+            .ok_or_else(|| error!(LendingError::MathOverflow))
+    }
+
+    /// Fails unless the loan is still active and strictly past its due date.
+    fn ensure_foreclosable(&self, now: i64) -> Result<()> {
+        require!(
+            self.status == LoanStatus::Active,
+            LendingError::LoanNotActive
+        );
+        require!(now > self.due_ts, LendingError::LoanNotDefaulted);
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum OfferStatus {
+    Open,
+    Taken,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum LoanStatus {
+    Active,
+    Repaid,
+    Foreclosed,
+}
+
+/// Simple interest on `principal` for `elapsed_secs` at `apr_bps`, rounded
+/// down to the lamport.
+fn compute_interest(principal: u64, apr_bps: u16, elapsed_secs: u64) -> Result<u64> {
+    let interest = (principal as u128)
+        .checked_mul(apr_bps as u128)
+        .ok_or(LendingError::MathOverflow)?
+        .checked_mul(elapsed_secs as u128)
+        .ok_or(LendingError::MathOverflow)?
+        .checked_div((BPS_DENOMINATOR as u128) * (SECONDS_PER_YEAR as u128))
+        .ok_or(LendingError::MathOverflow)?;
+    u64::try_from(interest).map_err(|_| error!(LendingError::MathOverflow))
+}
+
+/// Moves the collateral out of the loan vault and closes the vault, returning
+/// its rent to `rent_receiver`.
+fn release_collateral<'info>(
+    loan: &Account<'info, Loan>,
+    collateral_vault: &Account<'info, TokenAccount>,
+    destination: &AccountInfo<'info>,
+    rent_receiver: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let signer_seeds: &[&[u8]] = &[LOAN_SEED, loan.offer.as_ref(), &[loan.bump]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token::Transfer {
+                from: collateral_vault.to_account_info(),
+                to: destination.clone(),
+                authority: loan.to_account_info(),
+            },
+            &[signer_seeds],
+        ),
+        collateral_vault.amount,
+    )?;
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: collateral_vault.to_account_info(),
+            destination: rent_receiver.clone(),
+            authority: loan.to_account_info(),
+        },
+        &[signer_seeds],
+    ))
+}
+
+fn disburse(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    require_keys_eq!(*from.owner, crate::ID, LendingError::InvalidPayoutAccount);
+    require_keys_eq!(
+        *to.owner,
+        system_program::ID,
+        LendingError::InvalidPayoutAccount
+    );
+    **from.try_borrow_mut_lamports()? = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(LendingError::MathOverflow)?;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(LendingError::MathOverflow)?;
+    Ok(())
+}
+
+#[error_code]
+pub enum LendingError {
+    #[msg("Loan principal must be greater than zero.")]
+    InvalidPrincipal,
+    #[msg("APR exceeds the maximum supported rate.")]
+    InvalidApr,
+    #[msg("Loan duration must be greater than zero.")]
+    InvalidDuration,
+    #[msg("Loan offer is not open.")]
+    OfferNotOpen,
+    #[msg("Loan is not active.")]
+    LoanNotActive,
+    #[msg("Loan is past due and can no longer be repaid.")]
+    LoanPastDue,
+    #[msg("Loan has not defaulted yet.")]
+    LoanNotDefaulted,
+    #[msg("Collateral is not part of a verified collection.")]
+    CollectionNotVerified,
+    #[msg("Collateral collection does not match the offer.")]
+    CollectionMismatch,
+    #[msg("Collateral mint must be a non-fungible token.")]
+    InvalidCollateralMint,
+    #[msg("Token account mint does not match the collateral mint.")]
+    CollateralMintMismatch,
+    #[msg("Loyalty authority does not match registry configuration.")]
+    UnauthorizedLoyaltyAuthority,
+    #[msg("Destination account must be a system account.")]
+    InvalidPayoutAccount,
+    #[msg("Arithmetic overflow encountered.")]
+    MathOverflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_utils::assert_error;
+
+    const START_TS: i64 = 1_700_000_000;
+    const DURATION_SECS: i64 = 30 * 24 * 60 * 60;
+
+    fn active_loan(principal_lamports: u64, apr_bps: u16) -> Loan {
+        Loan {
+            bump: 255,
+            vault_bump: 254,
+            offer: Pubkey::new_unique(),
+            lender: Pubkey::new_unique(),
+            borrower: Pubkey::new_unique(),
+            collateral_mint: Pubkey::new_unique(),
+            principal_lamports,
+            apr_bps,
+            start_ts: START_TS,
+            due_ts: START_TS + DURATION_SECS,
+            closed_ts: None,
+            repaid_lamports: 0,
+            status: LoanStatus::Active,
+        }
+    }
+
+    #[test]
+    fn interest_accrues_linearly_and_rounds_down() {
+        let principal = 10_000_000_000;
+        assert_eq!(compute_interest(principal, 1_000, 0).unwrap(), 0);
+        assert_eq!(
+            compute_interest(principal, 1_000, SECONDS_PER_YEAR).unwrap(),
+            1_000_000_000
+        );
+        assert_eq!(
+            compute_interest(principal, 1_000, SECONDS_PER_YEAR / 2).unwrap(),
+            500_000_000
+        );
+        // One second at 1% APR on 1 SOL is ~0.317 lamports and rounds to zero.
+        assert_eq!(compute_interest(1_000_000_000, 100, 1).unwrap(), 0);
+        assert_eq!(compute_interest(principal, 0, SECONDS_PER_YEAR).unwrap(), 0);
+    }
+
+    #[test]
+    fn interest_that_does_not_fit_in_u64_is_rejected() {
+        assert_error(
+            compute_interest(u64::MAX, MAX_APR_BPS, SECONDS_PER_YEAR),
+            LendingError::MathOverflow,
+        );
+    }
+
+    #[test]
+    fn repayment_is_principal_plus_accrued_interest_until_due() {
+        let loan = active_loan(1_000_000_000, 3_650);
+        assert_eq!(loan.repayment_due(START_TS).unwrap(), 1_000_000_000);
+
+        let one_day = 24 * 60 * 60;
+        assert_eq!(
+            loan.repayment_due(START_TS + one_day).unwrap(),
+            1_000_000_000 + 1_000_000
+        );
+        assert_eq!(
+            loan.repayment_due(loan.due_ts).unwrap(),
+            1_000_000_000 + 30_000_000
+        );
+        // A clock behind the loan start never produces negative interest.
+        assert_eq!(loan.repayment_due(START_TS - 60).unwrap(), 1_000_000_000);
+    }
+
+    #[test]
+    fn repayment_rejected_after_due_date_or_once_closed() {
+        let mut loan = active_loan(1_000_000_000, 3_650);
+        assert_error(
+            loan.repayment_due(loan.due_ts + 1),
+            LendingError::LoanPastDue,
+        );
+
+        loan.status = LoanStatus::Repaid;
+        assert_error(loan.repayment_due(START_TS), LendingError::LoanNotActive);
+        loan.status = LoanStatus::Foreclosed;
+        assert_error(loan.repayment_due(START_TS), LendingError::LoanNotActive);
+    }
+
+    #[test]
+    fn foreclosure_only_opens_strictly_after_due_date() {
+        let mut loan = active_loan(1_000_000_000, 3_650);
+        assert_error(
+            loan.ensure_foreclosable(START_TS),
+            LendingError::LoanNotDefaulted,
+        );
+        assert_error(
+            loan.ensure_foreclosable(loan.due_ts),
+            LendingError::LoanNotDefaulted,
+        );
+        loan.ensure_foreclosable(loan.due_ts + 1).unwrap();
+
+        loan.status = LoanStatus::Repaid;
+        assert_error(
+            loan.ensure_foreclosable(loan.due_ts + 1),
+            LendingError::LoanNotActive,
+        );
+    }
+
+    #[test]
+    fn repay_and_foreclose_windows_never_overlap() {
+        let loan = active_loan(5_000_000_000, MAX_APR_BPS);
+        for now in [
+            START_TS,
+            loan.due_ts - 1,
+            loan.due_ts,
+            loan.due_ts + 1,
+            loan.due_ts + 86_400,
+        ] {
+            let can_repay = loan.repayment_due(now).is_ok();
+            let can_foreclose = loan.ensure_foreclosable(now).is_ok();
+            assert!(can_repay != can_foreclose, "exactly one path open at {now}");
+        }
+    }
+
+    /// Program input bytes for one account, laid out the way the runtime
+    /// serializes them: `AccountInfo::realloc`, which Anchor's `close` calls,
+    /// reads the original data length just before the key and writes the new
+    /// length just before the data.
+    struct SerializedAccount {
+        words: Vec<u64>,
+        data_len: usize,
+    }
+
+    impl SerializedAccount {
+        const ORIGINAL_LEN_OFFSET: usize = 4;
+        const KEY_OFFSET: usize = 8;
+        const OWNER_OFFSET: usize = 40;
+        const DATA_LEN_OFFSET: usize = 72;
+        const DATA_OFFSET: usize = 80;
+
+        fn new(key: &Pubkey, owner: &Pubkey, data: &[u8]) -> Self {
+            let mut account = SerializedAccount {
+                words: vec![0; (Self::DATA_OFFSET + data.len()).div_ceil(8)],
+                data_len: data.len(),
+            };
+            let bytes = account.bytes();
+            bytes[Self::ORIGINAL_LEN_OFFSET..Self::KEY_OFFSET]
+                .copy_from_slice(&(data.len() as u32).to_le_bytes());
+            bytes[Self::KEY_OFFSET..Self::OWNER_OFFSET].copy_from_slice(key.as_ref());
+            bytes[Self::OWNER_OFFSET..Self::DATA_LEN_OFFSET].copy_from_slice(owner.as_ref());
+            bytes[Self::DATA_LEN_OFFSET..Self::DATA_OFFSET]
+                .copy_from_slice(&(data.len() as u64).to_le_bytes());
+            bytes[Self::DATA_OFFSET..Self::DATA_OFFSET + data.len()].copy_from_slice(data);
+            account
+        }
+
+        fn bytes(&mut self) -> &mut [u8] {
+            let len = self.words.len() * 8;
+            // SAFETY: the words are plain integers viewed as their bytes.
+            unsafe { std::slice::from_raw_parts_mut(self.words.as_mut_ptr().cast(), len) }
+        }
+
+        fn info<'a>(&'a mut self, lamports: &'a mut u64) -> AccountInfo<'a> {
+            let data_len = self.data_len;
+            let bytes = self.bytes();
+            let (header, data) = bytes.split_at_mut(Self::DATA_OFFSET);
+            // SAFETY: `Pubkey` is a byte array, so any 32 bytes are a valid key.
+            let (key, owner) = unsafe {
+                (
+                    &*header[Self::KEY_OFFSET..].as_ptr().cast::<Pubkey>(),
+                    &*header[Self::OWNER_OFFSET..].as_ptr().cast::<Pubkey>(),
+                )
+            };
+            AccountInfo::new(
+                key,
+                false,
+                true,
+                lamports,
+                &mut data[..data_len],
+                owner,
+                false,
+                0,
+            )
+        }
+    }
+
+    #[test]
+    fn reopened_offer_can_take_a_loan_once_the_previous_one_is_closed() {
+        let lender = Pubkey::new_unique();
+        let collection = Pubkey::new_unique();
+        let offer_address = |offer_id: u64| {
+            Pubkey::find_program_address(
+                &[
+                    LOAN_OFFER_SEED,
+                    lender.as_ref(),
+                    collection.as_ref(),
+                    &offer_id.to_le_bytes(),
+                ],
+                &crate::ID,
+            )
+            .0
+        };
+        // Reopening an offer under the same id recreates it at the same
+        // address, so its next loan lands on the same loan PDA.
+        let offer = offer_address(7);
+        assert_eq!(offer, offer_address(7));
+        let (loan_key, bump) =
+            Pubkey::find_program_address(&[LOAN_SEED, offer.as_ref()], &crate::ID);
+
+        let mut loan = active_loan(1_000_000_000, 3_650);
+        loan.bump = bump;
+        loan.offer = offer;
+        loan.lender = lender;
+        loan.status = LoanStatus::Repaid;
+        let mut data = Vec::new();
+        loan.try_serialize(&mut data).unwrap();
+        data.resize(Loan::LEN, 0);
+
+        let rent = 2_000_000;
+        let mut loan_lamports = rent;
+        let mut serialized = SerializedAccount::new(&loan_key, &crate::ID, &data);
+        let loan_info = serialized.info(&mut loan_lamports);
+        let borrower = loan.borrower;
+        let mut borrower_lamports = 5_000;
+        let mut borrower_data = [];
+        let borrower_info = AccountInfo::new(
+            &borrower,
+            true,
+            true,
+            &mut borrower_lamports,
+            &mut borrower_data,
+            &system_program::ID,
+            false,
+            0,
+        );
+
+        // What `close = borrower` on `RepayLoan::loan` does on exit.
+        let loan_account = Account::<Loan>::try_from(&loan_info).unwrap();
+        loan_account.close(borrower_info.clone()).unwrap();
+
+        assert_eq!(borrower_info.lamports(), 5_000 + rent);
+        // An empty, unfunded system account is what `init` in `TakeLoan`
+        // needs to create the loan for the reopened offer.
+        assert_eq!(loan_info.lamports(), 0);
+        assert_eq!(*loan_info.owner, system_program::ID);
+        assert!(loan_info.data_is_empty());
+    }
+}